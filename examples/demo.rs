@@ -0,0 +1,41 @@
+mod scene;
+
+use std::thread;
+use std::time::Duration;
+
+use prima::platform;
+use prima::renderer::Renderer;
+
+fn main() -> prima::Result<()> {
+	println!("Hello, sailor!");
+
+	let mut window = platform::create_window(scene::WIDTH, scene::HEIGHT)?;
+	let mut renderer = unsafe { Renderer::new(&*window)? };
+	println!("Rendering with {}.", renderer.device_name());
+
+	window.show();
+
+	while window.pump() {
+		// Nothing gets rendered while minimized, no need to spin.
+		let (w, h) = window.client_size();
+		if w == 0 || h == 0 {
+			thread::sleep(Duration::from_millis(16));
+			continue;
+		}
+
+		let result = renderer.frame(&*window, |encoder| {
+			let (w, h) = encoder.size();
+			scene::draw(encoder, w, h)
+		});
+		if let Err(e) = result {
+			eprintln!("Failed to render a frame due to {e}");
+			break;
+		}
+	}
+
+	drop(renderer);
+
+	println!("Kthnx, bye!");
+
+	Ok(())
+}
@@ -0,0 +1,28 @@
+use std::path::Path;
+
+use prima::headless::{self, Headless};
+use prima::raster::Software;
+
+mod scene;
+
+fn main() -> prima::Result<()> {
+	let (w, h) = (scene::WIDTH, scene::HEIGHT);
+
+	let pixels = match Headless::new(w, h) {
+		Ok(mut headless) => {
+			println!("Rendering with {}.", headless.device_name());
+			headless.render(|encoder| scene::draw(encoder, w as f32, h as f32))?
+		},
+		Err(e) => {
+			eprintln!("Failed to initialize Vulkan due to {e}, falling back to the software rasterizer.");
+			let mut software = Software::new(w, h);
+			software.render(|encoder| scene::draw(encoder, w as f32, h as f32))?
+		},
+	};
+
+	let path = Path::new("prima.png");
+	headless::write_png(path, w, h, &pixels)?;
+	println!("Wrote {}", path.display());
+
+	Ok(())
+}
@@ -0,0 +1,17 @@
+use prima::encoder::{Color, CornerRadii, PrimaEncoder, Vertex};
+
+pub const WIDTH:  u32 = 800;
+pub const HEIGHT: u32 = 600;
+
+pub fn draw(encoder: &mut PrimaEncoder, w: f32, h: f32) -> prima::Result<()> {
+	encoder.rect(50.0, 150.0, 200.0, 120.0, Color::rgb(255, 179, 102))?;
+	encoder.rounded_rect(w - 250.0, 150.0, 200.0, 120.0, CornerRadii::all(16.0), Color::rgb(64, 96, 160))?;
+
+	encoder.triangle(
+		Vertex::new(w * 0.5,  h * 0.25, Color::rgb(255, 0, 0)),
+		Vertex::new(w * 0.25, h * 0.75, Color::rgb(0, 255, 0)),
+		Vertex::new(w * 0.75, h * 0.75, Color::rgb(0, 0, 255)),
+	)?;
+
+	Ok(())
+}
@@ -1,8 +1,450 @@
 #version 450
 
-layout(location = 0) in  vec3 frag_color;
+#extension GL_EXT_nonuniform_qualifier : require
+
+layout(binding = 0) readonly buffer Prima {
+	uint prima_data[];
+};
+
+layout(location = 0) in  vec4 frag_color;
+layout(location = 1) flat in uint frag_id;
+layout(location = 2) in vec2 frag_uv;
 layout(location = 0) out vec4 output_color;
 
+const uint PRIMA_ROUNDED_RECT = 2;
+const uint PRIMA_ELLIPSE      = 3;
+const uint PRIMA_ARC          = 4;
+const uint PRIMA_LINE         = 5;
+const uint PRIMA_JOIN         = 6;
+const uint PRIMA_BORDER       = 7;
+const uint PRIMA_SHADOW       = 8;
+const uint PRIMA_IMAGE        = 9;
+const uint PRIMA_NINE_SLICE   = 10;
+const uint PRIMA_GLYPH        = 11;
+const uint PRIMA_SDF_GLYPH    = 12;
+const uint PRIMA_MSDF_GLYPH   = 13;
+
+const uint MAX_TEXTURES = 64;
+
+// Unused slots hold a white texel.
+layout(set = 1, binding = 0) uniform sampler2D textures[MAX_TEXTURES];
+
+const uint PAINT_GRADIENT  = 0x20;
+
+const uint GRADIENT_LINEAR = 0;
+const uint GRADIENT_RADIAL = 1;
+
+const float TAU = 6.28318530718;
+
+const uint LINE_BUTT   = 0;
+const uint LINE_SQUARE = 1;
+const uint LINE_ROUND  = 2;
+
+const uint JOIN_BEVEL  = 1;
+const uint JOIN_ROUND  = 2;
+
+const float MITER_LIMIT = 4.0;
+
+vec4 decode_vec(uint offset) {
+	float x = uintBitsToFloat(prima_data[offset + 0]);
+	float y = uintBitsToFloat(prima_data[offset + 1]);
+	float z = uintBitsToFloat(prima_data[offset + 2]);
+	float w = uintBitsToFloat(prima_data[offset + 3]);
+	return vec4(x, y, z, w);
+}
+
+uint decode_type(uint id) {
+	return (id >> 26) & 0x1F;
+}
+
+bool decode_gradient(uint id) {
+	return ((id >> 26) & PAINT_GRADIENT) != 0;
+}
+
+uint decode_offset(uint id) {
+	return id & 0xFFFFFF;
+}
+
+vec4 decode_color(uint c) {
+	vec4 v = vec4(
+		(c >>  0) & 0xFF,
+		(c >>  8) & 0xFF,
+		(c >> 16) & 0xFF,
+		(c >> 24) & 0xFF
+	);
+	return v / 255.0f;
+}
+
+vec4 premultiply(vec4 c) {
+	return vec4(c.rgb * c.a, c.a);
+}
+
+// Color of the gradient at `at` in the pixel `p`.
+vec4 gradient(uint at, vec2 p) {
+	uint kind = prima_data[at];
+	vec4 g    = decode_vec(at + 1);
+
+	float t;
+	if (kind == GRADIENT_LINEAR) {
+		vec2 ab = g.zw - g.xy;
+		t = dot(p - g.xy, ab) / max(dot(ab, ab), 1e-6);
+	} else if (kind == GRADIENT_RADIAL) {
+		t = length(p - g.xy) / max(g.z, 1e-6);
+	} else {
+		vec2 q = p - g.xy;
+		t = fract((atan(q.y, q.x) - g.z) / TAU);
+	}
+	t = clamp(t, 0.0, 1.0);
+
+	// Stops are sorted, every one takes over from the previous one
+	// once `t` gets past it.
+	uint count = prima_data[at + 5];
+	float prev = uintBitsToFloat(prima_data[at + 6]);
+	vec4 c     = premultiply(decode_color(prima_data[at + 7]));
+	for (uint i = 1; i < count; i++) {
+		float o = uintBitsToFloat(prima_data[at + 6 + i * 2]);
+		vec4 ci = premultiply(decode_color(prima_data[at + 7 + i * 2]));
+		c    = mix(c, ci, clamp((t - prev) / max(o - prev, 1e-6), 0.0, 1.0));
+		prev = o;
+	}
+
+	return c.a > 0.0 ? vec4(c.rgb / c.a, c.a) : vec4(0.0);
+}
+
+// Maps `p` along `length` pixels to a texel along `region` texels,
+// `lo` & `hi` are the insets at the ends.
+float nine_slice_axis(float p, float length, float region, float lo, float hi) {
+	// Insets not fitting shrink evenly.
+	float f = min(length / max(lo + hi, 1e-6), 1.0);
+	if (p < lo * f) {
+		return p / f;
+	} else if (p > length - hi * f) {
+		return region - (length - p) / f;
+	} else {
+		return lo + (p - lo * f) * (region - lo - hi) / max(length - (lo + hi) * f, 1e-6);
+	}
+}
+
+// Texture coordinates of the pixel `p` of the nine-slice at `at`,
+// its texture is `size` texels.
+vec2 nine_slice(uint at, vec2 size, vec2 p) {
+	vec4 r      = decode_vec(at);
+	vec4 uv     = decode_vec(at + 6);
+	vec4 insets = decode_vec(at + 10);
+
+	// The rect the vertex stage has snapped.
+	vec2 lo = floor(r.xy + 0.5);
+	vec2 hi = floor(r.xy + r.zw + 0.5);
+
+	float u = nine_slice_axis(p.x - lo.x, hi.x - lo.x, uv.z * size.x, insets.w, insets.y);
+	float v = nine_slice_axis(p.y - lo.y, hi.y - lo.y, uv.w * size.y, insets.x, insets.z);
+	return uv.xy + vec2(u, v) / size;
+}
+
+float median(float a, float b, float c) {
+	return max(min(a, b), min(max(a, b), c));
+}
+
+// Signed distance in pixels to the outline of the distance field
+// glyph at `at`, positive outside like the shapes.
+float sd_glyph(uint at, uint ptype, vec2 uv) {
+	vec4 r      = decode_vec(at);
+	vec4 region = decode_vec(at + 6);
+	float range = uintBitsToFloat(prima_data[at + 10]);
+
+	uint texture_id = prima_data[at + 5];
+	vec4 texel = textureLod(textures[nonuniformEXT(texture_id)], uv, 0.0);
+	float v    = ptype == PRIMA_MSDF_GLYPH ? median(texel.r, texel.g, texel.b) : texel.a;
+
+	float width = float(textureSize(textures[nonuniformEXT(texture_id)], 0).x);
+	float pixels_per_texel = r.z / (region.z * width);
+	return (0.5 - v) * 2.0 * range * pixels_per_texel;
+}
+
+// Interleaved gradient noise in [0, 1), breaks up gradient banding.
+float dither(vec2 p) {
+	return fract(52.9829189 * fract(dot(p, vec2(0.06711056, 0.00583715))));
+}
+
+// Signed distance from `p` to a box with `b` half-extents centered
+// at the origin, corner radii go as top-left, top-right,
+// bottom-right & bottom-left.
+float sd_rounded_box(vec2 p, vec2 b, vec4 radii) {
+	float r = p.x > 0.0
+		? (p.y > 0.0 ? radii.z : radii.y)
+		: (p.y > 0.0 ? radii.w : radii.x);
+	r = min(r, min(b.x, b.y));
+
+	vec2 q = abs(p) - b + r;
+	return min(max(q.x, q.y), 0.0) + length(max(q, 0.0)) - r;
+}
+
+// Approximate, but exact enough around the edge to anti-alias it.
+float sd_ellipse(vec2 p, vec2 r) {
+	float k0 = length(p / r);
+	float k1 = length(p / (r * r));
+	return k1 > 0.0 ? k0 * (k0 - 1.0) / k1 : -min(r.x, r.y);
+}
+
+// Signed distance from `p` to an arc around the origin with butt
+// ends. `mid` points to the middle of the arc, `end` to its ends,
+// relative to `mid`, as the arc is symmetric. `end` of (-1, 0) is a
+// full ring.
+float sd_arc(vec2 p, float radius, float thickness, vec2 mid, vec2 end) {
+	vec2 q = vec2(dot(p, mid), abs(mid.x * p.y - mid.y * p.x));
+
+	float ring = abs(length(q) - radius) - thickness * 0.5;
+	if (end.x <= -1.0) {
+		return ring;
+	}
+
+	// Positive past the end.
+	float side = end.x * q.y - end.y * q.x;
+	if (side > 0.0) {
+		vec2 a    = end * (radius - thickness * 0.5);
+		vec2 ba   = end * thickness;
+		vec2 pa   = q - a;
+		float h   = clamp(dot(pa, ba) / max(dot(ba, ba), 1e-6), 0.0, 1.0);
+		return length(pa - ba * h);
+	}
+
+	return dot(q, end) > 0.0 ? max(ring, side) : ring;
+}
+
+// Signed distance from `p` to the ring between the edge of the `r`
+// rect & its inside, `widths` go as top, right, bottom & left.
+float sd_border(vec2 p, vec4 r, vec4 widths, vec4 radii) {
+	vec2 b      = r.zw * 0.5;
+	float outer = sd_rounded_box(p - (r.xy + b), b, radii);
+
+	vec2 inner_min = r.xy + widths.wx;
+	vec2 inner_max = r.xy + r.zw - widths.yz;
+	if (any(lessThanEqual(inner_max, inner_min))) {
+		return outer;
+	}
+
+	// Corners get thinner by the wider of the sides they join.
+	vec4 inner_radii = max(radii - max(widths.wxyz, widths.xyzw), 0.0);
+	vec2 ib     = (inner_max - inner_min) * 0.5;
+	float inner = sd_rounded_box(p - (inner_min + ib), ib, inner_radii);
+	return max(outer, -inner);
+}
+
+// Color of the side of the border at `at` that `p` is on, the corners
+// are split along the line from the outer to the inner corner.
+vec4 border_color(uint at, vec2 p) {
+	vec4 r      = decode_vec(at);
+	vec4 widths = decode_vec(at + 5);
+
+	bool left = p.x < r.x + r.z * 0.5;
+	bool top  = p.y < r.y + r.w * 0.5;
+
+	// From the nearest outer corner, inwards.
+	vec2 q = vec2(
+		left ? p.x - r.x : r.x + r.z - p.x,
+		top  ? p.y - r.y : r.y + r.w - p.y
+	);
+	vec2 w = vec2(left ? widths.w : widths.y, top ? widths.x : widths.z);
+
+	vec4 horizontal = decode_color(prima_data[top ? at + 4 : at + 10]);
+	vec4 vertical   = decode_color(prima_data[left ? at + 11 : at + 9]);
+
+	// Positive on the vertical side of the split.
+	float s = (w.x * q.y - w.y * q.x) / max(length(w), 1e-6);
+	vec4 c  = mix(premultiply(horizontal), premultiply(vertical), clamp(0.5 + s, 0.0, 1.0));
+	return c.a > 0.0 ? vec4(c.rgb / c.a, c.a) : vec4(0.0);
+}
+
+float gaussian(float x, float sigma) {
+	return exp(-(x * x) / (2.0 * sigma * sigma)) / (sqrt(TAU) * sigma);
+}
+
+// Abramowitz & Stegun approximation of the error function.
+vec2 erf(vec2 x) {
+	vec2 s = sign(x);
+	vec2 a = abs(x);
+	x  = 1.0 + (0.278393 + (0.230389 + 0.078108 * (a * a)) * a) * a;
+	x *= x;
+	return s - s / (x * x);
+}
+
+// Blurred row `y` of a rounded box with `b` half-extents, the blur
+// along x is exact.
+float shadow_row(float x, float y, float sigma, float radius, vec2 b) {
+	float delta   = min(b.y - radius - abs(y), 0.0);
+	float curved  = b.x - radius + sqrt(max(0.0, radius * radius - delta * delta));
+	vec2 integral = 0.5 + 0.5 * erf((x + vec2(-curved, curved)) * (sqrt(0.5) / sigma));
+	return integral.y - integral.x;
+}
+
+// Coverage of the `r` rect with corners of `radius` blurred by a
+// Gaussian of `sigma` at `p`. Exact along x, along y the rows are
+// sampled.
+float box_shadow(vec2 p, vec4 r, float radius, float sigma) {
+	vec2 b = r.zw * 0.5;
+	p     -= r.xy + b;
+	radius = min(radius, min(b.x, b.y));
+
+	// Too sharp to sample, just a rounded box.
+	if (sigma < 0.1) {
+		return clamp(0.5 - sd_rounded_box(p, b, vec4(radius)), 0.0, 1.0);
+	}
+
+	float low   = p.y - b.y;
+	float high  = p.y + b.y;
+	float start = clamp(-3.0 * sigma, low, high);
+	float end   = clamp(3.0 * sigma, low, high);
+
+	float step  = (end - start) / 4.0;
+	float y     = start + step * 0.5;
+	float value = 0.0;
+	for (int i = 0; i < 4; i++) {
+		value += shadow_row(p.x, p.y - y, sigma, radius, b) * gaussian(y, sigma) * step;
+		y     += step;
+	}
+	return value;
+}
+
+// Adds an end of `kind` to the line distance `dist`, `t` is how far
+// past the end `p` is, `pe` is `p` relative to the end. Clears `keep`
+// past a joint, those pixels belong to the join.
+float line_end(float dist, float t, vec2 pe, float hw, uint kind, inout bool keep) {
+	if (kind == LINE_BUTT) {
+		return max(dist, t);
+	}
+	if (kind == LINE_SQUARE) {
+		return max(dist, t - hw);
+	}
+	if (kind == LINE_ROUND) {
+		return t > 0.0 ? length(pe) - hw : dist;
+	}
+	keep = keep && t <= 0.0;
+	return dist;
+}
+
+// Signed distance from `p` to a line of `hw` half-width from `a` to
+// `b`, `ends` as in PRIMA_LINE.
+float sd_line(vec2 p, vec2 a, vec2 b, float hw, uint ends, out bool keep) {
+	vec2 d = normalize(b - a);
+	vec2 n = vec2(-d.y, d.x);
+
+	float dist = abs(dot(p - a, n)) - hw;
+	keep = true;
+	// Written the same way as in `sd_join`, so that joints are split
+	// exactly.
+	dist = line_end(dist, -dot(p - a, d), p - a, hw, ends & 0xFF, keep);
+	dist = line_end(dist, dot(p - b, d), p - b, hw, (ends >> 8) & 0xFF, keep);
+	return dist;
+}
+
+// Signed distance from `p` to the join at `j` of the lines coming
+// from `prev` & going to `next`. `keep` is set only for pixels past
+// the ends of both lines.
+float sd_join(vec2 p, vec2 j, vec2 prev, vec2 next, float hw, uint kind, out bool keep) {
+	vec2 d0 = normalize(j - prev);
+	vec2 d1 = normalize(next - j);
+	vec2 q  = p - j;
+
+	keep = dot(q, d0) > 0.0 && dot(q, d1) < 0.0;
+
+	if (kind == JOIN_ROUND) {
+		return length(q) - hw;
+	}
+
+	vec2 n0 = vec2(-d0.y, d0.x);
+	vec2 n1 = vec2(-d1.y, d1.x);
+	float dist = max(abs(dot(q, n0)), abs(dot(q, n1))) - hw;
+
+	// |d0 + d1| / 2 is the sine of half of the angle between the lines,
+	// the miter is as long as the width over it.
+	vec2 m = d0 + d1;
+	bool too_long = dot(m, m) * 0.25 * MITER_LIMIT * MITER_LIMIT < 1.0;
+
+	vec2 nb = d0 - d1;
+	if ((kind == JOIN_BEVEL || too_long) && dot(nb, nb) > 1e-12) {
+		nb   = normalize(nb);
+		dist = max(dist, dot(q, nb) - hw * abs(dot(n0, nb)));
+	}
+	return dist;
+}
+
 void main() {
-	output_color = vec4(frag_color, 1.0);
+	uint ptype  = decode_type(frag_id);
+	uint offset = decode_offset(frag_id);
+
+	// Fully covered, unless an anti-aliased shape says otherwise.
+	float d    = -0.5;
+	vec4 color = frag_color;
+
+	if (ptype == PRIMA_ROUNDED_RECT) {
+		vec4 r     = decode_vec(offset);
+		vec4 radii = decode_vec(offset + 5);
+		vec2 b     = r.zw * 0.5;
+		d = sd_rounded_box(gl_FragCoord.xy - (r.xy + b), b, radii);
+	} else if (ptype == PRIMA_ELLIPSE) {
+		vec4 e = decode_vec(offset);
+		d = sd_ellipse(gl_FragCoord.xy - e.xy, e.zw);
+	} else if (ptype == PRIMA_ARC) {
+		vec4 a    = decode_vec(offset);
+		vec4 dirs = decode_vec(offset + 5);
+		d = sd_arc(gl_FragCoord.xy - a.xy, a.z, a.w, dirs.xy, dirs.zw);
+	} else if (ptype == PRIMA_LINE) {
+		vec4 ab  = decode_vec(offset);
+		float hw = uintBitsToFloat(prima_data[offset + 5]) * 0.5;
+		bool keep;
+		d = sd_line(gl_FragCoord.xy, ab.xy, ab.zw, hw, prima_data[offset + 6], keep);
+		if (!keep) {
+			discard;
+		}
+	} else if (ptype == PRIMA_JOIN) {
+		vec4 j    = decode_vec(offset);
+		float hw  = uintBitsToFloat(prima_data[offset + 5]) * 0.5;
+		vec2 next = vec2(
+			uintBitsToFloat(prima_data[offset + 7]),
+			uintBitsToFloat(prima_data[offset + 8])
+		);
+		bool keep;
+		d = sd_join(gl_FragCoord.xy, j.xy, j.zw, next, hw, prima_data[offset + 6], keep);
+		if (!keep) {
+			discard;
+		}
+	} else if (ptype == PRIMA_BORDER) {
+		vec4 r      = decode_vec(offset);
+		vec4 widths = decode_vec(offset + 5);
+		vec4 radii  = decode_vec(offset + 12);
+		d     = sd_border(gl_FragCoord.xy, r, widths, radii);
+		color = border_color(offset, gl_FragCoord.xy);
+	} else if (ptype == PRIMA_SHADOW) {
+		vec4 r       = decode_vec(offset);
+		float radius = uintBitsToFloat(prima_data[offset + 5]);
+		float sigma  = uintBitsToFloat(prima_data[offset + 6]);
+		// Not a distance, the coverage is computed directly.
+		d = 0.5 - box_shadow(gl_FragCoord.xy, r, radius, sigma);
+	} else if (ptype == PRIMA_SDF_GLYPH || ptype == PRIMA_MSDF_GLYPH) {
+		d = sd_glyph(offset, ptype, frag_uv);
+	}
+
+	if (decode_gradient(frag_id)) {
+		color      = gradient(prima_data[offset + 4], gl_FragCoord.xy);
+		color.rgb += (dither(gl_FragCoord.xy) - 0.5) / 255.0;
+	}
+
+	if (ptype == PRIMA_IMAGE || ptype == PRIMA_NINE_SLICE) {
+		uint texture_id = prima_data[offset + 5];
+		vec2 uv         = frag_uv;
+		if (ptype == PRIMA_NINE_SLICE) {
+			vec2 size = vec2(textureSize(textures[nonuniformEXT(texture_id)], 0));
+			uv = nine_slice(offset, size, gl_FragCoord.xy);
+		}
+		// Explicit LOD, the derivatives are undefined in here.
+		color *= textureLod(textures[nonuniformEXT(texture_id)], uv, 0.0);
+	}
+
+	if (ptype == PRIMA_GLYPH) {
+		uint texture_id = prima_data[offset + 5];
+		color.a *= textureLod(textures[nonuniformEXT(texture_id)], frag_uv, 0.0).a;
+	}
+
+	float coverage = clamp(0.5 - d, 0.0, 1.0);
+	output_color   = vec4(color.rgb, color.a * coverage);
 }
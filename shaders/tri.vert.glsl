@@ -6,7 +6,31 @@ layout(binding = 0) readonly buffer Prima {
 	uint prima_data[];
 };
 
-layout(location = 0) out vec3 frag_color;
+layout(location = 0) out vec4 frag_color;
+layout(location = 1) flat out uint frag_id;
+layout(location = 2) out vec2 frag_uv;
+
+const uint PRIMA_TRI          = 0;
+const uint PRIMA_RECT         = 1;
+const uint PRIMA_ROUNDED_RECT = 2;
+const uint PRIMA_ELLIPSE      = 3;
+const uint PRIMA_ARC          = 4;
+const uint PRIMA_LINE         = 5;
+const uint PRIMA_JOIN         = 6;
+const uint PRIMA_BORDER       = 7;
+const uint PRIMA_SHADOW       = 8;
+const uint PRIMA_IMAGE        = 9;
+const uint PRIMA_NINE_SLICE   = 10;
+const uint PRIMA_GLYPH        = 11;
+const uint PRIMA_SDF_GLYPH    = 12;
+const uint PRIMA_MSDF_GLYPH   = 13;
+
+const uint LINE_SQUARE = 1;
+const uint LINE_ROUND  = 2;
+
+const uint JOIN_MITER  = 0;
+
+const float MITER_LIMIT = 4.0;
 
 vec4 decode_vec(uint offset) {
 	float x = uintBitsToFloat(prima_data[offset + 0]);
@@ -25,7 +49,7 @@ mat4 decode_proj() {
 }
 
 uint decode_type(uint id) {
-	return (id >> 26) & 0x3F;
+	return (id >> 26) & 0x1F;
 }
 
 uint decode_corner(uint id) {
@@ -46,6 +70,15 @@ vec4 decode_color(uint c) {
 	return v / 255.0f;
 }
 
+vec2 rect_corner(vec4 r, uint corner) {
+	// @Speed Not sure if this optimizes well, can be rewritten
+	// via some bit-twiddling.
+	return vec2(
+		r.x + ((corner == 2 || corner == 3) ? r.z : 0),
+		r.y + ((corner == 0 || corner == 3) ? r.w : 0)
+	);
+}
+
 void main() {
 	uint id     = gl_VertexIndex;
 
@@ -55,32 +88,88 @@ void main() {
 	uint offset = decode_offset(id);
 
 	vec3 v;
-	vec3 c;
+	vec4 c;
+	vec2 uv = vec2(0.0);
+	// Anti-aliased primitives are placed as is.
+	bool snap = true;
 
 	[[branch]]
-	if (ptype == 0) {
+	if (ptype == PRIMA_TRI) {
 		float vx = uintBitsToFloat(prima_data[offset + 0]);
 		float vy = uintBitsToFloat(prima_data[offset + 1]);
 		// @Idea Use unused corner bits as a mask to encode
 		// color availability to compress single color tris
 		// more.
-		c = decode_color(prima_data[offset + 2]).rgb;
+		c = decode_color(prima_data[offset + 2]);
 		v = vec3(vx, vy, 0.0);
-	} else {
+	} else if (ptype == PRIMA_RECT) {
 		vec4 r = decode_vec(offset);
-
-		// @Speed Not sure if this optimizes well, can be rewritten
-		// via some bit-twiddling.
-		v = vec3(
-			r.x + ((corner == 2 || corner == 3) ? r.z : 0),
-			r.y + ((corner == 0 || corner == 3) ? r.w : 0),
-			0
-		);
-		c = vec3(1.0, 0.7, 0.4);
+		v = vec3(rect_corner(r, corner), 0.0);
+		c = decode_color(prima_data[offset + 4]);
+	} else if (ptype == PRIMA_IMAGE || ptype == PRIMA_NINE_SLICE || ptype == PRIMA_GLYPH
+		|| ptype == PRIMA_SDF_GLYPH || ptype == PRIMA_MSDF_GLYPH) {
+		vec4 r = decode_vec(offset);
+		v  = vec3(rect_corner(r, corner), 0.0);
+		c  = decode_color(prima_data[offset + 4]);
+		uv = rect_corner(decode_vec(offset + 6), corner);
+	} else if (ptype == PRIMA_LINE) {
+		vec4 ab   = decode_vec(offset);
+		float hw  = uintBitsToFloat(prima_data[offset + 5]) * 0.5;
+		uint ends = prima_data[offset + 6];
+
+		vec2 d = normalize(ab.zw - ab.xy);
+		vec2 n = vec2(-d.y, d.x);
+
+		// Caps stick out by half of the width, a pixel of room for
+		// the anti-aliased edges on top.
+		uint start = ends & 0xFF;
+		uint end   = (ends >> 8) & 0xFF;
+		float e0   = (start == LINE_SQUARE || start == LINE_ROUND) ? hw + 1.0 : 1.0;
+		float e1   = (end   == LINE_SQUARE || end   == LINE_ROUND) ? hw + 1.0 : 1.0;
+
+		float u = (corner == 2 || corner == 3) ? length(ab.zw - ab.xy) + e1 : -e0;
+		float s = (corner == 0 || corner == 3) ? hw + 1.0 : -(hw + 1.0);
+		v = vec3(ab.xy + d * u + n * s, 0.0);
+		c = decode_color(prima_data[offset + 4]);
+		snap = false;
+	} else {
+		// Anti-aliased shapes, the fragment stage cuts them out of
+		// their bounds.
+		vec4 r;
+		if (ptype == PRIMA_ROUNDED_RECT || ptype == PRIMA_BORDER) {
+			// @Speed The inside of a border is shaded for nothing.
+			r = decode_vec(offset);
+		} else if (ptype == PRIMA_ELLIPSE) {
+			vec4 e = decode_vec(offset);
+			r = vec4(e.xy - e.zw, e.zw * 2.0);
+		} else if (ptype == PRIMA_SHADOW) {
+			// Past 3 sigmas the blur is all but gone.
+			float blur = uintBitsToFloat(prima_data[offset + 6]) * 3.0;
+			r = decode_vec(offset) + vec4(-blur, -blur, blur * 2.0, blur * 2.0);
+		} else if (ptype == PRIMA_JOIN) {
+			vec4 j   = decode_vec(offset);
+			float hw = uintBitsToFloat(prima_data[offset + 5]) * 0.5;
+			float e  = prima_data[offset + 6] == JOIN_MITER ? hw * MITER_LIMIT : hw;
+			r = vec4(j.xy - e, vec2(e * 2.0));
+		} else {
+			vec4 a  = decode_vec(offset);
+			float e = a.z + a.w * 0.5;
+			r = vec4(a.xy - e, vec2(e * 2.0));
+		}
+
+		// A pixel of room for the anti-aliased edges.
+		r += vec4(-1.0, -1.0, 2.0, 2.0);
+		v = vec3(rect_corner(r, corner), 0.0);
+		c = decode_color(prima_data[offset + 4]);
+		snap = false;
 	}
 
-	v.xy = floor(v.xy + 0.5f);
+	if (snap) {
+		v.xy = floor(v.xy + 0.5f);
+	}
 	gl_Position = proj * vec4(v.xy, 0.0, 1.0);
 
 	frag_color  = c;
+	frag_id     = id;
+	frag_uv     = uv;
 }
@@ -0,0 +1,418 @@
+//! Glyphs rasterized on demand into an atlas of fixed size.
+//!
+//! A [`GlyphCache`] rasterizes glyphs of its [`Face`]s the first time
+//! they are drawn, at the size & subpixel offset they are drawn at, so
+//! text stays crisp without an atlas per font & size. Glyphs go into
+//! shelves, when the atlas runs out of room the shelf used the longest
+//! ago is emptied for the new ones.
+//!
+//! Only the shelves changed since the last [`GlyphCache::flush`] are
+//! uploaded. Per frame: [`GlyphCache::next_frame`], [`GlyphCache::quads`]
+//! for the text, [`GlyphCache::flush`] into a single `update_texture` of
+//! the renderer, then [`crate::encoder::PrimaEncoder::glyphs`] in the
+//! frame.
+
+use std::collections::HashMap;
+
+use crate::font::{Face, FontError, GlyphId};
+use crate::layout::TextLayout;
+use crate::text::GlyphQuad;
+use crate::texture::TextureUpdate;
+
+/// Horizontal positions a glyph is rasterized at within a pixel.
+pub const SUBPIXEL_STEPS: u8 = 4;
+
+/// Empty texels kept around every glyph, so bilinear sampling never
+/// bleeds into the neighbours.
+const PADDING: u32 = 1;
+
+/// Shelf heights are rounded up to it, so glyphs of about the same
+/// height share them.
+const SHELF_STEP: u32 = 4;
+
+/// Face added to a [`GlyphCache`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FaceId(u32);
+
+/// What a glyph is rasterized for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GlyphKey {
+	pub face:     FaceId,
+	/// Bits of the pixels per em.
+	pub size:     u32,
+	pub glyph:    GlyphId,
+	/// Right of the pixel by this many [`SUBPIXEL_STEPS`].
+	pub subpixel: u8,
+}
+
+/// Glyph in the atlas of a [`GlyphCache`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CachedGlyph {
+	/// x, y, w & h in atlas texels, empty for blank glyphs.
+	pub rect:   [u32; 4],
+	/// From the pen on the baseline, rounded down to the pixel, to the
+	/// top-left corner of the bitmap, y goes down. Whole pixels.
+	pub offset: [f32; 2],
+}
+
+struct Entry {
+	glyph: CachedGlyph,
+	// Blank glyphs take no shelf.
+	shelf: Option<usize>,
+}
+
+struct Shelf {
+	y:         u32,
+	height:    u32,
+	// Start of the free space.
+	x:         u32,
+	// Frame a glyph on it has last been drawn in.
+	last_used: u64,
+	// Columns changed since the last flush, the whole height of the
+	// shelf is uploaded.
+	dirty:     Option<[u32; 2]>,
+}
+
+/// Glyphs of any face, size & subpixel offset, packed into an RGBA8
+/// atlas with the coverage in alpha.
+pub struct GlyphCache {
+	width:   u32,
+	height:  u32,
+	pixels:  Vec<u8>,
+	faces:   Vec<Face>,
+	entries: HashMap<GlyphKey, Entry>,
+	shelves: Vec<Shelf>,
+	frame:   u64,
+}
+
+impl GlyphCache {
+	/// Empty atlas of `width` x `height` texels, upload
+	/// [`Self::pixels`] as its texture.
+	pub fn new(width: u32, height: u32) -> Self {
+		Self {
+			width,
+			height,
+			pixels:  vec![0; width as usize * height as usize * 4],
+			faces:   Vec::new(),
+			entries: HashMap::new(),
+			shelves: Vec::new(),
+			frame:   0,
+		}
+	}
+
+	pub fn add_face(&mut self, face: Face) -> FaceId {
+		self.faces.push(face);
+		FaceId(self.faces.len() as u32 - 1)
+	}
+
+	pub fn face(&self, id: FaceId) -> &Face {
+		&self.faces[id.0 as usize]
+	}
+
+	/// Size of the atlas in texels.
+	pub fn size(&self) -> (u32, u32) {
+		(self.width, self.height)
+	}
+
+	/// Tightly packed RGBA8 texels of the atlas, top row first.
+	pub fn pixels(&self) -> &[u8] {
+		&self.pixels
+	}
+
+	/// Starts a frame. Glyphs drawn in the current one are never
+	/// evicted, their quads would sample someone else's texels.
+	pub fn next_frame(&mut self) {
+		self.frame += 1;
+	}
+
+	/// `glyph` of `face` at `size` pixels per em, `subpixel` steps
+	/// right of the pixel, up to [`SUBPIXEL_STEPS`] - 1, rasterized if
+	/// it isn't cached yet. `None` if the atlas has no room left this
+	/// frame.
+	pub fn glyph(&mut self, face: FaceId, size: f32, glyph: GlyphId, subpixel: u8) -> Result<Option<CachedGlyph>, FontError> {
+		let subpixel = subpixel.min(SUBPIXEL_STEPS - 1);
+		let key      = GlyphKey { face, size: size.to_bits(), glyph, subpixel };
+		if let Some(entry) = self.entries.get(&key) {
+			if let Some(shelf) = entry.shelf {
+				self.shelves[shelf].last_used = self.frame;
+			}
+			return Ok(Some(entry.glyph));
+		}
+
+		let shift  = subpixel as f32 / SUBPIXEL_STEPS as f32;
+		let bitmap = self.faces[face.0 as usize].rasterize_shifted(glyph, size, shift)?;
+		if bitmap.coverage.is_empty() {
+			let glyph = CachedGlyph { rect: [0; 4], offset: bitmap.offset };
+			self.entries.insert(key, Entry { glyph, shelf: None });
+			return Ok(Some(glyph));
+		}
+
+		let Some((shelf, x, y)) = self.allocate(bitmap.width, bitmap.height) else {
+			return Ok(None);
+		};
+		for (row, line) in bitmap.coverage.chunks_exact(bitmap.width as usize).enumerate() {
+			let at = ((y as usize + row) * self.width as usize + x as usize) * 4;
+			for (texel, a) in self.pixels[at..at + line.len() * 4].chunks_exact_mut(4).zip(line) {
+				texel.copy_from_slice(&[255, 255, 255, *a]);
+			}
+		}
+
+		let glyph = CachedGlyph { rect: [x, y, bitmap.width, bitmap.height], offset: bitmap.offset };
+		self.entries.insert(key, Entry { glyph, shelf: Some(shelf) });
+		Ok(Some(glyph))
+	}
+
+	/// Quads of the glyphs of `layout` with its top-left corner at
+	/// `pos`, `face` is the one it has been laid out with. Pens are
+	/// snapped to a subpixel step horizontally & a pixel vertically,
+	/// glyphs are drawn 1:1. Those the atlas has no room for are left
+	/// out.
+	pub fn quads(&mut self, face: FaceId, pos: [f32; 2], layout: &TextLayout) -> Result<Vec<GlyphQuad>, FontError> {
+		let atlas = [self.width as f32, self.height as f32];
+		let steps = SUBPIXEL_STEPS as i32;
+
+		let mut quads = Vec::with_capacity(layout.glyphs.len());
+		for positioned in &layout.glyphs {
+			let Some(glyph) = self.face(face).glyph_index(positioned.c) else {
+				continue;
+			};
+
+			let x = ((pos[0] + positioned.pos[0]) * steps as f32).round() as i32;
+			let y = (pos[1] + positioned.pos[1]).round();
+			let Some(cached) = self.glyph(face, layout.size, glyph, x.rem_euclid(steps) as u8)? else {
+				continue;
+			};
+
+			let [u, v, w, h] = cached.rect.map(|v| v as f32);
+			if w > 0.0 && h > 0.0 {
+				quads.push(GlyphQuad {
+					rect: [x.div_euclid(steps) as f32 + cached.offset[0], y + cached.offset[1], w, h],
+					uv:   [u / atlas[0], v / atlas[1], w / atlas[0], h / atlas[1]],
+				});
+			}
+		}
+
+		Ok(quads)
+	}
+
+	/// Texels changed since the last flush, an update per dirty shelf.
+	pub fn flush(&mut self) -> Vec<TextureUpdate> {
+		let mut updates = Vec::new();
+		for shelf in &mut self.shelves {
+			let Some([x0, x1]) = shelf.dirty else {
+				continue;
+			};
+
+			let rect = [x0, shelf.y, x1 - x0, shelf.height];
+			let mut texels = Vec::with_capacity(rect[2] as usize * rect[3] as usize * 4);
+			for row in shelf.y..shelf.y + shelf.height {
+				let at = (row as usize * self.width as usize + x0 as usize) * 4;
+				texels.extend_from_slice(&self.pixels[at..at + rect[2] as usize * 4]);
+			}
+
+			updates.push(TextureUpdate { rect, pixels: texels });
+			shelf.dirty = None;
+		}
+
+		updates
+	}
+
+	/// Free spot of `width` x `height` texels, returns its shelf & the
+	/// top-left corner. Takes the snuggest shelf with room, then a new
+	/// one, then empties the snuggest one unused this frame, the one
+	/// used the longest ago among those.
+	fn allocate(&mut self, width: u32, height: u32) -> Option<(usize, u32, u32)> {
+		let w = width  + PADDING * 2;
+		let h = height + PADDING * 2;
+		if w > self.width {
+			return None;
+		}
+
+		// At most half of a shelf goes to waste.
+		let snug = |s: &Shelf| s.height >= h && s.height <= h * 2;
+
+		let fitting = self.shelves
+			.iter()
+			.enumerate()
+			.filter(|(_, s)| snug(s) && s.x + w <= self.width)
+			.min_by_key(|(_, s)| s.height)
+			.map(|(i, _)| i);
+
+		let shelf = match fitting {
+			Some(i) => i,
+			None => {
+				let top    = self.shelves.last().map_or(0, |s| s.y + s.height);
+				let height = h.div_ceil(SHELF_STEP) * SHELF_STEP;
+				if top + height <= self.height {
+					self.shelves.push(Shelf { y: top, height, x: 0, last_used: self.frame, dirty: None });
+					self.shelves.len() - 1
+				} else {
+					let frame = self.frame;
+					let (i, _) = self.shelves
+						.iter()
+						.enumerate()
+						.filter(|(_, s)| snug(s) && s.last_used < frame)
+						.min_by_key(|(_, s)| (s.height, s.last_used))?;
+					self.evict(i);
+					i
+				}
+			},
+		};
+
+		let s = &mut self.shelves[shelf];
+		let x = s.x;
+		s.x += w;
+		s.last_used = self.frame;
+		s.dirty = Some(s.dirty.map_or([x, s.x], |[x0, _]| [x0, s.x]));
+
+		Some((shelf, x + PADDING, s.y + PADDING))
+	}
+
+	/// Drops the glyphs of the `i`-th shelf & clears its texels.
+	fn evict(&mut self, i: usize) {
+		self.entries.retain(|_, entry| entry.shelf != Some(i));
+
+		let shelf = &mut self.shelves[i];
+		let rows  = shelf.y as usize..(shelf.y + shelf.height) as usize;
+		let row   = self.width as usize * 4;
+		self.pixels[rows.start * row..rows.end * row].fill(0);
+
+		shelf.x     = 0;
+		shelf.dirty = Some([0, self.width]);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::font::dejavu;
+	use crate::layout::Layout;
+
+	fn cache(width: u32, height: u32) -> (GlyphCache, FaceId) {
+		let mut cache = GlyphCache::new(width, height);
+		let face = cache.add_face(dejavu());
+		(cache, face)
+	}
+
+	#[test]
+	fn caches_glyphs_per_subpixel_offset() {
+		let (mut cache, face) = cache(64, 64);
+		let o = cache.face(face).glyph_index('o').unwrap();
+
+		let first = cache.glyph(face, 12.0, o, 0).unwrap().unwrap();
+		assert_eq!(cache.glyph(face, 12.0, o, 0).unwrap(), Some(first));
+		// Other offsets & sizes are rasterized apart.
+		let shifted = cache.glyph(face, 12.0, o, 2).unwrap().unwrap();
+		assert_ne!(shifted.rect, first.rect);
+		let larger = cache.glyph(face, 13.0, o, 0).unwrap().unwrap();
+		assert_ne!(larger.rect, first.rect);
+		assert_eq!(cache.entries.len(), 3);
+		// Steps past the pixel are the last one.
+		let last = cache.glyph(face, 12.0, o, SUBPIXEL_STEPS - 1).unwrap();
+		assert_eq!(cache.glyph(face, 12.0, o, 200).unwrap(), last);
+		assert_eq!(cache.entries.len(), 4);
+
+		let [x, y, ..] = first.rect;
+		assert_eq!(cache.pixels[((y * 64 + x) * 4) as usize..][..3], [255, 255, 255]);
+	}
+
+	#[test]
+	fn snaps_pens_to_subpixel_steps() {
+		let (mut cache, face) = cache(64, 64);
+		let layout = Layout::new(12.0).layout("l l", cache.face(face));
+
+		let quads = cache.quads(face, [0.0, 0.0], &layout).unwrap();
+		// The space is blank, both l's are drawn on whole pixels.
+		assert_eq!(quads.len(), 2);
+		for quad in &quads {
+			assert_eq!(quad.rect.map(f32::fract), [0.0; 4]);
+		}
+
+		// A whole pixel over draws the same glyphs, a quarter over
+		// other ones.
+		let cached = cache.entries.len();
+		let moved  = cache.quads(face, [1.0, 0.0], &layout).unwrap();
+		assert_eq!(cache.entries.len(), cached);
+		assert_eq!(moved[0].rect[0], quads[0].rect[0] + 1.0);
+		assert_eq!(moved[0].uv, quads[0].uv);
+		cache.quads(face, [0.25, 0.0], &layout).unwrap();
+		assert_eq!(cache.entries.len(), cached + 2);
+	}
+
+	#[test]
+	fn evicts_the_least_recently_used_shelf() {
+		let mut cache = GlyphCache::new(16, 16);
+
+		// Four full width shelves, 4 texels high.
+		assert_eq!(cache.allocate(14, 2), Some((0, 1, 1)));
+		cache.next_frame();
+		assert_eq!(cache.allocate(14, 2), Some((1, 1, 5)));
+		cache.next_frame();
+		assert_eq!(cache.allocate(14, 2), Some((2, 1, 9)));
+		assert_eq!(cache.allocate(14, 2), Some((3, 1, 13)));
+		// Too tall for any shelf.
+		assert_eq!(cache.allocate(1, 3), None);
+
+		cache.next_frame();
+		cache.pixels[(5 * 16 + 1) * 4] = 255;
+		cache.entries.insert(
+			GlyphKey { face: FaceId(0), size: 0, glyph: GlyphId(1), subpixel: 0 },
+			Entry { glyph: CachedGlyph::default(), shelf: Some(1) },
+		);
+		assert_eq!(cache.allocate(14, 2), Some((0, 1, 1)));
+		assert_eq!(cache.allocate(14, 2), Some((1, 1, 5)));
+		// Its glyphs & texels are gone.
+		assert!(cache.entries.is_empty());
+		assert_eq!(cache.pixels[(5 * 16 + 1) * 4], 0);
+		assert_eq!(cache.allocate(14, 2), Some((2, 1, 9)));
+		assert_eq!(cache.allocate(14, 2), Some((3, 1, 13)));
+		// Everything is in use this frame.
+		assert_eq!(cache.allocate(14, 2), None);
+	}
+
+	#[test]
+	fn evicts_snug_shelves_only() {
+		let mut cache = GlyphCache::new(16, 24);
+
+		// A tall shelf & two short ones.
+		assert_eq!(cache.allocate(14, 14), Some((0, 1, 1)));
+		assert_eq!(cache.allocate(14, 2), Some((1, 1, 17)));
+		assert_eq!(cache.allocate(14, 2), Some((2, 1, 21)));
+
+		cache.next_frame();
+		assert_eq!(cache.allocate(14, 2), Some((1, 1, 17)));
+		assert_eq!(cache.allocate(14, 2), Some((2, 1, 21)));
+		// The tall one would be mostly wasted.
+		assert_eq!(cache.allocate(14, 2), None);
+		assert_eq!(cache.allocate(14, 12), Some((0, 1, 1)));
+
+		// Snugger beats older.
+		let mut cache = GlyphCache::new(16, 12);
+		assert_eq!(cache.allocate(14, 6), Some((0, 1, 1)));
+		cache.next_frame();
+		assert_eq!(cache.allocate(14, 2), Some((1, 1, 9)));
+		cache.next_frame();
+		assert_eq!(cache.allocate(14, 2), Some((1, 1, 9)));
+	}
+
+	#[test]
+	fn flushes_the_dirty_regions() {
+		let mut cache = GlyphCache::new(16, 16);
+		cache.allocate(2, 2);
+		cache.allocate(3, 2);
+		cache.allocate(2, 6);
+
+		let updates = cache.flush();
+		for update in &updates {
+			assert_eq!(update.pixels.len(), (update.rect[2] * update.rect[3] * 4) as usize);
+		}
+		// The columns taken on each shelf, all of its rows.
+		let rects: Vec<_> = updates.iter().map(|update| update.rect).collect();
+		assert_eq!(rects, [[0, 0, 9, 4], [0, 4, 4, 8]]);
+
+		cache.allocate(2, 2);
+		let rects: Vec<_> = cache.flush().iter().map(|update| update.rect).collect();
+		assert_eq!(rects, [[9, 0, 4, 4]]);
+		assert!(cache.flush().is_empty());
+	}
+}
@@ -0,0 +1,1457 @@
+//! Safe writer for the per-frame prima & index buffers.
+
+use std::error::Error;
+use std::f32::consts::PI;
+use std::fmt;
+
+use crate::index::{
+	make_index,
+	GRADIENT_CONIC,
+	GRADIENT_LINEAR,
+	GRADIENT_RADIAL,
+	JOIN_BEVEL,
+	JOIN_MITER,
+	JOIN_ROUND,
+	LINE_BUTT,
+	LINE_JOINT,
+	LINE_ROUND,
+	LINE_SQUARE,
+	MAX_OFFSET,
+	PAINT_GRADIENT,
+	PRIMA_ARC,
+	PRIMA_BORDER,
+	PRIMA_ELLIPSE,
+	PRIMA_GLYPH,
+	PRIMA_IMAGE,
+	PRIMA_JOIN,
+	PRIMA_LINE,
+	PRIMA_MSDF_GLYPH,
+	PRIMA_NINE_SLICE,
+	PRIMA_RECT,
+	PRIMA_ROUNDED_RECT,
+	PRIMA_SDF_GLYPH,
+	PRIMA_SHADOW,
+	PRIMA_TRI,
+};
+use crate::layout::{Layout, TextLayout};
+use crate::text::{Font, GlyphFormat, GlyphQuad};
+use crate::texture::TextureId;
+
+/// Packed RGBA8 color, red lives in the lowest byte.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Color(pub u32);
+
+impl Color {
+	pub const WHITE: Color = Color::rgba(255, 255, 255, 255);
+	pub const BLACK: Color = Color::rgba(0, 0, 0, 255);
+
+	pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+		Self(u32::from_le_bytes([r, g, b, a]))
+	}
+
+	pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+		Self::rgba(r, g, b, 255)
+	}
+}
+
+/// Solid color or a gradient written by [`PrimaEncoder::gradient`].
+///
+/// Gradients live in the frame they were written into, don't use
+/// them in another one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Paint {
+	// Color or the offset of the gradient.
+	word:     u32,
+	gradient: bool,
+}
+
+impl Paint {
+	/// Bits OR-ed into the primitive type.
+	fn flags(self) -> u32 {
+		if self.gradient { PAINT_GRADIENT } else { 0 }
+	}
+}
+
+impl From<Color> for Paint {
+	fn from(color: Color) -> Self {
+		Self { word: color.0, gradient: false }
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorStop {
+	/// Position along the gradient, from 0 to 1.
+	pub offset: f32,
+	pub color:  Color,
+}
+
+impl ColorStop {
+	pub const fn new(offset: f32, color: Color) -> Self {
+		Self { offset, color }
+	}
+}
+
+/// Color ramp over the frame, `stops` go in increasing offsets.
+/// Colors before the first stop & after the last one are padded.
+#[derive(Clone, Copy, Debug)]
+pub enum Gradient<'a> {
+	Linear {
+		start: [f32; 2],
+		end:   [f32; 2],
+		stops: &'a [ColorStop],
+	},
+	Radial {
+		center: [f32; 2],
+		radius: f32,
+		stops:  &'a [ColorStop],
+	},
+	/// Sweeps clockwise on the screen around `center`, from `angle`
+	/// (in radians) all the way around.
+	Conic {
+		center: [f32; 2],
+		angle:  f32,
+		stops:  &'a [ColorStop],
+	},
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Vertex {
+	pub x:     f32,
+	pub y:     f32,
+	pub color: Color,
+}
+
+impl Vertex {
+	pub const fn new(x: f32, y: f32, color: Color) -> Self {
+		Self { x, y, color }
+	}
+}
+
+/// Per-corner radii in pixels, clamped to half of the shorter side
+/// when drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CornerRadii {
+	pub top_left:     f32,
+	pub top_right:    f32,
+	pub bottom_right: f32,
+	pub bottom_left:  f32,
+}
+
+impl CornerRadii {
+	pub const fn new(top_left: f32, top_right: f32, bottom_right: f32, bottom_left: f32) -> Self {
+		Self { top_left, top_right, bottom_right, bottom_left }
+	}
+
+	pub const fn all(r: f32) -> Self {
+		Self::new(r, r, r, r)
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BorderSide {
+	pub width: f32,
+	pub color: Color,
+}
+
+impl BorderSide {
+	pub const fn new(width: f32, color: Color) -> Self {
+		Self { width, color }
+	}
+}
+
+/// CSS-like box border, drawn inside of its rect.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Border {
+	pub top:    BorderSide,
+	pub right:  BorderSide,
+	pub bottom: BorderSide,
+	pub left:   BorderSide,
+}
+
+impl Border {
+	pub const fn new(top: BorderSide, right: BorderSide, bottom: BorderSide, left: BorderSide) -> Self {
+		Self { top, right, bottom, left }
+	}
+
+	pub const fn all(width: f32, color: Color) -> Self {
+		let side = BorderSide::new(width, color);
+		Self::new(side, side, side, side)
+	}
+}
+
+/// Blurred shadow of a rounded rect, CSS `box-shadow` style.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Shadow {
+	/// Standard deviation of the Gaussian blur, in pixels.
+	pub sigma:  f32,
+	pub color:  Color,
+	pub offset: [f32; 2],
+	/// Grows the rect on every side before blurring, shrinks it if
+	/// negative.
+	pub spread: f32,
+	pub radius: f32,
+}
+
+impl Shadow {
+	pub const fn new(sigma: f32, color: Color) -> Self {
+		Self {
+			sigma,
+			color,
+			offset: [0.0, 0.0],
+			spread: 0.0,
+			radius: 0.0,
+		}
+	}
+
+	pub const fn with_offset(mut self, x: f32, y: f32) -> Self {
+		self.offset = [x, y];
+		self
+	}
+
+	pub const fn with_spread(mut self, spread: f32) -> Self {
+		self.spread = spread;
+		self
+	}
+
+	pub const fn with_radius(mut self, radius: f32) -> Self {
+		self.radius = radius;
+		self
+	}
+}
+
+/// Region of an uploaded texture.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Image {
+	pub texture: TextureId,
+	/// x, y, w & h in normalized texture coordinates.
+	pub uv:      [f32; 4],
+}
+
+impl Image {
+	/// The whole texture.
+	pub const fn new(texture: TextureId) -> Self {
+		Self { texture, uv: [0.0, 0.0, 1.0, 1.0] }
+	}
+
+	pub const fn with_uv(mut self, x: f32, y: f32, w: f32, h: f32) -> Self {
+		self.uv = [x, y, w, h];
+		self
+	}
+}
+
+/// Margins of a nine-slice image, in texels.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Insets {
+	pub top:    f32,
+	pub right:  f32,
+	pub bottom: f32,
+	pub left:   f32,
+}
+
+impl Insets {
+	pub const fn new(top: f32, right: f32, bottom: f32, left: f32) -> Self {
+		Self { top, right, bottom, left }
+	}
+
+	pub const fn all(inset: f32) -> Self {
+		Self::new(inset, inset, inset, inset)
+	}
+}
+
+/// Shape of the open ends of a stroke.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum LineCap {
+	/// Ends right at the end point.
+	#[default]
+	Butt,
+	/// Sticks out by half of the width.
+	Square,
+	Round,
+}
+
+/// Shape of the outer corners of a polyline.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum LineJoin {
+	/// Beveled when longer than [`crate::index::MITER_LIMIT`] times
+	/// the width.
+	#[default]
+	Miter,
+	Bevel,
+	Round,
+}
+
+/// How lines & polylines are drawn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stroke {
+	pub width: f32,
+	pub paint: Paint,
+	pub cap:   LineCap,
+	pub join:  LineJoin,
+}
+
+impl Stroke {
+	pub fn new(width: f32, paint: impl Into<Paint>) -> Self {
+		Self {
+			width,
+			paint: paint.into(),
+			cap:  LineCap::Butt,
+			join: LineJoin::Miter,
+		}
+	}
+
+	pub const fn with_cap(mut self, cap: LineCap) -> Self {
+		self.cap = cap;
+		self
+	}
+
+	pub const fn with_join(mut self, join: LineJoin) -> Self {
+		self.join = join;
+		self
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EncodeError {
+	/// Prima buffer has no room left for the primitive data.
+	OutOfPrimaSpace { needed: usize, available: usize },
+	/// Index buffer has no room left for the primitive indices.
+	OutOfIndexSpace { needed: usize, available: usize },
+	/// Primitive data doesn't fit into the 24-bit index offset.
+	OffsetOverflow { offset: usize },
+	/// Gradient stops don't go in increasing offsets.
+	UnsortedStops,
+}
+
+impl fmt::Display for EncodeError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			EncodeError::OutOfPrimaSpace { needed, available } => {
+				write!(f, "prima buffer is out of space ({needed} bytes needed, {available} available)")
+			},
+			EncodeError::OutOfIndexSpace { needed, available } => {
+				write!(f, "index buffer is out of space ({needed} bytes needed, {available} available)")
+			},
+			EncodeError::OffsetOverflow { offset } => {
+				write!(f, "prima offset {offset} exceeds 24 bits")
+			},
+			EncodeError::UnsortedStops => {
+				write!(f, "gradient stops are not in increasing offsets")
+			},
+		}
+	}
+}
+
+impl Error for EncodeError {}
+
+/// What to do with a primitive that doesn't fit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Overflow {
+	/// Report an [`EncodeError`] to the caller.
+	#[default]
+	Error,
+	/// Silently drop the primitive, keep the count in
+	/// [`PrimaEncoder::spilled`]. The frame is incomplete, but the
+	/// rest of the app keeps going.
+	Spill,
+}
+
+/// Writes primitives into a single frame worth of prima & index
+/// buffers.
+///
+/// Both buffers are usually the mapped per-frame regions, the
+/// encoder tracks where the next primitive goes and how many
+/// indices should be drawn.
+pub struct PrimaEncoder<'a> {
+	prima:    &'a mut [u8],
+	index:    &'a mut [u8],
+	size:     (f32, f32),
+	// In 32-bit words.
+	offset:   usize,
+	indices:  usize,
+	overflow: Overflow,
+	spilled:  usize,
+}
+
+impl<'a> PrimaEncoder<'a> {
+	/// Starts a new frame of `w` x `h` pixels, the projection goes
+	/// first.
+	///
+	/// Fails if the prima buffer can't fit even the projection,
+	/// regardless of the overflow policy.
+	pub fn new(prima: &'a mut [u8], index: &'a mut [u8], w: f32, h: f32) -> Result<Self, EncodeError> {
+		let mut encoder = Self {
+			prima,
+			index,
+			size:     (w, h),
+			offset:   0,
+			indices:  0,
+			overflow: Overflow::Error,
+			spilled:  0,
+		};
+
+		encoder.check(16, 0)?;
+		for column in ortho_projection(w, h) {
+			for v in column {
+				encoder.push_f32(v);
+			}
+		}
+
+		Ok(encoder)
+	}
+
+	pub fn with_overflow(mut self, overflow: Overflow) -> Self {
+		self.overflow = overflow;
+		self
+	}
+
+	/// Writes the color ramp of `gradient`, the returned paint can fill
+	/// any number of primitives in this frame.
+	///
+	/// A spilled gradient falls back to the color of its first stop, a
+	/// gradient without stops is transparent. A zero-length linear or a
+	/// zero-radius radial gradient is padded all over, so it is the
+	/// solid color of its last stop.
+	pub fn gradient(&mut self, gradient: &Gradient) -> Result<Paint, EncodeError> {
+		let (kind, params, stops) = match *gradient {
+			Gradient::Linear { start, end, stops } => {
+				(GRADIENT_LINEAR, [start[0], start[1], end[0], end[1]], stops)
+			},
+			Gradient::Radial { center, radius, stops } => {
+				(GRADIENT_RADIAL, [center[0], center[1], radius, 0.0], stops)
+			},
+			Gradient::Conic { center, angle, stops } => {
+				(GRADIENT_CONIC, [center[0], center[1], angle, 0.0], stops)
+			},
+		};
+
+		let (Some(first), Some(last)) = (stops.first(), stops.last()) else {
+			return Ok(Color(0).into());
+		};
+		// NaN offsets are out of order, too.
+		if !stops.is_sorted_by(|a, b| a.offset <= b.offset) {
+			return Err(EncodeError::UnsortedStops);
+		}
+		let degenerate = match *gradient {
+			Gradient::Linear { start, end, .. } => start == end,
+			Gradient::Radial { radius, .. }     => radius <= 0.0 || radius.is_nan(),
+			Gradient::Conic { .. }              => false,
+		};
+		if degenerate {
+			return Ok(last.color.into());
+		}
+		if !self.reserve(6 + stops.len() * 2, 0)? {
+			return Ok(first.color.into());
+		}
+
+		let offset = self.offset as u32;
+
+		self.push_u32(kind);
+		for v in params {
+			self.push_f32(v);
+		}
+		self.push_u32(stops.len() as u32);
+		for stop in stops {
+			self.push_f32(stop.offset);
+			self.push_u32(stop.color.0);
+		}
+
+		Ok(Paint { word: offset, gradient: true })
+	}
+
+	/// Axis-aligned rect with its top-left corner at (`x`, `y`),
+	/// filled with `paint`.
+	pub fn rect(&mut self, x: f32, y: f32, w: f32, h: f32, paint: impl Into<Paint>) -> Result<(), EncodeError> {
+		if !self.reserve(5, 6)? {
+			return Ok(());
+		}
+
+		let offset = self.offset as u32;
+		let paint  = paint.into();
+
+		self.push_f32(x);
+		self.push_f32(y);
+		self.push_f32(w);
+		self.push_f32(h);
+		self.push_u32(paint.word);
+
+		for corner in [0, 1, 2, 2, 3, 0] {
+			self.push_index(make_index(offset, PRIMA_RECT | paint.flags(), corner));
+		}
+
+		Ok(())
+	}
+
+	/// Anti-aliased rect with rounded corners, the edges are not
+	/// snapped to whole pixels.
+	pub fn rounded_rect(
+		&mut self,
+		x: f32,
+		y: f32,
+		w: f32,
+		h: f32,
+		radii: CornerRadii,
+		paint: impl Into<Paint>,
+	) -> Result<(), EncodeError> {
+		if !self.reserve(9, 6)? {
+			return Ok(());
+		}
+
+		let offset = self.offset as u32;
+		let paint  = paint.into();
+
+		self.push_f32(x);
+		self.push_f32(y);
+		self.push_f32(w);
+		self.push_f32(h);
+		self.push_u32(paint.word);
+		self.push_f32(radii.top_left);
+		self.push_f32(radii.top_right);
+		self.push_f32(radii.bottom_right);
+		self.push_f32(radii.bottom_left);
+
+		for corner in [0, 1, 2, 2, 3, 0] {
+			self.push_index(make_index(offset, PRIMA_ROUNDED_RECT | paint.flags(), corner));
+		}
+
+		Ok(())
+	}
+
+	/// Anti-aliased border inside of the rect with its top-left corner
+	/// at (`x`, `y`), `radii` round its outer corners.
+	pub fn border(
+		&mut self,
+		x: f32,
+		y: f32,
+		w: f32,
+		h: f32,
+		border: &Border,
+		radii: CornerRadii,
+	) -> Result<(), EncodeError> {
+		if !self.reserve(16, 6)? {
+			return Ok(());
+		}
+
+		let offset = self.offset as u32;
+
+		self.push_f32(x);
+		self.push_f32(y);
+		self.push_f32(w);
+		self.push_f32(h);
+		self.push_u32(border.top.color.0);
+		self.push_f32(border.top.width);
+		self.push_f32(border.right.width);
+		self.push_f32(border.bottom.width);
+		self.push_f32(border.left.width);
+		self.push_u32(border.right.color.0);
+		self.push_u32(border.bottom.color.0);
+		self.push_u32(border.left.color.0);
+		self.push_f32(radii.top_left);
+		self.push_f32(radii.top_right);
+		self.push_f32(radii.bottom_right);
+		self.push_f32(radii.bottom_left);
+
+		for corner in [0, 1, 2, 2, 3, 0] {
+			self.push_index(make_index(offset, PRIMA_BORDER, corner));
+		}
+
+		Ok(())
+	}
+
+	/// Shadow cast by the rect with its top-left corner at (`x`, `y`),
+	/// draw it before the rect.
+	pub fn shadow(&mut self, x: f32, y: f32, w: f32, h: f32, shadow: &Shadow) -> Result<(), EncodeError> {
+		if !self.reserve(7, 6)? {
+			return Ok(());
+		}
+
+		let offset = self.offset as u32;
+
+		let s = shadow.spread;
+		self.push_f32(x + shadow.offset[0] - s);
+		self.push_f32(y + shadow.offset[1] - s);
+		self.push_f32((w + s * 2.0).max(0.0));
+		self.push_f32((h + s * 2.0).max(0.0));
+		self.push_u32(shadow.color.0);
+		self.push_f32((shadow.radius + s).max(0.0));
+		self.push_f32(shadow.sigma.max(0.0));
+
+		for corner in [0, 1, 2, 2, 3, 0] {
+			self.push_index(make_index(offset, PRIMA_SHADOW, corner));
+		}
+
+		Ok(())
+	}
+
+	/// `image` stretched over the rect with its top-left corner at
+	/// (`x`, `y`), multiplied by `tint`. Snapped like [`Self::rect`].
+	pub fn image(
+		&mut self,
+		x: f32,
+		y: f32,
+		w: f32,
+		h: f32,
+		image: &Image,
+		tint: impl Into<Paint>,
+	) -> Result<(), EncodeError> {
+		if !self.reserve(10, 6)? {
+			return Ok(());
+		}
+
+		let offset = self.offset as u32;
+		let tint   = tint.into();
+
+		self.push_f32(x);
+		self.push_f32(y);
+		self.push_f32(w);
+		self.push_f32(h);
+		self.push_u32(tint.word);
+		self.push_u32(image.texture.0);
+		for v in image.uv {
+			self.push_f32(v);
+		}
+
+		for corner in [0, 1, 2, 2, 3, 0] {
+			self.push_index(make_index(offset, PRIMA_IMAGE | tint.flags(), corner));
+		}
+
+		Ok(())
+	}
+
+	/// `image` scaled as a nine-patch over the rect with its top-left
+	/// corner at (`x`, `y`): `insets` keep the corners as they are,
+	/// only the edges & the center stretch.
+	#[allow(clippy::too_many_arguments)]
+	pub fn nine_slice(
+		&mut self,
+		x: f32,
+		y: f32,
+		w: f32,
+		h: f32,
+		image: &Image,
+		insets: Insets,
+		tint: impl Into<Paint>,
+	) -> Result<(), EncodeError> {
+		if !self.reserve(14, 6)? {
+			return Ok(());
+		}
+
+		let offset = self.offset as u32;
+		let tint   = tint.into();
+
+		self.push_f32(x);
+		self.push_f32(y);
+		self.push_f32(w);
+		self.push_f32(h);
+		self.push_u32(tint.word);
+		self.push_u32(image.texture.0);
+		for v in image.uv {
+			self.push_f32(v);
+		}
+		self.push_f32(insets.top);
+		self.push_f32(insets.right);
+		self.push_f32(insets.bottom);
+		self.push_f32(insets.left);
+
+		for corner in [0, 1, 2, 2, 3, 0] {
+			self.push_index(make_index(offset, PRIMA_NINE_SLICE | tint.flags(), corner));
+		}
+
+		Ok(())
+	}
+
+	/// `text` in `font` scaled to `size` pixels, `pos` is the top-left
+	/// corner of the first line. Line breaks start new lines.
+	pub fn text(
+		&mut self,
+		pos: [f32; 2],
+		text: &str,
+		font: &Font,
+		size: f32,
+		paint: impl Into<Paint>,
+	) -> Result<(), EncodeError> {
+		self.text_layout(pos, &Layout::new(size).layout(text, font), font, paint)
+	}
+
+	/// Glyphs of `layout` with its top-left corner at `pos`, `font` is
+	/// the one it has been laid out with.
+	pub fn text_layout(
+		&mut self,
+		pos: [f32; 2],
+		layout: &TextLayout,
+		font: &Font,
+		paint: impl Into<Paint>,
+	) -> Result<(), EncodeError> {
+		let drawn = layout.glyphs
+			.iter()
+			.filter_map(|g| font.glyph(g.c))
+			.filter(|g| g.rect[2] > 0 && g.rect[3] > 0)
+			.count();
+		let words = match font.format() {
+			GlyphFormat::Coverage => 10,
+			GlyphFormat::Sdf { .. } | GlyphFormat::Msdf { .. } => 11,
+		};
+		// All or nothing, a word missing some letters is worse than none.
+		if !self.reserve(drawn * words, drawn * 6)? {
+			return Ok(());
+		}
+
+		let paint = paint.into();
+		let scale = layout.size / font.size();
+		let atlas = font.atlas_size();
+
+		for positioned in &layout.glyphs {
+			let Some(glyph) = font.glyph(positioned.c) else {
+				continue;
+			};
+
+			let [x, y, w, h] = glyph.rect.map(|v| v as f32);
+			if w > 0.0 && h > 0.0 {
+				let rect = [
+					pos[0] + positioned.pos[0] + glyph.offset[0] * scale,
+					pos[1] + positioned.pos[1] + glyph.offset[1] * scale,
+					w * scale,
+					h * scale,
+				];
+				let uv = [x / atlas[0], y / atlas[1], w / atlas[0], h / atlas[1]];
+				self.push_glyph(rect, font.texture(), uv, font.format(), paint);
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Coverage glyphs out of `texture`, such as the ones placed by a
+	/// [`crate::cache::GlyphCache`]. All of them or none are drawn.
+	pub fn glyphs(&mut self, texture: TextureId, quads: &[GlyphQuad], paint: impl Into<Paint>) -> Result<(), EncodeError> {
+		if !self.reserve(quads.len() * 10, quads.len() * 6)? {
+			return Ok(());
+		}
+
+		let paint = paint.into();
+		for quad in quads {
+			self.push_glyph(quad.rect, texture, quad.uv, GlyphFormat::Coverage, paint);
+		}
+
+		Ok(())
+	}
+
+	/// Anti-aliased ellipse centered at (`cx`, `cy`).
+	pub fn ellipse(&mut self, cx: f32, cy: f32, rx: f32, ry: f32, paint: impl Into<Paint>) -> Result<(), EncodeError> {
+		if !self.reserve(5, 6)? {
+			return Ok(());
+		}
+
+		let offset = self.offset as u32;
+		let paint  = paint.into();
+
+		self.push_f32(cx);
+		self.push_f32(cy);
+		self.push_f32(rx);
+		self.push_f32(ry);
+		self.push_u32(paint.word);
+
+		for corner in [0, 1, 2, 2, 3, 0] {
+			self.push_index(make_index(offset, PRIMA_ELLIPSE | paint.flags(), corner));
+		}
+
+		Ok(())
+	}
+
+	pub fn circle(&mut self, cx: f32, cy: f32, r: f32, paint: impl Into<Paint>) -> Result<(), EncodeError> {
+		self.ellipse(cx, cy, r, r, paint)
+	}
+
+	/// Anti-aliased circular arc of `thickness` around `radius` with
+	/// butt ends. It goes from `start` for `sweep` radians, positive
+	/// angles are clockwise on the screen. A sweep of 2π or more is a
+	/// full ring.
+	#[allow(clippy::too_many_arguments)]
+	pub fn arc(
+		&mut self,
+		cx: f32,
+		cy: f32,
+		radius: f32,
+		thickness: f32,
+		start: f32,
+		sweep: f32,
+		paint: impl Into<Paint>,
+	) -> Result<(), EncodeError> {
+		if !self.reserve(9, 6)? {
+			return Ok(());
+		}
+
+		let offset = self.offset as u32;
+		let paint  = paint.into();
+
+		let mid  = start + sweep * 0.5;
+		let half = sweep.abs() * 0.5;
+		let end  = if half >= PI { (-1.0, 0.0) } else { (half.cos(), half.sin()) };
+
+		self.push_f32(cx);
+		self.push_f32(cy);
+		self.push_f32(radius);
+		self.push_f32(thickness);
+		self.push_u32(paint.word);
+		self.push_f32(mid.cos());
+		self.push_f32(mid.sin());
+		self.push_f32(end.0);
+		self.push_f32(end.1);
+
+		for corner in [0, 1, 2, 2, 3, 0] {
+			self.push_index(make_index(offset, PRIMA_ARC | paint.flags(), corner));
+		}
+
+		Ok(())
+	}
+
+	/// Anti-aliased line from `a` to `b`, a zero-length one draws
+	/// nothing.
+	pub fn line(&mut self, a: [f32; 2], b: [f32; 2], stroke: &Stroke) -> Result<(), EncodeError> {
+		if a == b || !self.reserve(7, 6)? {
+			return Ok(());
+		}
+
+		let cap = line_cap(stroke.cap);
+		self.push_line(a, b, stroke, cap, cap);
+
+		Ok(())
+	}
+
+	/// Anti-aliased lines through `points`, capped at both ends &
+	/// joined in between. Repeated points are skipped.
+	///
+	/// Translucent strokes get darker where the lines overlap on the
+	/// inner side of the joints.
+	pub fn polyline(&mut self, points: &[[f32; 2]], stroke: &Stroke) -> Result<(), EncodeError> {
+		let mut points = points.to_vec();
+		points.dedup();
+		if points.len() < 2 {
+			return Ok(());
+		}
+
+		// All or nothing, a polyline missing its middle is worse than
+		// none.
+		let lines = points.len() - 1;
+		let joins = lines - 1;
+		if !self.reserve(lines * 7 + joins * 9, (lines + joins) * 6)? {
+			return Ok(());
+		}
+
+		let cap = line_cap(stroke.cap);
+		for (i, ab) in points.windows(2).enumerate() {
+			let start = if i == 0 { cap } else { LINE_JOINT };
+			let end   = if i == lines - 1 { cap } else { LINE_JOINT };
+			self.push_line(ab[0], ab[1], stroke, start, end);
+		}
+
+		let kind = match stroke.join {
+			LineJoin::Miter => JOIN_MITER,
+			LineJoin::Bevel => JOIN_BEVEL,
+			LineJoin::Round => JOIN_ROUND,
+		};
+		for joint in points.windows(3) {
+			let offset = self.offset as u32;
+
+			self.push_f32(joint[1][0]);
+			self.push_f32(joint[1][1]);
+			self.push_f32(joint[0][0]);
+			self.push_f32(joint[0][1]);
+			self.push_u32(stroke.paint.word);
+			self.push_f32(stroke.width);
+			self.push_u32(kind);
+			self.push_f32(joint[2][0]);
+			self.push_f32(joint[2][1]);
+
+			for corner in [0, 1, 2, 2, 3, 0] {
+				self.push_index(make_index(offset, PRIMA_JOIN | stroke.paint.flags(), corner));
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Anti-aliased quadratic Bézier curve from `p0` to `p2`, flattened
+	/// into a polyline.
+	pub fn quadratic_bezier(
+		&mut self,
+		p0: [f32; 2],
+		p1: [f32; 2],
+		p2: [f32; 2],
+		stroke: &Stroke,
+	) -> Result<(), EncodeError> {
+		let dd = length([p0[0] - 2.0 * p1[0] + p2[0], p0[1] - 2.0 * p1[1] + p2[1]]);
+		let n  = curve_segments(dd * 0.25);
+
+		let points: Vec<_> = (0..=n)
+			.map(|i| {
+				let t = i as f32 / n as f32;
+				let s = 1.0 - t;
+				let (w0, w1, w2) = (s * s, 2.0 * s * t, t * t);
+				[
+					w0 * p0[0] + w1 * p1[0] + w2 * p2[0],
+					w0 * p0[1] + w1 * p1[1] + w2 * p2[1],
+				]
+			})
+			.collect();
+
+		self.polyline(&points, stroke)
+	}
+
+	/// Anti-aliased cubic Bézier curve from `p0` to `p3`, flattened
+	/// into a polyline.
+	pub fn cubic_bezier(
+		&mut self,
+		p0: [f32; 2],
+		p1: [f32; 2],
+		p2: [f32; 2],
+		p3: [f32; 2],
+		stroke: &Stroke,
+	) -> Result<(), EncodeError> {
+		let dd0 = length([p0[0] - 2.0 * p1[0] + p2[0], p0[1] - 2.0 * p1[1] + p2[1]]);
+		let dd1 = length([p1[0] - 2.0 * p2[0] + p3[0], p1[1] - 2.0 * p2[1] + p3[1]]);
+		let n   = curve_segments(dd0.max(dd1) * 0.75);
+
+		let points: Vec<_> = (0..=n)
+			.map(|i| {
+				let t = i as f32 / n as f32;
+				let s = 1.0 - t;
+				let (w0, w1, w2, w3) = (s * s * s, 3.0 * s * s * t, 3.0 * s * t * t, t * t * t);
+				[
+					w0 * p0[0] + w1 * p1[0] + w2 * p2[0] + w3 * p3[0],
+					w0 * p0[1] + w1 * p1[1] + w2 * p2[1] + w3 * p3[1],
+				]
+			})
+			.collect();
+
+		self.polyline(&points, stroke)
+	}
+
+	pub fn triangle(&mut self, v0: Vertex, v1: Vertex, v2: Vertex) -> Result<(), EncodeError> {
+		if !self.reserve(9, 3)? {
+			return Ok(());
+		}
+
+		for v in [v0, v1, v2] {
+			let offset = self.offset as u32;
+
+			self.push_f32(v.x);
+			self.push_f32(v.y);
+			self.push_u32(v.color.0);
+
+			self.push_index(make_index(offset, PRIMA_TRI, 0));
+		}
+
+		Ok(())
+	}
+
+	/// Size of the frame in pixels.
+	pub fn size(&self) -> (f32, f32) {
+		self.size
+	}
+
+	/// Amount of indices written so far.
+	pub fn indices(&self) -> u32 {
+		self.indices as u32
+	}
+
+	/// Amount of primitives dropped by [`Overflow::Spill`].
+	pub fn spilled(&self) -> usize {
+		self.spilled
+	}
+
+	/// Finishes the frame, returns the index count to draw.
+	pub fn finish(self) -> u32 {
+		self.indices()
+	}
+
+	/// Makes sure a primitive of `words` data words and `indices`
+	/// indices fits. Returns `false` if it has been spilled instead.
+	fn reserve(&mut self, words: usize, indices: usize) -> Result<bool, EncodeError> {
+		match self.check(words, indices) {
+			Ok(()) => Ok(true),
+			Err(_) if self.overflow == Overflow::Spill => {
+				self.spilled += 1;
+				Ok(false)
+			},
+			Err(e) => Err(e),
+		}
+	}
+
+	fn check(&self, words: usize, indices: usize) -> Result<(), EncodeError> {
+		// @Robustness Being a bit conservative here: only the start of a
+		// record has to be addressable, but it is simpler to require the
+		// whole record to be.
+		let last = self.offset + words - 1;
+		if last > MAX_OFFSET {
+			return Err(EncodeError::OffsetOverflow { offset: last });
+		}
+
+		let needed    = (self.offset + words) * 4;
+		let available = self.prima.len();
+		if needed > available {
+			return Err(EncodeError::OutOfPrimaSpace { needed, available });
+		}
+
+		let needed    = (self.indices + indices) * 4;
+		let available = self.index.len();
+		if needed > available {
+			return Err(EncodeError::OutOfIndexSpace { needed, available });
+		}
+
+		Ok(())
+	}
+
+	/// Writes a PRIMA_LINE, the room has to be reserved.
+	fn push_line(&mut self, a: [f32; 2], b: [f32; 2], stroke: &Stroke, start: u32, end: u32) {
+		let offset = self.offset as u32;
+
+		self.push_f32(a[0]);
+		self.push_f32(a[1]);
+		self.push_f32(b[0]);
+		self.push_f32(b[1]);
+		self.push_u32(stroke.paint.word);
+		self.push_f32(stroke.width);
+		self.push_u32(start | end << 8);
+
+		for corner in [0, 1, 2, 2, 3, 0] {
+			self.push_index(make_index(offset, PRIMA_LINE | stroke.paint.flags(), corner));
+		}
+	}
+
+	/// Writes a glyph of `format`, the room has to be reserved.
+	fn push_glyph(&mut self, rect: [f32; 4], texture: TextureId, uv: [f32; 4], format: GlyphFormat, paint: Paint) {
+		let offset = self.offset as u32;
+
+		for v in rect {
+			self.push_f32(v);
+		}
+		self.push_u32(paint.word);
+		self.push_u32(texture.0);
+		for v in uv {
+			self.push_f32(v);
+		}
+
+		let ptype = match format {
+			GlyphFormat::Coverage => PRIMA_GLYPH,
+			GlyphFormat::Sdf { range } => {
+				self.push_f32(range);
+				PRIMA_SDF_GLYPH
+			},
+			GlyphFormat::Msdf { range } => {
+				self.push_f32(range);
+				PRIMA_MSDF_GLYPH
+			},
+		};
+
+		for corner in [0, 1, 2, 2, 3, 0] {
+			self.push_index(make_index(offset, ptype | paint.flags(), corner));
+		}
+	}
+
+	fn push_f32(&mut self, v: f32) {
+		self.push_u32(v.to_bits());
+	}
+
+	fn push_u32(&mut self, v: u32) {
+		let at = self.offset * 4;
+		self.prima[at..at + 4].copy_from_slice(&v.to_ne_bytes());
+		self.offset += 1;
+	}
+
+	fn push_index(&mut self, index: u32) {
+		let at = self.indices * 4;
+		self.index[at..at + 4].copy_from_slice(&index.to_ne_bytes());
+		self.indices += 1;
+	}
+}
+
+/// Largest distance between a flattened curve & the real one, in
+/// pixels.
+const CURVE_TOLERANCE: f32 = 0.2;
+
+/// Keeps huge curves from eating the whole prima buffer.
+const MAX_CURVE_SEGMENTS: usize = 256;
+
+/// Wang's formula, `bend` is the longest second difference of the
+/// control points times d(d - 1) / 8 for degree d. Split uniformly
+/// into that many lines, the curve stays within the tolerance.
+fn curve_segments(bend: f32) -> usize {
+	let n = (bend / CURVE_TOLERANCE).sqrt().ceil();
+	if n.is_nan() {
+		return 1;
+	}
+	(n as usize).clamp(1, MAX_CURVE_SEGMENTS)
+}
+
+fn length(v: [f32; 2]) -> f32 {
+	(v[0] * v[0] + v[1] * v[1]).sqrt()
+}
+
+fn line_cap(cap: LineCap) -> u32 {
+	match cap {
+		LineCap::Butt   => LINE_BUTT,
+		LineCap::Square => LINE_SQUARE,
+		LineCap::Round  => LINE_ROUND,
+	}
+}
+
+fn ortho_projection(w: f32, h: f32) -> [[f32; 4]; 4] {
+	let l = 0.0;
+	let r = l + w;
+	let t = 0.0;
+	let b = t + h;
+	let n = 0.0;
+	let f = 1.0;
+
+	[
+		[
+			2.0 / (r - l),
+			0.0,
+			0.0,
+			0.0,
+		],
+		[
+			0.0,
+			2.0 / (b - t),
+			0.0,
+			0.0,
+		],
+		[
+			0.0,
+			0.0,
+			1.0 / (n - f),
+			0.0,
+		],
+		[
+			-(r + l) / (r - l),
+			-(b + t) / (b - t),
+			n / (n - f),
+			1.0,
+		],
+	]
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::text::{Glyph, LineMetrics};
+
+	const RED: Color = Color::rgb(255, 0, 0);
+
+	fn tri(encoder: &mut PrimaEncoder) -> Result<(), EncodeError> {
+		encoder.triangle(
+			Vertex::new(0.0, 0.0, RED),
+			Vertex::new(1.0, 0.0, RED),
+			Vertex::new(0.0, 1.0, RED),
+		)
+	}
+
+	/// Word `i` of `buf`.
+	fn word(buf: &[u8], i: usize) -> u32 {
+		u32::from_ne_bytes(buf[i * 4..i * 4 + 4].try_into().unwrap())
+	}
+
+	fn float(buf: &[u8], i: usize) -> f32 {
+		f32::from_bits(word(buf, i))
+	}
+
+	/// Prima & index buffers of whatever `f` encodes into a 64x64
+	/// frame, the index one cut to the indices written.
+	fn encode(f: impl FnOnce(&mut PrimaEncoder) -> Result<(), EncodeError>) -> (Vec<u8>, Vec<u8>) {
+		let mut prima = vec![0; 64 * 1024];
+		let mut index = vec![0; 64 * 1024];
+
+		let mut encoder = PrimaEncoder::new(&mut prima, &mut index, 64.0, 64.0).unwrap();
+		f(&mut encoder).unwrap();
+		let indices = encoder.finish() as usize;
+
+		index.truncate(indices * 4);
+		(prima, index)
+	}
+
+	#[test]
+	fn encodes_rect_and_triangle() {
+		let (prima, index) = encode(|encoder| {
+			encoder.rect(1.0, 2.0, 3.0, 4.0, Color::WHITE)?;
+			tri(encoder)
+		});
+		assert_eq!(index.len(), 9 * 4);
+
+		assert_eq!(float(&prima, 16), 1.0);
+		assert_eq!(float(&prima, 19), 4.0);
+		assert_eq!(word(&prima, 20), Color::WHITE.0);
+		assert_eq!(word(&prima, 23), RED.0);
+
+		assert_eq!(word(&index, 0), make_index(16, PRIMA_RECT, 0));
+		assert_eq!(word(&index, 2), make_index(16, PRIMA_RECT, 2));
+		assert_eq!(word(&index, 8), make_index(27, PRIMA_TRI, 0));
+	}
+
+	#[test]
+	fn encodes_rounded_rect() {
+		let (prima, index) = encode(|encoder| {
+			encoder.rounded_rect(1.0, 2.0, 3.0, 4.0, CornerRadii::new(5.0, 6.0, 7.0, 8.0), RED)
+		});
+		assert_eq!(index.len(), 6 * 4);
+
+		assert_eq!(word(&prima, 20), RED.0);
+		assert_eq!(float(&prima, 21), 5.0);
+		assert_eq!(float(&prima, 24), 8.0);
+		assert_eq!(word(&index, 3), make_index(16, PRIMA_ROUNDED_RECT, 2));
+	}
+
+	#[test]
+	fn encodes_polyline_as_lines_and_joins() {
+		let stroke = Stroke::new(2.0, RED).with_cap(LineCap::Round);
+		let points = [[0.0, 0.0], [4.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0]];
+
+		let (prima, index) = encode(|encoder| encoder.polyline(&points, &stroke));
+		// The repeated point is skipped: 3 lines & 2 joins.
+		assert_eq!(index.len(), 5 * 6 * 4);
+
+		assert_eq!(word(&prima, 16 + 6), LINE_ROUND | LINE_JOINT << 8);
+		assert_eq!(word(&prima, 16 + 7 + 6), LINE_JOINT | LINE_JOINT << 8);
+		assert_eq!(word(&prima, 16 + 14 + 6), LINE_JOINT | LINE_ROUND << 8);
+		assert_eq!(word(&index, 18), make_index(16 + 21, PRIMA_JOIN, 0));
+		assert_eq!(float(&prima, 16 + 21), 4.0);
+		assert_eq!(word(&prima, 16 + 21 + 6), JOIN_MITER);
+	}
+
+	#[test]
+	fn spills_whole_polylines() {
+		// Room for a single line only.
+		let mut prima = vec![0; (16 + 7) * 4];
+		let mut index = vec![0; 1024];
+
+		let mut encoder = PrimaEncoder::new(&mut prima, &mut index, 8.0, 8.0)
+			.unwrap()
+			.with_overflow(Overflow::Spill);
+		let stroke = Stroke::new(1.0, RED);
+		encoder.polyline(&[[0.0, 0.0], [4.0, 0.0], [4.0, 4.0]], &stroke).unwrap();
+		encoder.line([0.0, 0.0], [4.0, 0.0], &stroke).unwrap();
+		assert_eq!(encoder.spilled(), 1);
+		assert_eq!(encoder.finish(), 6);
+	}
+
+	#[test]
+	fn flattens_curves_within_tolerance() {
+		let (p0, p1, p2, p3) = ([0.0, 0.0], [10.0, 200.0], [300.0, -100.0], [120.0, 40.0]);
+		let curve = |t: f32| {
+			let s = 1.0 - t;
+			let (w0, w1, w2, w3) = (s * s * s, 3.0 * s * s * t, 3.0 * s * t * t, t * t * t);
+			[
+				w0 * p0[0] + w1 * p1[0] + w2 * p2[0] + w3 * p3[0],
+				w0 * p0[1] + w1 * p1[1] + w2 * p2[1] + w3 * p3[1],
+			]
+		};
+
+		let (prima, index) = encode(|encoder| encoder.cubic_bezier(p0, p1, p2, p3, &Stroke::new(1.0, RED)));
+		let float = |i: usize| float(&prima, i);
+
+		// n lines go first, n - 1 joins after them.
+		let n = (index.len() / 4 / 6).div_ceil(2);
+		assert!(n > 16);
+		for i in 0..n {
+			let at = 16 + i * 7;
+			let (a, b) = ([float(at), float(at + 1)], [float(at + 2), float(at + 3)]);
+
+			// Distance from the middle of the span to its line.
+			let m  = curve((i as f32 + 0.5) / n as f32);
+			let ab = [b[0] - a[0], b[1] - a[1]];
+			let am = [m[0] - a[0], m[1] - a[1]];
+			let d  = (ab[0] * am[1] - ab[1] * am[0]).abs() / length(ab);
+			assert!(d <= CURVE_TOLERANCE, "{i}: {d}");
+		}
+	}
+
+	#[test]
+	fn straight_curves_are_single_lines() {
+		let (_, index) = encode(|encoder| {
+			encoder.quadratic_bezier([0.0, 0.0], [2.0, 2.0], [4.0, 4.0], &Stroke::new(1.0, RED))
+		});
+		assert_eq!(index.len(), 6 * 4);
+	}
+
+	#[test]
+	fn encodes_gradient_paint() {
+		let stops = [ColorStop::new(0.0, RED), ColorStop::new(1.0, Color::WHITE)];
+
+		let (prima, index) = encode(|encoder| {
+			let paint = encoder.gradient(&Gradient::Radial { center: [1.0, 2.0], radius: 3.0, stops: &stops })?;
+			encoder.rect(0.0, 0.0, 8.0, 8.0, paint)
+		});
+		assert_eq!(index.len(), 6 * 4);
+
+		assert_eq!(word(&prima, 16), GRADIENT_RADIAL);
+		assert_eq!(float(&prima, 19), 3.0);
+		assert_eq!(word(&prima, 21), 2);
+		assert_eq!(word(&prima, 25), Color::WHITE.0);
+		// The rect points at the gradient.
+		assert_eq!(word(&prima, 26 + 4), 16);
+		assert_eq!(word(&index, 0), make_index(26, PRIMA_RECT | PAINT_GRADIENT, 0));
+	}
+
+	#[test]
+	fn encodes_image() {
+		let image = Image::new(TextureId(3)).with_uv(0.25, 0.5, 0.5, 0.25);
+
+		let (prima, index) = encode(|encoder| encoder.image(1.0, 2.0, 3.0, 4.0, &image, RED));
+		assert_eq!(index.len(), 6 * 4);
+
+		assert_eq!(word(&prima, 16 + 4), RED.0);
+		assert_eq!(word(&prima, 16 + 5), 3);
+		assert_eq!(float(&prima, 16 + 6), 0.25);
+		assert_eq!(float(&prima, 16 + 9), 0.25);
+		assert_eq!(word(&index, 2), make_index(16, PRIMA_IMAGE, 2));
+	}
+
+	#[test]
+	fn encodes_nine_slice_as_one_quad() {
+		let image = Image::new(TextureId(1));
+
+		let (prima, index) = encode(|encoder| {
+			encoder.nine_slice(0.0, 0.0, 8.0, 8.0, &image, Insets::new(1.0, 2.0, 3.0, 4.0), RED)
+		});
+		assert_eq!(index.len(), 6 * 4);
+
+		assert_eq!(word(&prima, 16 + 5), 1);
+		assert_eq!(float(&prima, 16 + 10), 1.0);
+		assert_eq!(float(&prima, 16 + 13), 4.0);
+		assert_eq!(word(&index, 5), make_index(16, PRIMA_NINE_SLICE, 0));
+	}
+
+	#[test]
+	fn encodes_text_as_glyph_quads() {
+		let mut font = Font::new(TextureId(2), (32, 16), 8.0, LineMetrics::new(6.0, -2.0, 1.0));
+		font.add_glyph('a', Glyph { rect: [1, 1, 4, 5], offset: [0.0, -5.0], advance: 5.0 });
+		font.add_glyph(' ', Glyph { rect: [0; 4], offset: [0.0, 0.0], advance: 3.0 });
+		font.add_kerning('a', ' ', -1.0);
+
+		// Scaled twice, the missing 'b' is skipped.
+		let (prima, index) = encode(|encoder| encoder.text([10.0, 20.0], "a ab\na", &font, 16.0, RED));
+		assert_eq!(index.len(), 3 * 6 * 4);
+
+		let float = |i: usize| float(&prima, i);
+
+		// Baseline at 20 + 6 * 2.
+		assert_eq!([float(16), float(17), float(18), float(19)], [10.0, 22.0, 8.0, 10.0]);
+		assert_eq!(word(&prima, 16 + 5), 2);
+		assert_eq!([float(22), float(23), float(24), float(25)], [1.0 / 32.0, 1.0 / 16.0, 4.0 / 32.0, 5.0 / 16.0]);
+		// Past the first 'a' & the space, kerned closer.
+		assert_eq!(float(26), 10.0 + 16.0 - 2.0);
+		// Next line, 9 pixels below before scaling.
+		assert_eq!([float(36), float(37)], [10.0, 22.0 + 18.0]);
+		assert_eq!(word(&index, 0), make_index(16, PRIMA_GLYPH, 0));
+	}
+
+	#[test]
+	fn encodes_distance_field_glyphs() {
+		let mut font = Font::new(TextureId(1), (32, 16), 8.0, LineMetrics::new(6.0, -2.0, 1.0))
+			.with_format(GlyphFormat::Msdf { range: 2.0 });
+		font.add_glyph('a', Glyph { rect: [1, 1, 8, 9], offset: [-2.0, -7.0], advance: 5.0 });
+
+		let (prima, index) = encode(|encoder| encoder.text([0.0, 0.0], "aa", &font, 8.0, RED));
+		assert_eq!(index.len(), 2 * 6 * 4);
+
+		// The range follows the image, the next glyph the range.
+		assert_eq!(float(&prima, 16 + 10), 2.0);
+		assert_eq!(word(&index, 0), make_index(16, PRIMA_MSDF_GLYPH, 0));
+		assert_eq!(word(&index, 6), make_index(16 + 11, PRIMA_MSDF_GLYPH, 0));
+	}
+
+	#[test]
+	fn spilled_gradient_falls_back_to_its_first_stop() {
+		let mut prima = vec![0; (16 + 5) * 4];
+		let mut index = vec![0; 1024];
+
+		let stops = [ColorStop::new(0.0, RED), ColorStop::new(1.0, Color::WHITE)];
+
+		let mut encoder = PrimaEncoder::new(&mut prima, &mut index, 8.0, 8.0)
+			.unwrap()
+			.with_overflow(Overflow::Spill);
+		let paint = encoder.gradient(&Gradient::Linear { start: [0.0, 0.0], end: [8.0, 0.0], stops: &stops }).unwrap();
+		assert_eq!(paint, Paint::from(RED));
+		assert_eq!(encoder.spilled(), 1);
+	}
+
+	#[test]
+	fn rejects_unsorted_stops() {
+		let mut prima = vec![0; 1024];
+		let mut index = vec![0; 1024];
+
+		let stops = [ColorStop::new(0.5, RED), ColorStop::new(0.25, Color::WHITE)];
+		let nan   = [ColorStop::new(f32::NAN, RED), ColorStop::new(1.0, Color::WHITE)];
+
+		let mut encoder = PrimaEncoder::new(&mut prima, &mut index, 8.0, 8.0).unwrap();
+		for stops in [&stops, &nan] {
+			let err = encoder.gradient(&Gradient::Conic { center: [0.0, 0.0], angle: 0.0, stops }).unwrap_err();
+			assert_eq!(err, EncodeError::UnsortedStops);
+		}
+	}
+
+	#[test]
+	fn reports_out_of_prima_space() {
+		let mut prima = vec![0; (16 + 5) * 4];
+		let mut index = vec![0; 1024];
+
+		let mut encoder = PrimaEncoder::new(&mut prima, &mut index, 8.0, 8.0).unwrap();
+		encoder.rect(0.0, 0.0, 1.0, 1.0, RED).unwrap();
+		let err = encoder.rect(0.0, 0.0, 1.0, 1.0, RED).unwrap_err();
+		assert_eq!(err, EncodeError::OutOfPrimaSpace { needed: 104, available: 84 });
+		assert_eq!(encoder.finish(), 6);
+	}
+
+	#[test]
+	fn reports_out_of_index_space() {
+		let mut prima = vec![0; 1024];
+		let mut index = vec![0; 4 * 4];
+
+		let mut encoder = PrimaEncoder::new(&mut prima, &mut index, 8.0, 8.0).unwrap();
+		tri(&mut encoder).unwrap();
+		let err = tri(&mut encoder).unwrap_err();
+		assert_eq!(err, EncodeError::OutOfIndexSpace { needed: 24, available: 16 });
+	}
+
+	#[test]
+	fn reports_missing_room_for_projection() {
+		let mut prima = vec![0; 15 * 4];
+		let mut index = vec![0; 0];
+
+		let err = PrimaEncoder::new(&mut prima, &mut index, 8.0, 8.0).err();
+		assert_eq!(err, Some(EncodeError::OutOfPrimaSpace { needed: 64, available: 60 }));
+	}
+
+	#[test]
+	fn reports_offset_overflow() {
+		// Enough room for everything, so the 24 bits are the only limit.
+		let mut prima = vec![0; (MAX_OFFSET + 64) * 4];
+		let mut index = vec![0; MAX_OFFSET * 2];
+
+		let mut encoder = PrimaEncoder::new(&mut prima, &mut index, 8.0, 8.0).unwrap();
+		let err = loop {
+			if let Err(e) = tri(&mut encoder) {
+				break e;
+			}
+		};
+
+		// 16 words of projection, then 9 words per triangle.
+		let fitting = (MAX_OFFSET + 1 - 16) / 9;
+		assert_eq!(err, EncodeError::OffsetOverflow { offset: 16 + fitting * 9 + 8 });
+		assert_eq!(encoder.finish() as usize, fitting * 3);
+	}
+
+	#[test]
+	fn spills_instead_of_failing() {
+		let mut prima = vec![0; (16 + 5) * 4];
+		let mut index = vec![0; 1024];
+
+		let mut encoder = PrimaEncoder::new(&mut prima, &mut index, 8.0, 8.0)
+			.unwrap()
+			.with_overflow(Overflow::Spill);
+		encoder.rect(0.0, 0.0, 1.0, 1.0, RED).unwrap();
+		encoder.rect(0.0, 0.0, 1.0, 1.0, RED).unwrap();
+		tri(&mut encoder).unwrap();
+		assert_eq!(encoder.spilled(), 2);
+		assert_eq!(encoder.finish(), 6);
+	}
+}
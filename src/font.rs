@@ -0,0 +1,1976 @@
+//! TrueType & OpenType font files: metrics, kerning & glyph outlines,
+//! rasterized with exact area coverage.
+//!
+//! The files are read as they are, no system font service is
+//! involved. Outlines come from either the `glyf` table (quadratic) or
+//! the `CFF ` one (cubic), kerning from GPOS or the older `kern`.
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::text::{Font, Glyph, GlyphAtlas, GlyphFormat, LineMetrics};
+use crate::texture::TextureId;
+
+/// Largest distance between a flattened curve & the real one, in
+/// pixels. Finer than the encoder's, glyphs are small.
+const CURVE_TOLERANCE: f32 = 0.05;
+
+const MAX_CURVE_SEGMENTS: usize = 64;
+
+/// Composite glyphs & CFF subroutines nest at most that deep.
+const MAX_DEPTH: u32 = 10;
+
+/// Channels of the edges of a multi-channel distance field.
+const RED:   u8 = 1;
+const GREEN: u8 = 2;
+const BLUE:  u8 = 4;
+const WHITE: u8 = RED | GREEN | BLUE;
+
+/// Edges turning by more than ~8 degrees, as the sine of it, meet at
+/// a corner.
+const CORNER_SINE: f32 = 0.14;
+
+#[derive(Debug)]
+pub enum FontError {
+	Io(io::Error),
+	MissingTable(&'static str),
+	/// The glyphs don't fit into the atlas.
+	AtlasFull,
+	/// Data of the table is out of bounds or inconsistent.
+	Malformed(&'static str),
+	Unsupported(&'static str),
+}
+
+impl fmt::Display for FontError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			FontError::Io(e)              => write!(f, "failed to read the font: {e}"),
+			FontError::MissingTable(name) => write!(f, "font has no `{name}` table"),
+			FontError::AtlasFull          => write!(f, "glyphs don't fit into the atlas"),
+			FontError::Malformed(name)    => write!(f, "font has a malformed `{name}` table"),
+			FontError::Unsupported(what)  => write!(f, "font uses unsupported {what}"),
+		}
+	}
+}
+
+impl Error for FontError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		match self {
+			FontError::Io(e) => Some(e),
+			_ => None,
+		}
+	}
+}
+
+impl From<io::Error> for FontError {
+	fn from(e: io::Error) -> Self {
+		FontError::Io(e)
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GlyphId(pub u16);
+
+/// Outline command in font units, y goes up.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PathOp {
+	MoveTo([f32; 2]),
+	LineTo([f32; 2]),
+	QuadTo([f32; 2], [f32; 2]),
+	CubicTo([f32; 2], [f32; 2], [f32; 2]),
+	Close,
+}
+
+/// Coverage of a rasterized glyph, a byte per pixel, top row first.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Bitmap {
+	pub width:    u32,
+	pub height:   u32,
+	/// From the pen on the baseline to the top-left corner, y goes
+	/// down.
+	pub offset:   [f32; 2],
+	pub coverage: Vec<u8>,
+}
+
+/// RGBA8 distance field of a glyph as described by [`GlyphFormat`],
+/// top row first.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DistanceField {
+	pub width:  u32,
+	pub height: u32,
+	/// From the pen on the baseline to the top-left corner, y goes
+	/// down.
+	pub offset: [f32; 2],
+	pub pixels: Vec<u8>,
+}
+
+/// Glyphs of a face rasterized into an atlas, see [`Face::font`].
+#[derive(Clone, Debug, Default)]
+pub struct GlyphSet {
+	pub size:   f32,
+	pub format: GlyphFormat,
+	pub glyphs: Vec<(char, GlyphId, Glyph)>,
+}
+
+/// Parsed font file.
+pub struct Face {
+	data: Vec<u8>,
+
+	units_per_em:   f32,
+	ascent:         f32,
+	descent:        f32,
+	line_gap:       f32,
+	glyph_count:    u16,
+	h_metric_count: u16,
+	hmtx:           Table,
+	cmap:           Option<Cmap>,
+	kerning:        Kerning,
+	outlines:       Outlines,
+}
+
+/// Byte range of a table within the file.
+#[derive(Clone, Copy, Debug, Default)]
+struct Table {
+	start: usize,
+	end:   usize,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Cmap {
+	Format4(usize),
+	Format12(usize),
+}
+
+#[derive(Clone, Debug)]
+enum Kerning {
+	None,
+	/// Format 0 subtable of `kern`: pair count & the start of the pairs.
+	Kern { pairs: usize, start: usize },
+	/// Pair adjustment subtables of the GPOS `kern` lookups.
+	Gpos(Vec<usize>),
+}
+
+enum Outlines {
+	Glyf {
+		loca:      Table,
+		glyf:      Table,
+		long_loca: bool,
+	},
+	Cff(Cff),
+}
+
+struct Cff {
+	charstrings:  Index,
+	global_subrs: Index,
+	/// Local subroutines, per font dict of CID-keyed fonts.
+	local_subrs:  Vec<Index>,
+	/// Font dict of every glyph, empty unless CID-keyed.
+	fd_select:    Option<usize>,
+}
+
+/// CFF INDEX, absolute offsets of its `count + 1` entry boundaries are
+/// read on demand.
+#[derive(Clone, Copy, Debug, Default)]
+struct Index {
+	count:    usize,
+	off_size: usize,
+	// Start of the offset array.
+	offsets:  usize,
+	// Offsets are relative to the byte before this.
+	data:     usize,
+}
+
+impl Face {
+	pub fn from_file(path: impl AsRef<Path>) -> Result<Self, FontError> {
+		Self::parse(fs::read(path)?)
+	}
+
+	/// Parses a TrueType or an OpenType font, the first one of a
+	/// collection.
+	pub fn parse(data: Vec<u8>) -> Result<Self, FontError> {
+		let d = &data[..];
+
+		let mut sfnt = 0;
+		if d.get(0..4) == Some(b"ttcf") {
+			sfnt = u32_at(d, 12).ok_or(FontError::Malformed("ttcf"))? as usize;
+		}
+
+		let version = u32_at(d, sfnt).ok_or(FontError::Malformed("sfnt"))?;
+		if version != 0x0001_0000 && version != u32::from_be_bytes(*b"OTTO") && version != u32::from_be_bytes(*b"true") {
+			return Err(FontError::Unsupported("font format"));
+		}
+
+		let table = |tag: &'static str| -> Option<Table> {
+			let count = u16_at(d, sfnt + 4)? as usize;
+			(0..count).find_map(|i| {
+				let record = sfnt + 12 + i * 16;
+				if d.get(record..record + 4)? != tag.as_bytes() {
+					return None;
+				}
+				let start = u32_at(d, record + 8)? as usize;
+				let end   = start + u32_at(d, record + 12)? as usize;
+				(end <= d.len()).then_some(Table { start, end })
+			})
+		};
+		let required = |tag: &'static str| table(tag).ok_or(FontError::MissingTable(tag));
+
+		let head = required("head")?;
+		let hhea = required("hhea")?;
+		let maxp = required("maxp")?;
+		let hmtx = required("hmtx")?;
+
+		let units_per_em = u16_at(d, head.start + 18).ok_or(FontError::Malformed("head"))?;
+		if units_per_em == 0 {
+			return Err(FontError::Malformed("head"));
+		}
+		let long_loca = i16_at(d, head.start + 50).ok_or(FontError::Malformed("head"))? != 0;
+
+		let hhea_field = |at: usize| i16_at(d, hhea.start + at).ok_or(FontError::Malformed("hhea"));
+		let ascent         = hhea_field(4)?;
+		let descent        = hhea_field(6)?;
+		let line_gap       = hhea_field(8)?;
+		let h_metric_count = hhea_field(34)? as u16;
+
+		let glyph_count = u16_at(d, maxp.start + 4).ok_or(FontError::Malformed("maxp"))?;
+
+		if h_metric_count == 0 || hmtx.start + h_metric_count as usize * 4 > hmtx.end {
+			return Err(FontError::Malformed("hmtx"));
+		}
+
+		let cmap = match table("cmap") {
+			Some(cmap) => parse_cmap(d, cmap).ok_or(FontError::Malformed("cmap"))?,
+			None => None,
+		};
+
+		let gpos = match table("GPOS") {
+			Some(gpos) => parse_gpos_kerning(d, gpos).ok_or(FontError::Malformed("GPOS"))?,
+			None => Vec::new(),
+		};
+		let kerning = if !gpos.is_empty() {
+			Kerning::Gpos(gpos)
+		} else if let Some(kern) = table("kern") {
+			parse_kern(d, kern).ok_or(FontError::Malformed("kern"))?
+		} else {
+			Kerning::None
+		};
+
+		let outlines = if let (Some(loca), Some(glyf)) = (table("loca"), table("glyf")) {
+			Outlines::Glyf { loca, glyf, long_loca }
+		} else if let Some(cff) = table("CFF ") {
+			Outlines::Cff(parse_cff(d, cff)?)
+		} else {
+			return Err(FontError::MissingTable("glyf"));
+		};
+
+		Ok(Self {
+			data,
+			units_per_em: units_per_em as f32,
+			ascent:       ascent as f32,
+			descent:      descent as f32,
+			line_gap:     line_gap as f32,
+			glyph_count,
+			h_metric_count,
+			hmtx,
+			cmap,
+			kerning,
+			outlines,
+		})
+	}
+
+	pub fn units_per_em(&self) -> f32 {
+		self.units_per_em
+	}
+
+	pub fn glyph_count(&self) -> u16 {
+		self.glyph_count
+	}
+
+	/// Pixels per font unit at `size` pixels per em.
+	pub fn scale(&self, size: f32) -> f32 {
+		size / self.units_per_em
+	}
+
+	/// Ascent, descent & line gap at `size` pixels.
+	pub fn line_metrics(&self, size: f32) -> LineMetrics {
+		let scale = self.scale(size);
+		LineMetrics::new(self.ascent * scale, self.descent * scale, self.line_gap * scale)
+	}
+
+	/// `None` if the font has no glyph for `c`.
+	pub fn glyph_index(&self, c: char) -> Option<GlyphId> {
+		let d = &self.data[..];
+		let c = c as u32;
+
+		let glyph = match self.cmap? {
+			Cmap::Format4(at) => {
+				if c > 0xFFFF {
+					return None;
+				}
+				let c = c as u16;
+
+				let segments = u16_at(d, at + 6)? as usize / 2;
+				let ends     = at + 14;
+				let starts   = ends + segments * 2 + 2;
+				let deltas   = starts + segments * 2;
+				let ranges   = deltas + segments * 2;
+
+				let segment = (0..segments).find(|&i| u16_at(d, ends + i * 2).is_some_and(|end| end >= c))?;
+				let start   = u16_at(d, starts + segment * 2)?;
+				if c < start {
+					return None;
+				}
+
+				let delta  = u16_at(d, deltas + segment * 2)?;
+				let range  = ranges + segment * 2;
+				let offset = u16_at(d, range)? as usize;
+				if offset == 0 {
+					c.wrapping_add(delta)
+				} else {
+					let glyph = u16_at(d, range + offset + (c - start) as usize * 2)?;
+					if glyph == 0 {
+						return None;
+					}
+					glyph.wrapping_add(delta)
+				}
+			},
+			Cmap::Format12(at) => {
+				let groups = u32_at(d, at + 12)? as usize;
+				(0..groups).find_map(|i| {
+					let group = at + 16 + i * 12;
+					let start = u32_at(d, group)?;
+					let end   = u32_at(d, group + 4)?;
+					if !(start..=end).contains(&c) {
+						return None;
+					}
+					// Ids past the glyphs would be cut to 16 bits otherwise.
+					let glyph = u32_at(d, group + 8)?.checked_add(c - start)?;
+					(glyph < self.glyph_count as u32).then_some(glyph as u16)
+				})?
+			},
+		};
+
+		(glyph != 0 && glyph < self.glyph_count).then_some(GlyphId(glyph))
+	}
+
+	/// Horizontal advance in font units.
+	pub fn advance(&self, glyph: GlyphId) -> f32 {
+		let i = glyph.0.min(self.h_metric_count - 1) as usize;
+		u16_at(&self.data, self.hmtx.start + i * 4).unwrap_or(0) as f32
+	}
+
+	/// Adjustment of the advance of `left` followed by `right`, in font
+	/// units.
+	pub fn kerning(&self, left: GlyphId, right: GlyphId) -> f32 {
+		let d = &self.data[..];
+		match &self.kerning {
+			Kerning::None => 0.0,
+			Kerning::Kern { pairs, start } => {
+				let key = (left.0 as u32) << 16 | right.0 as u32;
+				let (mut lo, mut hi) = (0, *pairs);
+				while lo < hi {
+					let mid  = (lo + hi) / 2;
+					let pair = start + mid * 6;
+					let Some(k) = u32_at(d, pair) else {
+						return 0.0;
+					};
+					match k.cmp(&key) {
+						std::cmp::Ordering::Less    => lo = mid + 1,
+						std::cmp::Ordering::Greater => hi = mid,
+						std::cmp::Ordering::Equal   => return i16_at(d, pair + 4).unwrap_or(0) as f32,
+					}
+				}
+				0.0
+			},
+			Kerning::Gpos(subtables) => subtables
+				.iter()
+				.find_map(|&at| gpos_pair_adjustment(d, at, left.0, right.0))
+				.unwrap_or(0) as f32,
+		}
+	}
+
+	/// Outline in font units, empty for blank glyphs.
+	pub fn outline(&self, glyph: GlyphId) -> Result<Vec<PathOp>, FontError> {
+		if glyph.0 >= self.glyph_count {
+			return Ok(Vec::new());
+		}
+
+		let mut path = Vec::new();
+		match &self.outlines {
+			Outlines::Glyf { loca, glyf, long_loca } => {
+				let glyf = Glyf { data: &self.data, loca: *loca, glyf: *glyf, long_loca: *long_loca };
+				glyf.outline(glyph.0, [1.0, 0.0, 0.0, 1.0, 0.0, 0.0], 0, &mut path)
+					.ok_or(FontError::Malformed("glyf"))?;
+			},
+			Outlines::Cff(cff) => {
+				cff.outline(&self.data, glyph.0, &mut path)?;
+			},
+		}
+		Ok(path)
+	}
+
+	/// Coverage of `glyph` at `size` pixels per em.
+	pub fn rasterize(&self, glyph: GlyphId, size: f32) -> Result<Bitmap, FontError> {
+		self.rasterize_shifted(glyph, size, 0.0)
+	}
+
+	/// Same as [`Self::rasterize`], with the outline moved `shift`
+	/// pixels to the right, for pens between pixels. The offset is
+	/// still from the unmoved pen, so the bitmap lands on whole pixels
+	/// when drawn at the pen rounded down.
+	pub fn rasterize_shifted(&self, glyph: GlyphId, size: f32, shift: f32) -> Result<Bitmap, FontError> {
+		let mut lines = flatten(&self.outline(glyph)?, self.scale(size));
+		for p in lines.iter_mut().flatten() {
+			p[0] += shift;
+		}
+		Ok(rasterize(&lines))
+	}
+
+	/// Signed distances to the outline of `glyph` at `size` pixels per
+	/// em, `range` pixels at most, see [`GlyphFormat`]. With
+	/// `multi_channel` the RGB channels hold distances to differently
+	/// colored edges, otherwise they are white.
+	pub fn distance_field(&self, glyph: GlyphId, size: f32, range: f32, multi_channel: bool) -> Result<DistanceField, FontError> {
+		let contours = contours(&self.outline(glyph)?, self.scale(size));
+		Ok(distance_field(contours, range, multi_channel))
+	}
+
+	/// Rasterizes the glyphs of `chars` at `size` pixels into `atlas`.
+	/// Characters the font has no glyphs for are left out.
+	pub fn rasterize_glyphs(
+		&self,
+		atlas: &mut GlyphAtlas,
+		size: f32,
+		chars: impl IntoIterator<Item = char>,
+	) -> Result<GlyphSet, FontError> {
+		self.rasterize_glyphs_as(atlas, size, GlyphFormat::Coverage, chars)
+	}
+
+	/// Same as [`Self::rasterize_glyphs`], with the glyphs in `format`.
+	pub fn rasterize_glyphs_as(
+		&self,
+		atlas: &mut GlyphAtlas,
+		size: f32,
+		format: GlyphFormat,
+		chars: impl IntoIterator<Item = char>,
+	) -> Result<GlyphSet, FontError> {
+		let scale = self.scale(size);
+
+		let mut glyphs = Vec::new();
+		for c in chars {
+			let Some(id) = self.glyph_index(c) else {
+				continue;
+			};
+
+			let (rect, offset) = match format {
+				GlyphFormat::Coverage => {
+					let bitmap = self.rasterize(id, size)?;
+					(atlas.insert(bitmap.width, bitmap.height, &bitmap.coverage), bitmap.offset)
+				},
+				GlyphFormat::Sdf { range } | GlyphFormat::Msdf { range } => {
+					let field = self.distance_field(id, size, range, matches!(format, GlyphFormat::Msdf { .. }))?;
+					(atlas.insert_rgba(field.width, field.height, &field.pixels), field.offset)
+				},
+			};
+			let glyph = Glyph {
+				rect:    rect.ok_or(FontError::AtlasFull)?,
+				offset,
+				advance: self.advance(id) * scale,
+			};
+			glyphs.push((c, id, glyph));
+		}
+
+		Ok(GlyphSet { size, format, glyphs })
+	}
+
+	/// Font drawing `glyphs` out of `atlas` uploaded as `texture`, along
+	/// with the kerning between them.
+	pub fn font(&self, texture: TextureId, atlas: &GlyphAtlas, glyphs: &GlyphSet) -> Font {
+		let scale = self.scale(glyphs.size);
+
+		let mut font = Font::new(texture, (atlas.width, atlas.height), glyphs.size, self.line_metrics(glyphs.size))
+			.with_format(glyphs.format);
+		for (c, _, glyph) in &glyphs.glyphs {
+			font.add_glyph(*c, *glyph);
+		}
+
+		if !matches!(self.kerning, Kerning::None) {
+			for (left, left_id, _) in &glyphs.glyphs {
+				for (right, right_id, _) in &glyphs.glyphs {
+					let kerning = self.kerning(*left_id, *right_id);
+					if kerning != 0.0 {
+						font.add_kerning(*left, *right, kerning * scale);
+					}
+				}
+			}
+		}
+
+		font
+	}
+}
+
+fn parse_cmap(d: &[u8], cmap: Table) -> Option<Option<Cmap>> {
+	let count = u16_at(d, cmap.start + 2)? as usize;
+
+	let mut format4  = None;
+	let mut format12 = None;
+	for i in 0..count {
+		let record   = cmap.start + 4 + i * 8;
+		let platform = u16_at(d, record)?;
+		let encoding = u16_at(d, record + 2)?;
+		let at       = cmap.start + u32_at(d, record + 4)? as usize;
+
+		// Unicode, either explicitly or as Windows' BMP & full
+		// repertoire.
+		let unicode = platform == 0 || (platform == 3 && (encoding == 1 || encoding == 10));
+		if !unicode {
+			continue;
+		}
+
+		match u16_at(d, at)? {
+			4  => format4  = format4.or(Some(Cmap::Format4(at))),
+			12 => format12 = format12.or(Some(Cmap::Format12(at))),
+			_ => {},
+		}
+	}
+
+	Some(format12.or(format4))
+}
+
+fn parse_kern(d: &[u8], kern: Table) -> Option<Kerning> {
+	// Only the Microsoft flavour, Apple's starts with a 32-bit version.
+	if u16_at(d, kern.start)? != 0 {
+		return Some(Kerning::None);
+	}
+
+	let count = u16_at(d, kern.start + 2)? as usize;
+	let mut at = kern.start + 4;
+	for _ in 0..count {
+		let length   = u16_at(d, at + 2)? as usize;
+		let coverage = u16_at(d, at + 4)?;
+		let format   = coverage >> 8;
+		// Horizontal, not minimum values nor cross-stream.
+		if format == 0 && coverage & 0x7 == 0x1 {
+			// The length is often wrong for huge subtables, the pair
+			// count is not.
+			let pairs = u16_at(d, at + 6)? as usize;
+			let start = at + 14;
+			if start + pairs * 6 > d.len() {
+				return None;
+			}
+			return Some(Kerning::Kern { pairs, start });
+		}
+		at += length;
+	}
+
+	Some(Kerning::None)
+}
+
+/// Pair adjustment subtables of the lookups behind the `kern`
+/// features.
+fn parse_gpos_kerning(d: &[u8], gpos: Table) -> Option<Vec<usize>> {
+	let features = gpos.start + u16_at(d, gpos.start + 6)? as usize;
+	let lookups  = gpos.start + u16_at(d, gpos.start + 8)? as usize;
+
+	let mut lookup_indices = Vec::new();
+	let feature_count = u16_at(d, features)? as usize;
+	for i in 0..feature_count {
+		let record = features + 2 + i * 6;
+		if d.get(record..record + 4)? != b"kern" {
+			continue;
+		}
+		let feature = features + u16_at(d, record + 4)? as usize;
+		let count   = u16_at(d, feature + 2)? as usize;
+		for j in 0..count {
+			lookup_indices.push(u16_at(d, feature + 4 + j * 2)? as usize);
+		}
+	}
+	lookup_indices.sort_unstable();
+	lookup_indices.dedup();
+
+	let mut subtables = Vec::new();
+	for i in lookup_indices {
+		let lookup = lookups + u16_at(d, lookups + 2 + i * 2)? as usize;
+		let kind   = u16_at(d, lookup)?;
+		let count  = u16_at(d, lookup + 4)? as usize;
+		for j in 0..count {
+			let mut subtable = lookup + u16_at(d, lookup + 6 + j * 2)? as usize;
+			let mut kind     = kind;
+			// Extension, points at the real subtable.
+			if kind == 9 {
+				kind      = u16_at(d, subtable + 2)?;
+				subtable += u32_at(d, subtable + 4)? as usize;
+			}
+			if kind == 2 {
+				subtables.push(subtable);
+			}
+		}
+	}
+
+	Some(subtables)
+}
+
+/// X advance adjustment of the first glyph by a PairPos subtable, if
+/// it covers the pair.
+fn gpos_pair_adjustment(d: &[u8], at: usize, left: u16, right: u16) -> Option<i16> {
+	let format   = u16_at(d, at)?;
+	let coverage = coverage_index(d, at + u16_at(d, at + 2)? as usize, left)?;
+	let format1  = u16_at(d, at + 4)?;
+	let format2  = u16_at(d, at + 6)?;
+
+	let size1 = format1.count_ones() as usize * 2;
+	let size2 = format2.count_ones() as usize * 2;
+	// Placements go before the advance.
+	let x_advance = |record: usize| -> Option<i16> {
+		if format1 & 0x4 == 0 {
+			return Some(0);
+		}
+		i16_at(d, record + (format1 & 0x3).count_ones() as usize * 2)
+	};
+
+	match format {
+		1 => {
+			let set   = at + u16_at(d, at + 10 + coverage * 2)? as usize;
+			let count = u16_at(d, set)? as usize;
+			let pair  = size1 + size2 + 2;
+			(0..count).find_map(|i| {
+				let record = set + 2 + i * pair;
+				(u16_at(d, record)? == right).then(|| x_advance(record + 2))?
+			})
+		},
+		2 => {
+			let class1 = class_of(d, at + u16_at(d, at + 8)? as usize, left)? as usize;
+			let class2 = class_of(d, at + u16_at(d, at + 10)? as usize, right)? as usize;
+			let class2_count = u16_at(d, at + 14)? as usize;
+			let record = at + 16 + (class1 * class2_count + class2) * (size1 + size2);
+			x_advance(record)
+		},
+		_ => None,
+	}
+}
+
+fn coverage_index(d: &[u8], at: usize, glyph: u16) -> Option<usize> {
+	let count = u16_at(d, at + 2)? as usize;
+	match u16_at(d, at)? {
+		1 => (0..count).find(|&i| u16_at(d, at + 4 + i * 2) == Some(glyph)),
+		2 => (0..count).find_map(|i| {
+			let range = at + 4 + i * 6;
+			let start = u16_at(d, range)?;
+			let end   = u16_at(d, range + 2)?;
+			let index = u16_at(d, range + 4)?;
+			(start..=end).contains(&glyph).then(|| index as usize + (glyph - start) as usize)
+		}),
+		_ => None,
+	}
+}
+
+/// Glyphs missing from a class definition are of class 0.
+fn class_of(d: &[u8], at: usize, glyph: u16) -> Option<u16> {
+	match u16_at(d, at)? {
+		1 => {
+			let start = u16_at(d, at + 2)?;
+			let count = u16_at(d, at + 4)?;
+			if glyph < start || glyph - start >= count {
+				return Some(0);
+			}
+			u16_at(d, at + 6 + (glyph - start) as usize * 2)
+		},
+		2 => {
+			let count = u16_at(d, at + 2)? as usize;
+			Some((0..count).find_map(|i| {
+				let range = at + 4 + i * 6;
+				let start = u16_at(d, range)?;
+				let end   = u16_at(d, range + 2)?;
+				(start..=end).contains(&glyph).then(|| u16_at(d, range + 4))?
+			}).unwrap_or(0))
+		},
+		_ => None,
+	}
+}
+
+/// TrueType outlines.
+struct Glyf<'a> {
+	data:      &'a [u8],
+	loca:      Table,
+	glyf:      Table,
+	long_loca: bool,
+}
+
+impl Glyf<'_> {
+	fn range(&self, glyph: u16) -> Option<(usize, usize)> {
+		let d = self.data;
+		let i = glyph as usize;
+		let (start, end) = if self.long_loca {
+			(u32_at(d, self.loca.start + i * 4)? as usize, u32_at(d, self.loca.start + i * 4 + 4)? as usize)
+		} else {
+			(u16_at(d, self.loca.start + i * 2)? as usize * 2, u16_at(d, self.loca.start + i * 2 + 2)? as usize * 2)
+		};
+		let (start, end) = (self.glyf.start + start, self.glyf.start + end);
+		(start <= end && end <= self.glyf.end).then_some((start, end))
+	}
+
+	/// Appends the outline of `glyph` transformed by the 2x3 matrix
+	/// `m` (column-major, translation last).
+	fn outline(&self, glyph: u16, m: [f32; 6], depth: u32, path: &mut Vec<PathOp>) -> Option<()> {
+		if depth > MAX_DEPTH {
+			return None;
+		}
+
+		let (start, end) = self.range(glyph)?;
+		if start == end {
+			return Some(());
+		}
+
+		let d = self.data;
+		let contours = i16_at(d, start)?;
+		if contours >= 0 {
+			self.simple(start, contours as usize, m, path)
+		} else {
+			self.composite(start + 10, m, depth, path)
+		}
+	}
+
+	fn simple(&self, start: usize, contours: usize, m: [f32; 6], path: &mut Vec<PathOp>) -> Option<()> {
+		let d = self.data;
+
+		let ends_at = start + 10;
+		let points  = if contours == 0 { 0 } else { u16_at(d, ends_at + (contours - 1) * 2)? as usize + 1 };
+		let instructions = u16_at(d, ends_at + contours * 2)? as usize;
+		let mut at = ends_at + contours * 2 + 2 + instructions;
+
+		const ON_CURVE: u8 = 0x01;
+		const X_SHORT:  u8 = 0x02;
+		const Y_SHORT:  u8 = 0x04;
+		const REPEAT:   u8 = 0x08;
+		const X_SAME:   u8 = 0x10;
+		const Y_SAME:   u8 = 0x20;
+
+		let mut flags = Vec::with_capacity(points);
+		while flags.len() < points {
+			let flag = *d.get(at)?;
+			at += 1;
+			flags.push(flag);
+			if flag & REPEAT != 0 {
+				let repeat = *d.get(at)?;
+				at += 1;
+				for _ in 0..repeat {
+					flags.push(flag);
+				}
+			}
+		}
+		flags.truncate(points);
+
+		let mut coords = vec![[0.0f32; 2]; points];
+		for (axis, short, same) in [(0, X_SHORT, X_SAME), (1, Y_SHORT, Y_SAME)] {
+			let mut v = 0i32;
+			for (flag, coord) in flags.iter().zip(coords.iter_mut()) {
+				if flag & short != 0 {
+					let delta = *d.get(at)? as i32;
+					at += 1;
+					v += if flag & same != 0 { delta } else { -delta };
+				} else if flag & same == 0 {
+					v += i16_at(d, at)? as i32;
+					at += 2;
+				}
+				coord[axis] = v as f32;
+			}
+		}
+
+		let transform = |p: [f32; 2]| [
+			m[0] * p[0] + m[2] * p[1] + m[4],
+			m[1] * p[0] + m[3] * p[1] + m[5],
+		];
+
+		let mut first = 0;
+		for c in 0..contours {
+			let last = u16_at(d, ends_at + c * 2)? as usize;
+			if last < first || last >= points {
+				return None;
+			}
+
+			let contour: Vec<_> = (first..=last)
+				.map(|i| (transform(coords[i]), flags[i] & ON_CURVE != 0))
+				.collect();
+			first = last + 1;
+
+			push_quadratic_contour(&contour, path);
+		}
+
+		Some(())
+	}
+
+	fn composite(&self, mut at: usize, m: [f32; 6], depth: u32, path: &mut Vec<PathOp>) -> Option<()> {
+		let d = self.data;
+
+		const ARGS_ARE_WORDS:  u16 = 0x0001;
+		const ARGS_ARE_XY:     u16 = 0x0002;
+		const HAS_SCALE:       u16 = 0x0008;
+		const MORE_COMPONENTS: u16 = 0x0020;
+		const HAS_XY_SCALE:    u16 = 0x0040;
+		const HAS_2X2:         u16 = 0x0080;
+
+		loop {
+			let flags = u16_at(d, at)?;
+			let glyph = u16_at(d, at + 2)?;
+			at += 4;
+
+			let (dx, dy) = if flags & ARGS_ARE_WORDS != 0 {
+				at += 4;
+				(i16_at(d, at - 4)? as f32, i16_at(d, at - 2)? as f32)
+			} else {
+				at += 2;
+				(*d.get(at - 2)? as i8 as f32, *d.get(at - 1)? as i8 as f32)
+			};
+			if flags & ARGS_ARE_XY == 0 {
+				return None;
+			}
+
+			let f2dot14 = |at: usize| i16_at(d, at).map(|v| v as f32 / 16384.0);
+			let (a, b, c, e) = if flags & HAS_SCALE != 0 {
+				at += 2;
+				let s = f2dot14(at - 2)?;
+				(s, 0.0, 0.0, s)
+			} else if flags & HAS_XY_SCALE != 0 {
+				at += 4;
+				(f2dot14(at - 4)?, 0.0, 0.0, f2dot14(at - 2)?)
+			} else if flags & HAS_2X2 != 0 {
+				at += 8;
+				(f2dot14(at - 8)?, f2dot14(at - 6)?, f2dot14(at - 4)?, f2dot14(at - 2)?)
+			} else {
+				(1.0, 0.0, 0.0, 1.0)
+			};
+
+			// Component transform first, then the parent one.
+			let child = [
+				m[0] * a + m[2] * b,
+				m[1] * a + m[3] * b,
+				m[0] * c + m[2] * e,
+				m[1] * c + m[3] * e,
+				m[0] * dx + m[2] * dy + m[4],
+				m[1] * dx + m[3] * dy + m[5],
+			];
+			self.outline(glyph, child, depth + 1, path)?;
+
+			if flags & MORE_COMPONENTS == 0 {
+				return Some(());
+			}
+		}
+	}
+}
+
+/// TrueType contour of on & off-curve points, two off-curve points in
+/// a row imply an on-curve one right between them.
+fn push_quadratic_contour(points: &[([f32; 2], bool)], path: &mut Vec<PathOp>) {
+	let Some(start) = points.iter().position(|p| p.1) else {
+		// All off-curve, starts in between the first two.
+		if let [a, b, ..] = points {
+			let mid = midpoint(a.0, b.0);
+			path.push(PathOp::MoveTo(mid));
+			let mut control = None;
+			for p in points.iter().cycle().skip(1).take(points.len()) {
+				push_quadratic_point(p.0, false, &mut control, path);
+			}
+			if let Some(c) = control {
+				path.push(PathOp::QuadTo(c, mid));
+			}
+			path.push(PathOp::Close);
+		}
+		return;
+	};
+
+	let first = points[start].0;
+	path.push(PathOp::MoveTo(first));
+
+	let mut control = None;
+	for i in 1..points.len() {
+		let (p, on_curve) = points[(start + i) % points.len()];
+		push_quadratic_point(p, on_curve, &mut control, path);
+	}
+	match control {
+		Some(c) => path.push(PathOp::QuadTo(c, first)),
+		None    => path.push(PathOp::LineTo(first)),
+	}
+	path.push(PathOp::Close);
+}
+
+fn push_quadratic_point(p: [f32; 2], on_curve: bool, control: &mut Option<[f32; 2]>, path: &mut Vec<PathOp>) {
+	match (control.take(), on_curve) {
+		(None, true)     => path.push(PathOp::LineTo(p)),
+		(None, false)    => *control = Some(p),
+		(Some(c), true)  => path.push(PathOp::QuadTo(c, p)),
+		(Some(c), false) => {
+			path.push(PathOp::QuadTo(c, midpoint(c, p)));
+			*control = Some(p);
+		},
+	}
+}
+
+fn midpoint(a: [f32; 2], b: [f32; 2]) -> [f32; 2] {
+	[(a[0] + b[0]) * 0.5, (a[1] + b[1]) * 0.5]
+}
+
+fn parse_cff(d: &[u8], cff: Table) -> Result<Cff, FontError> {
+	let malformed = || FontError::Malformed("CFF ");
+	let d = &d[..cff.end];
+
+	let header_size  = *d.get(cff.start + 2).ok_or_else(malformed)? as usize;
+	let names        = Index::parse(d, cff.start + header_size).ok_or_else(malformed)?;
+	let top_dicts    = Index::parse(d, names.end(d).ok_or_else(malformed)?).ok_or_else(malformed)?;
+	let strings      = Index::parse(d, top_dicts.end(d).ok_or_else(malformed)?).ok_or_else(malformed)?;
+	let global_subrs = Index::parse(d, strings.end(d).ok_or_else(malformed)?).ok_or_else(malformed)?;
+
+	let dict = |index: &Index, i: usize| {
+		index.get(d, i).and_then(|(start, end)| parse_dict(&d[start..end])).ok_or_else(malformed)
+	};
+	let top = dict(&top_dicts, 0)?;
+	let operand = |op: u16| top.iter().find(|(o, _)| *o == op).and_then(|(_, args)| args.first().copied());
+
+	if operand(12 << 8 | 6).unwrap_or(2.0) != 2.0 {
+		return Err(FontError::Unsupported("charstring type"));
+	}
+
+	let charstrings = operand(17).ok_or_else(malformed)?;
+	let charstrings = Index::parse(d, cff.start + charstrings as usize).ok_or_else(malformed)?;
+
+	let private_subrs = |dict: &[(u16, Vec<f32>)]| -> Result<Index, FontError> {
+		let Some((_, private)) = dict.iter().find(|(o, _)| *o == 18) else {
+			return Ok(Index::default());
+		};
+		let &[size, offset] = &private[..] else {
+			return Err(malformed());
+		};
+		let start   = cff.start + offset as usize;
+		let private = d.get(start..start + size as usize).and_then(parse_dict).ok_or_else(malformed)?;
+		// Local subroutines are relative to the private dict.
+		match private.iter().find(|(o, _)| *o == 19).and_then(|(_, args)| args.first()) {
+			Some(subrs) => Index::parse(d, start + *subrs as usize).ok_or_else(malformed),
+			None => Ok(Index::default()),
+		}
+	};
+
+	// CID-keyed fonts have a private dict per font dict.
+	let (local_subrs, fd_select) = match (operand(12 << 8 | 36), operand(12 << 8 | 37)) {
+		(Some(fd_array), Some(fd_select)) => {
+			let fd_array = Index::parse(d, cff.start + fd_array as usize).ok_or_else(malformed)?;
+			let local_subrs = (0..fd_array.count)
+				.map(|i| private_subrs(&dict(&fd_array, i)?))
+				.collect::<Result<_, _>>()?;
+			(local_subrs, Some(cff.start + fd_select as usize))
+		},
+		_ => (vec![private_subrs(&top)?], None),
+	};
+
+	Ok(Cff { charstrings, global_subrs, local_subrs, fd_select })
+}
+
+/// Operators & their operands, 2-byte operators are `12 << 8 | b1`.
+fn parse_dict(d: &[u8]) -> Option<Vec<(u16, Vec<f32>)>> {
+	let mut entries  = Vec::new();
+	let mut operands = Vec::new();
+	let mut at = 0;
+	while at < d.len() {
+		let b0 = d[at];
+		at += 1;
+		match b0 {
+			0..=21 => {
+				let op = if b0 == 12 {
+					at += 1;
+					12 << 8 | *d.get(at - 1)? as u16
+				} else {
+					b0 as u16
+				};
+				entries.push((op, std::mem::take(&mut operands)));
+			},
+			28 => {
+				operands.push(i16_at(d, at)? as f32);
+				at += 2;
+			},
+			29 => {
+				operands.push(u32_at(d, at)? as i32 as f32);
+				at += 4;
+			},
+			30 => {
+				let mut s = String::new();
+				'real: loop {
+					let b = *d.get(at)?;
+					at += 1;
+					for nibble in [b >> 4, b & 0xF] {
+						match nibble {
+							0..=9 => s.push((b'0' + nibble) as char),
+							0xA   => s.push('.'),
+							0xB   => s.push('E'),
+							0xC   => s.push_str("E-"),
+							0xE   => s.push('-'),
+							0xF   => break 'real,
+							_     => {},
+						}
+					}
+				}
+				operands.push(s.parse().ok()?);
+			},
+			32..=246  => operands.push(b0 as f32 - 139.0),
+			247..=250 => {
+				operands.push((b0 as f32 - 247.0) * 256.0 + *d.get(at)? as f32 + 108.0);
+				at += 1;
+			},
+			251..=254 => {
+				operands.push(-(b0 as f32 - 251.0) * 256.0 - *d.get(at)? as f32 - 108.0);
+				at += 1;
+			},
+			_ => return None,
+		}
+	}
+	Some(entries)
+}
+
+impl Index {
+	fn parse(d: &[u8], at: usize) -> Option<Self> {
+		let count = u16_at(d, at)? as usize;
+		if count == 0 {
+			return Some(Self { count: 0, off_size: 0, offsets: at + 2, data: at + 2 });
+		}
+		let off_size = *d.get(at + 2)? as usize;
+		if !(1..=4).contains(&off_size) {
+			return None;
+		}
+		let offsets = at + 3;
+		let data    = offsets + (count + 1) * off_size - 1;
+		Some(Self { count, off_size, offsets, data })
+	}
+
+	fn offset(&self, d: &[u8], i: usize) -> Option<usize> {
+		let at    = self.offsets + i * self.off_size;
+		let bytes = d.get(at..at + self.off_size)?;
+		Some(self.data + bytes.iter().fold(0, |v, b| v << 8 | *b as usize))
+	}
+
+	/// Byte range of the `i`-th entry.
+	fn get(&self, d: &[u8], i: usize) -> Option<(usize, usize)> {
+		if i >= self.count {
+			return None;
+		}
+		let start = self.offset(d, i)?;
+		let end   = self.offset(d, i + 1)?;
+		(start <= end && end <= d.len()).then_some((start, end))
+	}
+
+	/// First byte after the index.
+	fn end(&self, d: &[u8]) -> Option<usize> {
+		if self.count == 0 {
+			return Some(self.data);
+		}
+		self.offset(d, self.count)
+	}
+
+	/// Added to the subroutine numbers of the charstrings.
+	fn bias(&self) -> i32 {
+		match self.count {
+			0..=1239    => 107,
+			1240..=33899 => 1131,
+			_           => 32768,
+		}
+	}
+}
+
+impl Cff {
+	fn outline(&self, d: &[u8], glyph: u16, path: &mut Vec<PathOp>) -> Result<(), FontError> {
+		let malformed = || FontError::Malformed("CFF ");
+
+		let fd = match self.fd_select {
+			Some(at) => fd_index(d, at, glyph).ok_or_else(malformed)?,
+			None => 0,
+		};
+		let local_subrs = self.local_subrs.get(fd).ok_or_else(malformed)?;
+
+		let (start, end) = self.charstrings.get(d, glyph as usize).ok_or_else(malformed)?;
+
+		let mut state = Charstring {
+			d,
+			global_subrs: &self.global_subrs,
+			local_subrs,
+			path,
+			stack:   Vec::with_capacity(48),
+			pen:     [0.0; 2],
+			open:    false,
+			stems:   0,
+			width:   false,
+			done:    false,
+		};
+		state.run(start, end, 0).ok_or_else(malformed)?;
+		if state.open {
+			state.path.push(PathOp::Close);
+		}
+
+		Ok(())
+	}
+}
+
+/// Font dict of `glyph` in a CID-keyed font.
+fn fd_index(d: &[u8], at: usize, glyph: u16) -> Option<usize> {
+	match *d.get(at)? {
+		0 => d.get(at + 1 + glyph as usize).map(|fd| *fd as usize),
+		3 => {
+			let ranges = u16_at(d, at + 1)? as usize;
+			(0..ranges).find_map(|i| {
+				let range = at + 3 + i * 3;
+				let first = u16_at(d, range)?;
+				let next  = u16_at(d, range + 3)?;
+				(first..next).contains(&glyph).then(|| d.get(range + 2).map(|fd| *fd as usize))?
+			})
+		},
+		_ => None,
+	}
+}
+
+/// Type 2 charstring interpreter.
+struct Charstring<'a> {
+	d:            &'a [u8],
+	global_subrs: &'a Index,
+	local_subrs:  &'a Index,
+	path:         &'a mut Vec<PathOp>,
+	stack:        Vec<f32>,
+	pen:          [f32; 2],
+	// A contour has been started & not closed yet.
+	open:         bool,
+	stems:        usize,
+	// The optional width before the first stack clearing operator is
+	// dealt with.
+	width:        bool,
+	done:         bool,
+}
+
+impl Charstring<'_> {
+	fn run(&mut self, mut at: usize, end: usize, depth: u32) -> Option<()> {
+		if depth > MAX_DEPTH {
+			return None;
+		}
+
+		let d = self.d;
+		while at < end && !self.done {
+			let b0 = d[at];
+			at += 1;
+			match b0 {
+				28 => {
+					self.stack.push(i16_at(d, at)? as f32);
+					at += 2;
+				},
+				32..=246  => self.stack.push(b0 as f32 - 139.0),
+				247..=250 => {
+					self.stack.push((b0 as f32 - 247.0) * 256.0 + *d.get(at)? as f32 + 108.0);
+					at += 1;
+				},
+				251..=254 => {
+					self.stack.push(-(b0 as f32 - 251.0) * 256.0 - *d.get(at)? as f32 - 108.0);
+					at += 1;
+				},
+				255 => {
+					self.stack.push(u32_at(d, at)? as i32 as f32 / 65536.0);
+					at += 4;
+				},
+				// callsubr & callgsubr
+				10 | 29 => {
+					let subrs = if b0 == 10 { self.local_subrs } else { self.global_subrs };
+					let i = self.stack.pop()? as i32 + subrs.bias();
+					let (start, end) = subrs.get(d, usize::try_from(i).ok()?)?;
+					self.run(start, end, depth + 1)?;
+				},
+				// return
+				11 => return Some(()),
+				// hintmask & cntrmask
+				19 | 20 => {
+					// Pending arguments are implicit vstems.
+					self.stem_hints();
+					at += self.stems.div_ceil(8);
+				},
+				12 => {
+					let b1 = *d.get(at)?;
+					at += 1;
+					self.flex(b1)?;
+				},
+				_ => self.operator(b0)?,
+			}
+		}
+
+		Some(())
+	}
+
+	/// Takes the width off the stack if an operator taking `args`
+	/// arguments (or pairs of them if `even`) got one more.
+	fn take_width(&mut self, even: bool, args: usize) {
+		if self.width {
+			return;
+		}
+		self.width = true;
+		let extra = if even { self.stack.len() % 2 == 1 } else { self.stack.len() > args };
+		if extra {
+			self.stack.remove(0);
+		}
+	}
+
+	fn stem_hints(&mut self) {
+		self.take_width(true, 0);
+		self.stems += self.stack.len() / 2;
+		self.stack.clear();
+	}
+
+	fn move_to(&mut self, dx: f32, dy: f32) {
+		if self.open {
+			self.path.push(PathOp::Close);
+		}
+		self.pen = [self.pen[0] + dx, self.pen[1] + dy];
+		self.path.push(PathOp::MoveTo(self.pen));
+		self.open = true;
+	}
+
+	fn line_to(&mut self, dx: f32, dy: f32) {
+		self.pen = [self.pen[0] + dx, self.pen[1] + dy];
+		self.path.push(PathOp::LineTo(self.pen));
+	}
+
+	fn curve_to(&mut self, d: [f32; 6]) {
+		let c1  = [self.pen[0] + d[0], self.pen[1] + d[1]];
+		let c2  = [c1[0] + d[2], c1[1] + d[3]];
+		self.pen = [c2[0] + d[4], c2[1] + d[5]];
+		self.path.push(PathOp::CubicTo(c1, c2, self.pen));
+	}
+
+	fn operator(&mut self, op: u8) -> Option<()> {
+		match op {
+			// hstem, vstem, hstemhm & vstemhm
+			1 | 3 | 18 | 23 => self.stem_hints(),
+			// rmoveto
+			21 => {
+				self.take_width(false, 2);
+				let [dx, dy] = self.stack.get(..2)?.try_into().ok()?;
+				self.move_to(dx, dy);
+			},
+			// hmoveto & vmoveto
+			22 | 4 => {
+				self.take_width(false, 1);
+				let v = *self.stack.first()?;
+				if op == 22 { self.move_to(v, 0.0) } else { self.move_to(0.0, v) }
+			},
+			// rlineto
+			5 => {
+				for pair in self.stack.clone().chunks_exact(2) {
+					self.line_to(pair[0], pair[1]);
+				}
+			},
+			// hlineto & vlineto, alternating
+			6 | 7 => {
+				let mut horizontal = op == 6;
+				for v in self.stack.clone() {
+					if horizontal { self.line_to(v, 0.0) } else { self.line_to(0.0, v) }
+					horizontal = !horizontal;
+				}
+			},
+			// rrcurveto
+			8 => {
+				for d in self.stack.clone().chunks_exact(6) {
+					self.curve_to(d.try_into().ok()?);
+				}
+			},
+			// rcurveline
+			24 => {
+				let s = self.stack.clone();
+				let curves = s.len().checked_sub(2)? / 6;
+				for d in s.chunks_exact(6).take(curves) {
+					self.curve_to(d.try_into().ok()?);
+				}
+				self.line_to(s[curves * 6], s[curves * 6 + 1]);
+			},
+			// rlinecurve
+			25 => {
+				let s = self.stack.clone();
+				let lines = s.len().checked_sub(6)? / 2;
+				for pair in s.chunks_exact(2).take(lines) {
+					self.line_to(pair[0], pair[1]);
+				}
+				self.curve_to(s[lines * 2..lines * 2 + 6].try_into().ok()?);
+			},
+			// hhcurveto & vvcurveto
+			27 | 26 => {
+				let mut s = &self.stack.clone()[..];
+				let mut first = 0.0;
+				if s.len() % 4 == 1 {
+					first = s[0];
+					s = &s[1..];
+				}
+				for d in s.chunks_exact(4) {
+					if op == 27 {
+						self.curve_to([d[0], first, d[1], d[2], d[3], 0.0]);
+					} else {
+						self.curve_to([first, d[0], d[1], d[2], 0.0, d[3]]);
+					}
+					first = 0.0;
+				}
+			},
+			// hvcurveto & vhcurveto, alternating
+			31 | 30 => {
+				let s = self.stack.clone();
+				let mut horizontal = op == 31;
+				let mut i = 0;
+				while i + 4 <= s.len() {
+					// The last one may have an extra coordinate.
+					let last  = if s.len() - (i + 4) == 1 { s[i + 4] } else { 0.0 };
+					let d = &s[i..i + 4];
+					if horizontal {
+						self.curve_to([d[0], 0.0, d[1], d[2], last, d[3]]);
+					} else {
+						self.curve_to([0.0, d[0], d[1], d[2], d[3], last]);
+					}
+					horizontal = !horizontal;
+					i += 4;
+				}
+			},
+			// endchar
+			14 => {
+				self.take_width(false, 0);
+				if self.stack.len() >= 4 {
+					// Accented characters of the standard encoding.
+					return None;
+				}
+				self.done = true;
+			},
+			_ => return None,
+		}
+
+		self.stack.clear();
+		Some(())
+	}
+
+	fn flex(&mut self, op: u8) -> Option<()> {
+		let s = std::mem::take(&mut self.stack);
+		match (op, &s[..]) {
+			// flex
+			(35, [d @ .., _fd]) if d.len() == 12 => {
+				self.curve_to(d[..6].try_into().ok()?);
+				self.curve_to(d[6..].try_into().ok()?);
+			},
+			// hflex
+			(34, &[dx1, dx2, dy2, dx3, dx4, dx5, dx6]) => {
+				self.curve_to([dx1, 0.0, dx2, dy2, dx3, 0.0]);
+				self.curve_to([dx4, 0.0, dx5, -dy2, dx6, 0.0]);
+			},
+			// hflex1
+			(36, &[dx1, dy1, dx2, dy2, dx3, dx4, dx5, dy5, dx6]) => {
+				self.curve_to([dx1, dy1, dx2, dy2, dx3, 0.0]);
+				self.curve_to([dx4, 0.0, dx5, dy5, dx6, -(dy1 + dy2 + dy5)]);
+			},
+			// flex1, the last coordinate goes along the longer axis.
+			(37, &[dx1, dy1, dx2, dy2, dx3, dy3, dx4, dy4, dx5, dy5, d6]) => {
+				let dx = dx1 + dx2 + dx3 + dx4 + dx5;
+				let dy = dy1 + dy2 + dy3 + dy4 + dy5;
+				let (dx6, dy6) = if dx.abs() > dy.abs() { (d6, -dy) } else { (-dx, d6) };
+				self.curve_to([dx1, dy1, dx2, dy2, dx3, dy3]);
+				self.curve_to([dx4, dy4, dx5, dy5, dx6, dy6]);
+			},
+			_ => return None,
+		}
+		Some(())
+	}
+}
+
+/// Contours of `path` in pixels, y going down. Every command of the
+/// path is an edge, flattened into a polyline starting where the
+/// previous one ends.
+fn contours(path: &[PathOp], scale: f32) -> Vec<Vec<Vec<[f32; 2]>>> {
+	let to_pixels = |p: [f32; 2]| [p[0] * scale, -p[1] * scale];
+
+	let mut contours = Vec::new();
+	let mut edges: Vec<Vec<[f32; 2]>> = Vec::new();
+	let mut start = [0.0; 2];
+	let mut pen   = [0.0; 2];
+	for op in path {
+		match *op {
+			PathOp::MoveTo(p) => {
+				if !edges.is_empty() {
+					contours.push(std::mem::take(&mut edges));
+				}
+				start = to_pixels(p);
+				pen   = start;
+			},
+			PathOp::LineTo(p) => {
+				let p = to_pixels(p);
+				edges.push(vec![pen, p]);
+				pen = p;
+			},
+			PathOp::QuadTo(c, p) => {
+				let (c, p) = (to_pixels(c), to_pixels(p));
+				let dd = length([pen[0] - 2.0 * c[0] + p[0], pen[1] - 2.0 * c[1] + p[1]]);
+				let n  = curve_segments(dd * 0.25);
+				let mut edge = vec![pen];
+				for i in 1..=n {
+					let t  = i as f32 / n as f32;
+					let mt = 1.0 - t;
+					edge.push([
+						mt * mt * pen[0] + 2.0 * mt * t * c[0] + t * t * p[0],
+						mt * mt * pen[1] + 2.0 * mt * t * c[1] + t * t * p[1],
+					]);
+				}
+				edges.push(edge);
+				pen = p;
+			},
+			PathOp::CubicTo(c1, c2, p) => {
+				let (c1, c2, p) = (to_pixels(c1), to_pixels(c2), to_pixels(p));
+				let dd0 = length([pen[0] - 2.0 * c1[0] + c2[0], pen[1] - 2.0 * c1[1] + c2[1]]);
+				let dd1 = length([c1[0] - 2.0 * c2[0] + p[0], c1[1] - 2.0 * c2[1] + p[1]]);
+				let n   = curve_segments(dd0.max(dd1) * 0.75);
+				let mut edge = vec![pen];
+				for i in 1..=n {
+					let t  = i as f32 / n as f32;
+					let mt = 1.0 - t;
+					let (w0, w1, w2, w3) = (mt * mt * mt, 3.0 * mt * mt * t, 3.0 * mt * t * t, t * t * t);
+					edge.push([
+						w0 * pen[0] + w1 * c1[0] + w2 * c2[0] + w3 * p[0],
+						w0 * pen[1] + w1 * c1[1] + w2 * c2[1] + w3 * p[1],
+					]);
+				}
+				edges.push(edge);
+				pen = p;
+			},
+			PathOp::Close => {
+				if pen != start {
+					edges.push(vec![pen, start]);
+				}
+				pen = start;
+			},
+		}
+	}
+	if !edges.is_empty() {
+		contours.push(edges);
+	}
+
+	contours
+}
+
+/// Flattens `path` into lines in pixels, y going down.
+fn flatten(path: &[PathOp], scale: f32) -> Vec<[[f32; 2]; 2]> {
+	contours(path, scale)
+		.iter()
+		.flatten()
+		.flat_map(|edge| edge.windows(2).map(|w| [w[0], w[1]]))
+		.collect()
+}
+
+/// Wang's formula, see the encoder's counterpart.
+fn curve_segments(bend: f32) -> usize {
+	let n = (bend / CURVE_TOLERANCE).sqrt().ceil();
+	(n as usize).clamp(1, MAX_CURVE_SEGMENTS)
+}
+
+fn length(v: [f32; 2]) -> f32 {
+	(v[0] * v[0] + v[1] * v[1]).sqrt()
+}
+
+/// Exact area coverage of the closed `lines`, non-zero filled as long
+/// as contours don't overlap, which is what fonts are made of.
+///
+/// Every line adds the signed area it covers to the right of it per
+/// pixel, a running sum over a row turns those into coverage.
+fn rasterize(lines: &[[[f32; 2]; 2]]) -> Bitmap {
+	if lines.is_empty() {
+		return Bitmap::default();
+	}
+
+	let mut min = [f32::MAX; 2];
+	let mut max = [f32::MIN; 2];
+	for p in lines.iter().flatten() {
+		for axis in 0..2 {
+			min[axis] = min[axis].min(p[axis]);
+			max[axis] = max[axis].max(p[axis]);
+		}
+	}
+	let min = min.map(f32::floor);
+	let max = max.map(f32::ceil);
+
+	let width  = (max[0] - min[0]) as usize;
+	let height = (max[1] - min[1]) as usize;
+	if width == 0 || height == 0 {
+		return Bitmap::default();
+	}
+
+	// A column of slack, the right edge spills over.
+	let stride = width + 2;
+	let mut area = vec![0.0f32; stride * height];
+
+	for line in lines {
+		let p0 = [line[0][0] - min[0], line[0][1] - min[1]];
+		let p1 = [line[1][0] - min[0], line[1][1] - min[1]];
+		accumulate_line(&mut area, stride, height, p0, p1);
+	}
+
+	let mut coverage = Vec::with_capacity(width * height);
+	for row in area.chunks_exact(stride) {
+		let mut acc = 0.0;
+		for a in &row[..width] {
+			acc += a;
+			coverage.push((acc.abs().min(1.0) * 255.0).round() as u8);
+		}
+	}
+
+	Bitmap {
+		width:  width as u32,
+		height: height as u32,
+		offset: min,
+		coverage,
+	}
+}
+
+fn accumulate_line(area: &mut [f32], stride: usize, height: usize, p0: [f32; 2], p1: [f32; 2]) {
+	if p0[1] == p1[1] {
+		return;
+	}
+
+	// Downwards, the direction goes into the sign.
+	let (dir, p0, p1) = if p0[1] < p1[1] { (1.0, p0, p1) } else { (-1.0, p1, p0) };
+	let dxdy = (p1[0] - p0[0]) / (p1[1] - p0[1]);
+
+	let mut x = p0[0];
+	let y0 = p0[1].max(0.0) as usize;
+	let y1 = (p1[1].ceil() as usize).min(height);
+	for y in y0..y1 {
+		let row = &mut area[y * stride..(y + 1) * stride];
+
+		// Part of the line within this row.
+		let dy    = (y as f32 + 1.0).min(p1[1]) - (y as f32).max(p0[1]);
+		let xnext = x + dxdy * dy;
+		let d     = dy * dir;
+
+		let (x0, x1) = if x < xnext { (x, xnext) } else { (xnext, x) };
+		let x0_floor = x0.floor();
+		let x0i = x0_floor as usize;
+		let x1_ceil = x1.ceil();
+		let x1i = x1_ceil as usize;
+
+		if x1i <= x0i + 1 {
+			// Within a single pixel, splits between it & the next one
+			// by the mean x.
+			let xm = 0.5 * (x + xnext) - x0_floor;
+			row[x0i]     += d - d * xm;
+			row[x0i + 1] += d * xm;
+		} else {
+			// Crosses pixels, every one gets the trapezoid under the
+			// line.
+			let s   = 1.0 / (x1 - x0);
+			let x0f = x0 - x0_floor;
+			let a0  = 0.5 * s * (1.0 - x0f) * (1.0 - x0f);
+			let x1f = x1 - x1_ceil + 1.0;
+			let am  = 0.5 * s * x1f * x1f;
+
+			row[x0i] += d * a0;
+			if x1i == x0i + 2 {
+				row[x0i + 1] += d * (1.0 - a0 - am);
+			} else {
+				let a1 = s * (1.5 - x0f);
+				row[x0i + 1] += d * (a1 - a0);
+				for a in &mut row[x0i + 2..x1i - 1] {
+					*a += d * s;
+				}
+				let a2 = a1 + (x1i - x0i - 3) as f32 * s;
+				row[x1i - 1] += d * (1.0 - a2 - am);
+			}
+			row[x1i] += d * am;
+		}
+
+		x = xnext;
+	}
+}
+
+/// Flattened edge of a contour & the channels of a multi-channel
+/// distance field it goes into.
+struct Edge {
+	points:   Vec<[f32; 2]>,
+	channels: u8,
+}
+
+/// Signed distances to the outline made of `contours` over its
+/// bounds grown by `range`, see [`Face::distance_field`].
+///
+/// The multi-channel one follows Chlumsky's MSDF: edges meeting at a
+/// corner go into different channels, so the median of the distances
+/// to the nearest edge of every channel keeps the corner sharp.
+fn distance_field(contours: Vec<Vec<Vec<[f32; 2]>>>, range: f32, multi_channel: bool) -> DistanceField {
+	let lines: Vec<[[f32; 2]; 2]> = contours
+		.iter()
+		.flatten()
+		.flat_map(|edge| edge.windows(2).map(|w| [w[0], w[1]]))
+		.collect();
+	if lines.is_empty() {
+		return DistanceField::default();
+	}
+
+	let pad = range.ceil();
+	let mut min = [f32::MAX; 2];
+	let mut max = [f32::MIN; 2];
+	for p in lines.iter().flatten() {
+		for axis in 0..2 {
+			min[axis] = min[axis].min(p[axis]);
+			max[axis] = max[axis].max(p[axis]);
+		}
+	}
+	let min = min.map(|v| v.floor() - pad);
+	let max = max.map(|v| v.ceil() + pad);
+	let width  = (max[0] - min[0]) as usize;
+	let height = (max[1] - min[1]) as usize;
+
+	// Outer contours go either way depending on the font, the larger
+	// area tells which side is inside.
+	let area: f32 = lines.iter().map(|[a, b]| a[0] * b[1] - b[0] * a[1]).sum();
+	let orientation = if area >= 0.0 { 1.0 } else { -1.0 };
+
+	let edges: Vec<Edge> = if multi_channel {
+		contours.into_iter().flat_map(color_edges).collect()
+	} else {
+		Vec::new()
+	};
+
+	let encode = |d: f32| ((0.5 + d / (2.0 * range)).clamp(0.0, 1.0) * 255.0).round() as u8;
+
+	let mut pixels = Vec::with_capacity(width * height * 4);
+	for y in 0..height {
+		for x in 0..width {
+			let p = [min[0] + x as f32 + 0.5, min[1] + y as f32 + 0.5];
+			let d = true_distance(&lines, p);
+
+			let rgb = if multi_channel {
+				let rgb = [RED, GREEN, BLUE].map(|channel| edge_distance(&edges, channel, p, orientation));
+				// Channels disagreeing on the side, where edges of the same
+				// color come close, fall back to the true distance.
+				if (median(rgb) > 0.0) != (d > 0.0) { [d; 3] } else { rgb }
+			} else {
+				[range; 3]
+			};
+
+			pixels.extend(rgb.map(encode));
+			pixels.push(encode(d));
+		}
+	}
+
+	DistanceField {
+		width:  width as u32,
+		height: height as u32,
+		offset: min,
+		pixels,
+	}
+}
+
+/// Splits a closed contour into runs of edges between corners, every
+/// run takes two channels, different from the neighbours. Contours
+/// with fewer than two corners are white, smooth all around.
+fn color_edges(edges: Vec<Vec<[f32; 2]>>) -> Vec<Edge> {
+	const COLORS: [u8; 3] = [GREEN | BLUE, RED | BLUE, RED | GREEN];
+
+	let n = edges.len();
+	let corners: Vec<usize> = (0..n)
+		.filter(|&i| is_corner(end_direction(&edges[(i + n - 1) % n]), start_direction(&edges[i])))
+		.collect();
+
+	let mut channels = vec![WHITE; n];
+	if corners.len() >= 2 {
+		let runs = corners.len();
+		for (k, &corner) in corners.iter().enumerate() {
+			// The last run meets the first one as well.
+			let color = if k == runs - 1 && k % 3 == 0 { COLORS[1] } else { COLORS[k % 3] };
+			let end   = corners[(k + 1) % runs];
+			let mut i = corner;
+			loop {
+				channels[i] = color;
+				i = (i + 1) % n;
+				if i == end {
+					break;
+				}
+			}
+		}
+	}
+
+	edges
+		.into_iter()
+		.zip(channels)
+		.map(|(points, channels)| Edge { points, channels })
+		.collect()
+}
+
+fn start_direction(edge: &[[f32; 2]]) -> [f32; 2] {
+	edge.windows(2)
+		.map(|w| [w[1][0] - w[0][0], w[1][1] - w[0][1]])
+		.find(|d| d[0] != 0.0 || d[1] != 0.0)
+		.unwrap_or([0.0; 2])
+}
+
+fn end_direction(edge: &[[f32; 2]]) -> [f32; 2] {
+	edge.windows(2)
+		.rev()
+		.map(|w| [w[1][0] - w[0][0], w[1][1] - w[0][1]])
+		.find(|d| d[0] != 0.0 || d[1] != 0.0)
+		.unwrap_or([0.0; 2])
+}
+
+fn is_corner(a: [f32; 2], b: [f32; 2]) -> bool {
+	let (la, lb) = (length(a), length(b));
+	if la == 0.0 || lb == 0.0 {
+		return false;
+	}
+	let dot   = (a[0] * b[0] + a[1] * b[1]) / (la * lb);
+	let cross = (a[0] * b[1] - a[1] * b[0]) / (la * lb);
+	dot <= 0.0 || cross.abs() > CORNER_SINE
+}
+
+/// Distance from `p` to the nearest of `lines`, positive inside.
+fn true_distance(lines: &[[[f32; 2]; 2]], p: [f32; 2]) -> f32 {
+	let mut nearest = f32::MAX;
+	let mut winding = 0;
+	for &[a, b] in lines {
+		let ab = [b[0] - a[0], b[1] - a[1]];
+		let ap = [p[0] - a[0], p[1] - a[1]];
+		let len2 = ab[0] * ab[0] + ab[1] * ab[1];
+		let t = if len2 > 0.0 { ((ap[0] * ab[0] + ap[1] * ab[1]) / len2).clamp(0.0, 1.0) } else { 0.0 };
+		nearest = nearest.min(length([ap[0] - ab[0] * t, ap[1] - ab[1] * t]));
+
+		// Crossings of a ray going right, non-zero is inside.
+		if (a[1] <= p[1]) != (b[1] <= p[1]) {
+			let x = a[0] + (p[1] - a[1]) / ab[1] * ab[0];
+			if x > p[0] {
+				winding += if b[1] > a[1] { 1 } else { -1 };
+			}
+		}
+	}
+
+	if winding != 0 { nearest } else { -nearest }
+}
+
+/// Signed pseudo-distance from `p` to the nearest of the `edges` of
+/// `channel`, positive inside. Past the ends of an edge it is the
+/// distance to its tangent, which is what extends corners.
+fn edge_distance(edges: &[Edge], channel: u8, p: [f32; 2], orientation: f32) -> f32 {
+	let mut nearest = f32::MAX;
+	// How square `p` is to the nearest line, breaks ties at the points
+	// lines share.
+	let mut square  = 0.0;
+	let mut signed  = -f32::MAX;
+
+	for edge in edges.iter().filter(|e| e.channels & channel != 0) {
+		let last = edge.points.len() - 2;
+		for (i, w) in edge.points.windows(2).enumerate() {
+			let (a, b) = (w[0], w[1]);
+			let ab   = [b[0] - a[0], b[1] - a[1]];
+			let ap   = [p[0] - a[0], p[1] - a[1]];
+			let len2 = ab[0] * ab[0] + ab[1] * ab[1];
+			if len2 == 0.0 {
+				continue;
+			}
+
+			let t  = (ap[0] * ab[0] + ap[1] * ab[1]) / len2;
+			let tc = t.clamp(0.0, 1.0);
+			let d  = length([ap[0] - ab[0] * tc, ap[1] - ab[1] * tc]);
+			// |ab| times the distance to the line through `a` & `b`.
+			let cross = ab[0] * ap[1] - ab[1] * ap[0];
+			let s = if d > 0.0 { (cross / (len2.sqrt() * d)).abs() } else { 1.0 };
+
+			if d < nearest - 1e-5 || (d < nearest + 1e-5 && s > square) {
+				nearest = d;
+				square  = s;
+
+				let mut dist = d;
+				if (i == 0 && t < 0.0) || (i == last && t > 1.0) {
+					dist = dist.min((cross / len2.sqrt()).abs());
+				}
+				signed = dist * cross.signum() * orientation;
+			}
+		}
+	}
+
+	signed
+}
+
+fn median(v: [f32; 3]) -> f32 {
+	v[0].min(v[1]).max(v[0].max(v[1]).min(v[2]))
+}
+
+fn u16_at(d: &[u8], at: usize) -> Option<u16> {
+	Some(u16::from_be_bytes(d.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn i16_at(d: &[u8], at: usize) -> Option<i16> {
+	u16_at(d, at).map(|v| v as i16)
+}
+
+fn u32_at(d: &[u8], at: usize) -> Option<u32> {
+	Some(u32::from_be_bytes(d.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// Printable ASCII of DejaVu Sans with its kerning, see
+/// tests/fonts/README.md.
+#[cfg(test)]
+pub(crate) fn dejavu() -> Face {
+	Face::parse(include_bytes!("../tests/fonts/DejaVuSans-Subset.ttf").to_vec()).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn reads_the_metrics() {
+		let face = dejavu();
+		assert_eq!(face.units_per_em(), 2048.0);
+
+		let metrics = face.line_metrics(2048.0);
+		assert_eq!(metrics, LineMetrics::new(1901.0, -483.0, 0.0));
+
+		let a = face.glyph_index('A').unwrap();
+		assert_eq!(face.advance(a), 1401.0);
+		assert_eq!(face.glyph_index('\u{e9}'), None);
+	}
+
+	#[test]
+	fn kerns_pairs() {
+		let face = dejavu();
+		let glyph = |c| face.glyph_index(c).unwrap();
+		assert!(face.kerning(glyph('A'), glyph('V')) < 0.0);
+		assert_eq!(face.kerning(glyph('V'), glyph('V')), 0.0);
+	}
+
+	#[test]
+	fn rasterizes_exact_coverage() {
+		// Square straddling pixel edges: corners get a quarter, sides a
+		// half, the middle all of it.
+		let square = [
+			PathOp::MoveTo([0.5, -0.5]),
+			PathOp::LineTo([2.5, -0.5]),
+			PathOp::LineTo([2.5, -2.5]),
+			PathOp::LineTo([0.5, -2.5]),
+			PathOp::Close,
+		];
+		let bitmap = rasterize(&flatten(&square, 1.0));
+		assert_eq!((bitmap.width, bitmap.height), (3, 3));
+		assert_eq!(bitmap.offset, [0.0, 0.0]);
+		assert_eq!(bitmap.coverage, [
+			64,  128, 64,
+			128, 255, 128,
+			64,  128, 64,
+		]);
+	}
+
+	#[test]
+	fn rasterizes_glyphs() {
+		let face   = dejavu();
+		let bitmap = face.rasterize(face.glyph_index('O').unwrap(), 32.0).unwrap();
+
+		// Sits on the baseline, the bitmap hugs the outline.
+		assert!(bitmap.offset[1] < -20.0);
+		assert!((bitmap.offset[1] + bitmap.height as f32).abs() <= 1.0);
+
+		// The counter is empty, the stroke solid & the edges in between.
+		let at = |x: u32, y: u32| bitmap.coverage[(y * bitmap.width + x) as usize];
+		let mid = bitmap.height / 2;
+		assert_eq!(at(bitmap.width / 2, mid), 0);
+		assert_eq!(at(2, mid), 255);
+		assert!(bitmap.coverage.iter().any(|c| (1..255).contains(c)));
+
+		assert!(face.rasterize(face.glyph_index(' ').unwrap(), 32.0).unwrap().coverage.is_empty());
+	}
+
+	#[test]
+	fn rasterizes_between_pixels() {
+		let face  = dejavu();
+		let glyph = face.glyph_index('l').unwrap();
+		let whole = face.rasterize(glyph, 16.0).unwrap();
+		let half  = face.rasterize_shifted(glyph, 16.0, 0.5).unwrap();
+
+		// Same ink, spread differently over the columns.
+		let ink = |b: &Bitmap| b.coverage.iter().map(|&c| c as u32).sum::<u32>();
+		assert!(ink(&whole).abs_diff(ink(&half)) <= whole.height * 2);
+		assert_ne!(whole.coverage, half.coverage);
+		assert_eq!(half.offset[1], whole.offset[1]);
+		assert!(half.offset[0] >= whole.offset[0]);
+		assert_eq!(half.offset[0].fract(), 0.0);
+	}
+
+	#[test]
+	fn generates_distance_fields() {
+		// 10x10 square, padded by the range.
+		let square = [
+			PathOp::MoveTo([0.0, 0.0]),
+			PathOp::LineTo([10.0, 0.0]),
+			PathOp::LineTo([10.0, -10.0]),
+			PathOp::LineTo([0.0, -10.0]),
+			PathOp::Close,
+		];
+		let field = distance_field(contours(&square, 1.0), 2.0, true);
+		assert_eq!((field.width, field.height), (14, 14));
+		assert_eq!(field.offset, [-2.0, -2.0]);
+
+		// Median of the channels & the true distance.
+		let at = |x: u32, y: u32| {
+			let i = ((y * field.width + x) * 4) as usize;
+			let t = &field.pixels[i..i + 4];
+			(t[0].min(t[1]).max(t[0].max(t[1]).min(t[2])), t[3])
+		};
+		// Deep inside, then half a pixel outside an edge.
+		assert_eq!(at(7, 7), (255, 255));
+		assert_eq!(at(1, 7), (96, 96));
+		// Diagonally off a corner the true distance is rounded, the
+		// median is as far as off the edges.
+		assert_eq!(at(12, 12), (96, 82));
+	}
+
+	#[test]
+	fn runs_charstrings() {
+		// Width, then a 10x20 box with a curved top.
+		let charstring = [
+			139 + 50, 139 + 10, 139 + 5, 21,   // 50 10 5 rmoveto
+			139 + 10, 7,                       // 10 vlineto
+			139 + 10, 139 + 10, 139 + 10, 139 - 10, 31, // hvcurveto
+			139 - 20, 6,                       // -20 hlineto
+			14,                                // endchar
+		];
+		let empty = Index::default();
+		let mut path = Vec::new();
+		let mut state = Charstring {
+			d:            &charstring,
+			global_subrs: &empty,
+			local_subrs:  &empty,
+			path:         &mut path,
+			stack:        Vec::new(),
+			pen:          [0.0; 2],
+			open:         false,
+			stems:        0,
+			width:        false,
+			done:         false,
+		};
+		state.run(0, charstring.len(), 0).unwrap();
+
+		assert_eq!(path, [
+			PathOp::MoveTo([10.0, 5.0]),
+			PathOp::LineTo([10.0, 15.0]),
+			PathOp::CubicTo([20.0, 15.0], [30.0, 25.0], [30.0, 15.0]),
+			PathOp::LineTo([10.0, 15.0]),
+		]);
+	}
+
+	#[test]
+	fn reads_gpos_pair_adjustments() {
+		let subtable = [
+			0, 1, 0, 12, 0, 4, 0, 0, 0, 1, 0, 18, // format 1, x advance
+			0, 1, 0, 1, 0, 5,                     // coverage of glyph 5
+			0, 1, 0, 7, 0xFF, 0xCE,               // 5 then 7: -50
+		];
+		assert_eq!(gpos_pair_adjustment(&subtable, 0, 5, 7), Some(-50));
+		assert_eq!(gpos_pair_adjustment(&subtable, 0, 5, 8), None);
+		assert_eq!(gpos_pair_adjustment(&subtable, 0, 6, 7), None);
+	}
+
+	#[test]
+	fn reads_coverage_ranges_past_u16() {
+		let coverage = [
+			0, 2, 0, 1,                   // format 2, a range
+			0x10, 0, 0x20, 0, 0xFF, 0xF0, // 4096 to 8192, from index 65520
+		];
+		assert_eq!(coverage_index(&coverage, 0, 0x1000), Some(65520));
+		assert_eq!(coverage_index(&coverage, 0, 0x1100), Some(65520 + 256));
+		assert_eq!(coverage_index(&coverage, 0, 0x2001), None);
+	}
+}
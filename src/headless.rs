@@ -0,0 +1,364 @@
+//! Offscreen renderer reading the frames back to the host.
+//!
+//! Uses the same prima buffers & pipeline as the window one, but
+//! needs neither a window nor a swapchain, so it runs fine on build
+//! machines with a software driver (e.g. lavapipe).
+
+use std::fs;
+use std::io::BufWriter;
+use std::path::Path;
+use std::slice;
+
+use ash::{vk, Entry};
+
+use crate::Result;
+use crate::encoder::PrimaEncoder;
+use crate::texture::{TextureId, TextureUpdate};
+use crate::vulkan::{self, Pipeline, PrimaBuffers, Textures};
+
+const FORMAT: vk::Format = vk::Format::R8G8B8A8_UNORM;
+
+pub struct Headless {
+	// Keeps the Vulkan library loaded.
+	_entry:        Entry,
+	instance:      ash::Instance,
+	device:        ash::Device,
+	device_name:   String,
+	queue:         vk::Queue,
+	extent:        vk::Extent2D,
+	image:         vk::Image,
+	image_mem:     vk::DeviceMemory,
+	image_view:    vk::ImageView,
+	render_pass:   vk::RenderPass,
+	framebuffer:   vk::Framebuffer,
+	readback:      vk::Buffer,
+	readback_mem:  vk::DeviceMemory,
+	readback_data: *const u8,
+	command_pool:  vk::CommandPool,
+	cmd_buffer:    vk::CommandBuffer,
+	fence:         vk::Fence,
+	prima:         PrimaBuffers,
+	textures:      Textures,
+	tri_pipeline:  Pipeline,
+}
+
+impl Headless {
+	pub fn new(width: u32, height: u32) -> Result<Self> {
+		unsafe { Self::create(width, height) }
+	}
+
+	unsafe fn create(width: u32, height: u32) -> Result<Self> {
+		let entry    = Entry::load()?;
+		let instance = vulkan::create_instance(&entry, &[])?;
+
+		let (physical_device, queue_family) = pick_physical_device_and_queue_family(&instance)?;
+
+		let props       = instance.get_physical_device_properties(physical_device);
+		let device_name = vulkan::str_from_null_terminated_bytes(&props.device_name).to_owned();
+
+		let (device, queue) = vulkan::create_device_and_queue(
+			&instance,
+			physical_device,
+			queue_family,
+			&[],
+		)?;
+
+		let mem_props = instance.get_physical_device_memory_properties(physical_device);
+
+		let extent = vk::Extent2D { width, height };
+
+		let image_create_info = vk::ImageCreateInfo::builder()
+			.image_type(vk::ImageType::TYPE_2D)
+			.format(FORMAT)
+			.extent(vk::Extent3D { width, height, depth: 1 })
+			.mip_levels(1)
+			.array_layers(1)
+			.samples(vk::SampleCountFlags::TYPE_1)
+			.tiling(vk::ImageTiling::OPTIMAL)
+			.usage(vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC)
+			.sharing_mode(vk::SharingMode::EXCLUSIVE)
+			.initial_layout(vk::ImageLayout::UNDEFINED);
+		let image = device.create_image(&image_create_info, None)?;
+
+		let mem_req = device.get_image_memory_requirements(image);
+		let Some(mem_index) = vulkan::find_memory_type(&mem_props, mem_req.memory_type_bits, vk::MemoryPropertyFlags::DEVICE_LOCAL)
+			.or_else(|| vulkan::find_memory_type(&mem_props, mem_req.memory_type_bits, vk::MemoryPropertyFlags::empty()))
+		else {
+			return Err("Failed to find a suitable image memory.".into());
+		};
+		let mem_alloc_info = vk::MemoryAllocateInfo::builder()
+			.allocation_size(mem_req.size)
+			.memory_type_index(mem_index);
+		let image_mem = device.allocate_memory(&mem_alloc_info, None)?;
+		device.bind_image_memory(image, image_mem, 0)?;
+
+		let image_view  = vulkan::create_image_view(&device, image, FORMAT)?;
+		let render_pass = vulkan::create_render_pass(&device, FORMAT)?;
+		let framebuffer = vulkan::create_framebuffer(&device, render_pass, image_view, extent)?;
+
+		let readback_size        = width as u64 * height as u64 * 4;
+		let readback_create_info = vk::BufferCreateInfo::builder()
+			.size(readback_size)
+			.usage(vk::BufferUsageFlags::TRANSFER_DST)
+			.sharing_mode(vk::SharingMode::EXCLUSIVE);
+		let readback = device.create_buffer(&readback_create_info, None)?;
+
+		let mem_req       = device.get_buffer_memory_requirements(readback);
+		let host_coherent = vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT;
+		let Some(mem_index) = vulkan::find_memory_type(&mem_props, mem_req.memory_type_bits, host_coherent) else {
+			return Err("Failed to find a suitable readback memory.".into());
+		};
+		let mem_alloc_info = vk::MemoryAllocateInfo::builder()
+			.allocation_size(mem_req.size)
+			.memory_type_index(mem_index);
+		let readback_mem = device.allocate_memory(&mem_alloc_info, None)?;
+		device.bind_buffer_memory(readback, readback_mem, 0)?;
+
+		let readback_data = device.map_memory(readback_mem, 0, readback_size, vk::MemoryMapFlags::default())?;
+
+		let (command_pool, cmd_buffer) = vulkan::create_command_buffer(&device, queue_family)?;
+
+		let fence = device.create_fence(&vk::FenceCreateInfo::default(), None)?;
+
+		// There is a single target, so a single frame worth of buffers
+		// is enough.
+		let prima        = PrimaBuffers::new(&instance, physical_device, &device, 1)?;
+		let textures     = Textures::new(&instance, physical_device, &device, queue, queue_family, 1)?;
+		let set_layouts  = [prima.descriptor_set_layout, textures.descriptor_set_layout];
+		let tri_pipeline = vulkan::create_tri_pipeline(&device, render_pass, &set_layouts)?;
+
+		Ok(Self {
+			_entry: entry,
+			instance,
+			device,
+			device_name,
+			queue,
+			extent,
+			image,
+			image_mem,
+			image_view,
+			render_pass,
+			framebuffer,
+			readback,
+			readback_mem,
+			readback_data: readback_data as *const u8,
+			command_pool,
+			cmd_buffer,
+			fence,
+			prima,
+			textures,
+			tri_pipeline,
+		})
+	}
+
+	/// Size of the frame in pixels.
+	pub fn size(&self) -> (u32, u32) {
+		(self.extent.width, self.extent.height)
+	}
+
+	/// Name of the GPU rendering the frames.
+	pub fn device_name(&self) -> &str {
+		&self.device_name
+	}
+
+	/// Uploads tightly packed RGBA8 `pixels`, top row first.
+	pub fn upload_texture(&mut self, width: u32, height: u32, pixels: &[u8]) -> Result<TextureId> {
+		// Every frame is waited for, nothing is in flight.
+		unsafe { self.textures.upload(&self.device, width, height, pixels) }
+	}
+
+	/// Overwrites parts of the `id` texture, all of `updates` or none,
+	/// before the next frame is rendered.
+	pub fn update_texture(&mut self, id: TextureId, updates: &[TextureUpdate]) -> Result<()> {
+		// Every frame is waited for, nothing is in flight.
+		unsafe { self.textures.stage(&self.device, 0, id, updates) }
+	}
+
+	/// Encodes a frame via `encode`, renders it and returns its
+	/// tightly packed RGBA8 pixels, top row first.
+	pub fn render<F>(&mut self, encode: F) -> Result<Vec<u8>>
+	where
+		F: FnOnce(&mut PrimaEncoder) -> Result<()>,
+	{
+		unsafe { self.draw(encode) }
+	}
+
+	unsafe fn draw<F>(&mut self, encode: F) -> Result<Vec<u8>>
+	where
+		F: FnOnce(&mut PrimaEncoder) -> Result<()>,
+	{
+		let w = self.extent.width  as f32;
+		let h = self.extent.height as f32;
+
+		let (pbuf, ibuf) = self.prima.frame(0);
+		let mut encoder  = PrimaEncoder::new(pbuf, ibuf, w, h)?;
+		encode(&mut encoder)?;
+		let indices = encoder.finish();
+
+		let device     = &self.device;
+		let cmd_buffer = self.cmd_buffer;
+
+		device.reset_command_pool(self.command_pool, vk::CommandPoolResetFlags::empty())?;
+
+		let cmd_buffer_begin_info = vk::CommandBufferBeginInfo::builder()
+			.flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+		device.begin_command_buffer(cmd_buffer, &cmd_buffer_begin_info)?;
+
+		self.textures.cmd_updates(device, cmd_buffer, 0);
+
+		let render_begin_barrier = vulkan::image_barrier(
+			&self.image,
+			vk::AccessFlags::empty(),
+			vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+			vk::ImageLayout::UNDEFINED,
+			vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+		);
+		device.cmd_pipeline_barrier(
+			cmd_buffer,
+			vk::PipelineStageFlags::TOP_OF_PIPE,
+			vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+			vk::DependencyFlags::BY_REGION,
+			&[],
+			&[],
+			slice::from_ref(&render_begin_barrier),
+		);
+
+		let clear_value = vulkan::clear_value();
+		let render_pass_begin_info = vk::RenderPassBeginInfo::builder()
+			.render_pass(self.render_pass)
+			.framebuffer(self.framebuffer)
+			.render_area(vk::Rect2D {
+				offset: vk::Offset2D::default(),
+				extent: self.extent,
+			})
+			.clear_values(slice::from_ref(&clear_value));
+
+		device.cmd_begin_render_pass(cmd_buffer, &render_pass_begin_info, vk::SubpassContents::INLINE);
+		self.prima.cmd_draw(device, cmd_buffer, &self.tri_pipeline, &self.textures, self.extent, 0, indices);
+		device.cmd_end_render_pass(cmd_buffer);
+
+		let render_end_barrier = vulkan::image_barrier(
+			&self.image,
+			vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+			vk::AccessFlags::TRANSFER_READ,
+			vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+			vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+		);
+		device.cmd_pipeline_barrier(
+			cmd_buffer,
+			vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+			vk::PipelineStageFlags::TRANSFER,
+			vk::DependencyFlags::BY_REGION,
+			&[],
+			&[],
+			slice::from_ref(&render_end_barrier),
+		);
+
+		let region = vk::BufferImageCopy::builder()
+			.image_subresource(vk::ImageSubresourceLayers {
+				aspect_mask: vk::ImageAspectFlags::COLOR,
+				mip_level: 0,
+				base_array_layer: 0,
+				layer_count: 1,
+			})
+			.image_extent(vk::Extent3D {
+				width:  self.extent.width,
+				height: self.extent.height,
+				depth:  1,
+			});
+		device.cmd_copy_image_to_buffer(
+			cmd_buffer,
+			self.image,
+			vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+			self.readback,
+			slice::from_ref(&region),
+		);
+
+		let readback_barrier = vk::BufferMemoryBarrier::builder()
+			.src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+			.dst_access_mask(vk::AccessFlags::HOST_READ)
+			.src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+			.dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+			.buffer(self.readback)
+			.size(vk::WHOLE_SIZE);
+		device.cmd_pipeline_barrier(
+			cmd_buffer,
+			vk::PipelineStageFlags::TRANSFER,
+			vk::PipelineStageFlags::HOST,
+			vk::DependencyFlags::empty(),
+			&[],
+			slice::from_ref(&readback_barrier),
+			&[],
+		);
+
+		device.end_command_buffer(cmd_buffer)?;
+
+		let submit_info = vk::SubmitInfo::builder()
+			.command_buffers(slice::from_ref(&cmd_buffer));
+		device.reset_fences(slice::from_ref(&self.fence))?;
+		device.queue_submit(self.queue, slice::from_ref(&*submit_info), self.fence)?;
+		device.wait_for_fences(slice::from_ref(&self.fence), true, u64::MAX)?;
+
+		let size   = self.extent.width as usize * self.extent.height as usize * 4;
+		let pixels = slice::from_raw_parts(self.readback_data, size).to_vec();
+
+		Ok(pixels)
+	}
+}
+
+impl Drop for Headless {
+	fn drop(&mut self) {
+		unsafe {
+			let device = &self.device;
+
+			let _ = device.device_wait_idle();
+
+			self.tri_pipeline.destroy(device);
+			self.textures.destroy(device);
+			self.prima.destroy(device);
+			device.destroy_fence(self.fence, None);
+			device.destroy_command_pool(self.command_pool, None);
+			device.destroy_buffer(self.readback, None);
+			device.unmap_memory(self.readback_mem);
+			device.free_memory(self.readback_mem, None);
+			device.destroy_framebuffer(self.framebuffer, None);
+			device.destroy_render_pass(self.render_pass, None);
+			device.destroy_image_view(self.image_view, None);
+			device.destroy_image(self.image, None);
+			device.free_memory(self.image_mem, None);
+			device.destroy_device(None);
+			self.instance.destroy_instance(None);
+		}
+	}
+}
+
+/// Writes tightly packed RGBA8 `pixels` as a PNG file.
+pub fn write_png(path: &Path, width: u32, height: u32, pixels: &[u8]) -> Result<()> {
+	let file = fs::File::create(path)?;
+
+	let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+	encoder.set_color(png::ColorType::Rgba);
+	encoder.set_depth(png::BitDepth::Eight);
+
+	let mut writer = encoder.write_header()?;
+	writer.write_image_data(pixels)?;
+
+	Ok(())
+}
+
+unsafe fn pick_physical_device_and_queue_family(instance: &ash::Instance) -> Result<(vk::PhysicalDevice, u32)> {
+	let physical_devices = instance.enumerate_physical_devices()?;
+
+	let (physical_device, queue_family) = physical_devices
+		.iter()
+		.find_map(|pdevice| {
+			instance
+				.get_physical_device_queue_family_properties(*pdevice)
+				.iter()
+				.position(|props| props.queue_flags.contains(vk::QueueFlags::GRAPHICS))
+				.map(|i| (*pdevice, i as u32))
+		})
+		.ok_or("Failed to find a suitable physical device.")?;
+
+	Ok((physical_device, queue_family))
+}
@@ -0,0 +1,295 @@
+//! Index & prima buffer format shared with `tri.vert.glsl` &
+//! `tri.frag.glsl`.
+
+//
+// Index is encoded as follows:
+//
+// [31] [30:26] [25:24] [23:0]
+//  |     |       |       |
+//  |     |       |       +------- offset into prima buffer (in 32-bit words)
+//  |     |       +--------------- rect corner id
+//  |     +----------------------- primitive type
+//  +----------------------------- gradient paint
+//
+// Prima buffer starts with a column-major 4x4 projection
+// matrix (16 words), primitive data follows it. Buffer at
+// offset will contain primitive type specific data.
+//
+// With the gradient paint bit set, the `c` of the primitive is the
+// offset of a gradient instead of a color:
+//
+//   struct Gradient {
+//     kind:   u32,      // GRADIENT_*
+//     params: [f32; 4], // linear: start xy, end xy
+//                       // radial: center xy, radius, unused
+//                       // conic:  center xy, start angle, unused
+//     count:  u32,
+//     stops:  [(f32, u32); count], // offset & color, increasing offsets
+//   };
+//
+// Gradients are in frame pixels, padded past the ends, interpolated
+// with premultiplied alpha & dithered. Only the primitives with `c`
+// right after their first 4 words can be painted with one, that is
+// all but PRIMA_TRI & PRIMA_BORDER. An image paint is multiplied by
+// its texture.
+//
+// Supported primitive types & their data:
+//
+// * PRIMA_TRI:
+//
+//   Buffer data:
+//
+//   struct TriVertex {
+//     x: f32,
+//     y: f32,
+//     c: u32, // RGBA8, R in the lowest byte.
+//   };
+//
+//   Indices: (0, 1, 2), each pointing at its own vertex.
+//
+// * PRIMA_RECT:
+//
+//   Buffer data:
+//
+//   struct Rect {
+//     x: f32,
+//     y: f32,
+//     w: f32,
+//     h: f32,
+//     c: u32, // Fill color, same as TriVertex::c.
+//   };
+//
+//   Indices:
+//
+//     1 +--+ 2
+//       | /|
+//       |/ |
+//     0 +__+ 3
+//
+//     (0, 1, 2, 2, 3, 0)
+//
+// * PRIMA_ROUNDED_RECT:
+//
+//   Buffer data:
+//
+//   struct RoundedRect {
+//     rect:  Rect,
+//     radii: [f32; 4], // top-left, top-right, bottom-right, bottom-left
+//   };
+//
+//   Indices: same as PRIMA_RECT. The quad is inflated by a pixel
+//   and not snapped, the fragment stage cuts out the shape via its
+//   signed distance and anti-aliases the edges.
+//
+// * PRIMA_ELLIPSE:
+//
+//   Buffer data:
+//
+//   struct Ellipse {
+//     cx: f32,
+//     cy: f32,
+//     rx: f32,
+//     ry: f32,
+//     c:  u32,
+//   };
+//
+//   Indices: same as PRIMA_RECT, the quad is the bounding box
+//   treated as the one of PRIMA_ROUNDED_RECT.
+//
+// * PRIMA_ARC:
+//
+//   Buffer data:
+//
+//   struct Arc {
+//     cx:        f32,
+//     cy:        f32,
+//     radius:    f32,
+//     thickness: f32,
+//     c:         u32,
+//     mid:       [f32; 2], // cos & sin of the middle angle
+//     end:       [f32; 2], // cos & sin of half of the sweep, (-1, 0) for a full ring
+//   };
+//
+//   Indices: same as PRIMA_ELLIPSE.
+//
+// * PRIMA_LINE:
+//
+//   Buffer data:
+//
+//   struct Line {
+//     a:     [f32; 2],
+//     b:     [f32; 2],
+//     c:     u32,
+//     width: f32,
+//     ends:  u32, // LINE_* of `a` in the lowest byte, of `b` in the next one
+//   };
+//
+//   Indices: same as PRIMA_RECT, but the quad is oriented along the
+//   line: corners 0 & 1 are around `a`, 2 & 3 around `b`. It is
+//   inflated by the caps & a pixel, the fragment stage cuts out the
+//   stroke. LINE_JOINT ends are hard, the PRIMA_JOIN of a polyline
+//   covers exactly the pixels past them, so nothing is blended twice
+//   on the outer side of a joint.
+//
+// * PRIMA_JOIN:
+//
+//   Buffer data:
+//
+//   struct Join {
+//     p:     [f32; 2], // The joint.
+//     prev:  [f32; 2], // Start of the line ending at `p`.
+//     c:     u32,
+//     width: f32,
+//     kind:  u32,      // JOIN_*
+//     next:  [f32; 2], // End of the line starting at `p`.
+//   };
+//
+//   Indices: same as PRIMA_ELLIPSE. Miters longer than MITER_LIMIT
+//   times the width are beveled.
+//
+// * PRIMA_BORDER:
+//
+//   Buffer data:
+//
+//   struct Border {
+//     rect:   Rect,     // `c` is the top color.
+//     widths: [f32; 4], // top, right, bottom, left
+//     colors: [u32; 3], // right, bottom, left
+//     radii:  [f32; 4], // as in RoundedRect
+//   };
+//
+//   Indices: same as PRIMA_ROUNDED_RECT, the inner edge has its radii
+//   shrunk by the adjacent widths. Sides meet along the lines from the
+//   outer corners to the inner ones, like in CSS.
+//
+// * PRIMA_SHADOW:
+//
+//   Buffer data:
+//
+//   struct Shadow {
+//     rect:   Rect, // Already offset & spread.
+//     radius: f32,
+//     sigma:  f32,
+//   };
+//
+//   Indices: same as PRIMA_ROUNDED_RECT, but the quad is inflated by
+//   3 sigmas to fit the blur.
+//
+// * PRIMA_IMAGE:
+//
+//   Buffer data:
+//
+//   struct Image {
+//     rect:    Rect,     // `c` tints the texture.
+//     texture: u32,      // Slot in the texture array, < MAX_TEXTURES.
+//     uv:      [f32; 4], // x, y, w, h in normalized texture coordinates
+//   };
+//
+//   Indices: same as PRIMA_RECT. Textures are RGBA8 with straight
+//   alpha, sampled bilinearly & clamped to the edges. All of them are
+//   bound at once, unused slots hold a white texel.
+//
+// * PRIMA_NINE_SLICE:
+//
+//   Buffer data:
+//
+//   struct NineSlice {
+//     image:  Image,
+//     insets: [f32; 4], // top, right, bottom, left, in texels
+//   };
+//
+//   Indices: same as PRIMA_IMAGE. The insets cut the region into
+//   nine slices, the corners are drawn texel per pixel, the edges are
+//   stretched along & the center both ways. Insets not fitting into
+//   the rect shrink proportionally. The fragment stage maps the pixel
+//   to its texel, so it is a single quad.
+//
+// * PRIMA_GLYPH:
+//
+//   Buffer data: same as PRIMA_IMAGE, the texture is a glyph atlas.
+//
+//   Indices: same as PRIMA_IMAGE. The alpha of the texel is the
+//   coverage of `c`, the other channels are ignored.
+//
+// * PRIMA_SDF_GLYPH:
+//
+//   Buffer data:
+//
+//   struct DistanceGlyph {
+//     image: Image,
+//     range: f32, // Texels of distance alpha 0 & 1 are away.
+//   };
+//
+//   Indices: same as PRIMA_IMAGE. The alpha of the texel is the
+//   signed distance to the outline, 0.5 on it & growing inwards. It
+//   is scaled by the pixels per texel of the rect & anti-aliased like
+//   the shapes, so the glyph stays crisp at any size.
+//
+// * PRIMA_MSDF_GLYPH:
+//
+//   Buffer data: same as PRIMA_SDF_GLYPH.
+//
+//   Indices: same as PRIMA_IMAGE. The distance is the median of the
+//   red, green & blue channels of the texel, which keeps the corners
+//   sharp.
+//
+
+pub const PRIMA_TRI:  u32 = 0;
+pub const PRIMA_RECT: u32 = 1;
+pub const PRIMA_ROUNDED_RECT: u32 = 2;
+pub const PRIMA_ELLIPSE: u32 = 3;
+pub const PRIMA_ARC: u32 = 4;
+pub const PRIMA_LINE: u32 = 5;
+pub const PRIMA_JOIN: u32 = 6;
+pub const PRIMA_BORDER: u32 = 7;
+pub const PRIMA_SHADOW: u32 = 8;
+pub const PRIMA_IMAGE: u32 = 9;
+pub const PRIMA_NINE_SLICE: u32 = 10;
+pub const PRIMA_GLYPH: u32 = 11;
+pub const PRIMA_SDF_GLYPH: u32 = 12;
+pub const PRIMA_MSDF_GLYPH: u32 = 13;
+
+/// OR-ed into the primitive type of a gradient painted primitive.
+pub const PAINT_GRADIENT: u32 = 0x20;
+
+pub const GRADIENT_LINEAR: u32 = 0;
+pub const GRADIENT_RADIAL: u32 = 1;
+pub const GRADIENT_CONIC:  u32 = 2;
+
+pub const LINE_BUTT:   u32 = 0;
+pub const LINE_SQUARE: u32 = 1;
+pub const LINE_ROUND:  u32 = 2;
+pub const LINE_JOINT:  u32 = 3;
+
+pub const JOIN_MITER: u32 = 0;
+pub const JOIN_BEVEL: u32 = 1;
+pub const JOIN_ROUND: u32 = 2;
+
+/// Longest miter, relative to the stroke width.
+pub const MITER_LIMIT: f32 = 4.0;
+
+/// Size of the texture array, same as in `tri.frag.glsl`.
+pub const MAX_TEXTURES: usize = 64;
+
+/// Largest prima buffer offset (in words) an index can address.
+pub const MAX_OFFSET: usize = 0xFFFFFF;
+
+pub const fn make_index(offset: u32, p_type: u32, corner: u8) -> u32 {
+	(p_type << 26) | ((corner as u32) << 24) | offset
+}
+
+pub const fn decode_type(index: u32) -> u32 {
+	(index >> 26) & 0x1F
+}
+
+pub const fn decode_gradient(index: u32) -> bool {
+	(index >> 26) & PAINT_GRADIENT != 0
+}
+
+pub const fn decode_corner(index: u32) -> u8 {
+	((index >> 24) & 0x3) as u8
+}
+
+pub const fn decode_offset(index: u32) -> u32 {
+	index & 0xFFFFFF
+}
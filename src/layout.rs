@@ -0,0 +1,445 @@
+//! Lays strings out into lines of positioned glyphs.
+//!
+//! A [`Layout`] breaks text at spaces & hard line breaks to fit a
+//! width, aligns the lines & cuts off what doesn't fit with an
+//! ellipsis. The [`TextLayout`] it makes goes straight into
+//! [`crate::encoder::PrimaEncoder::text_layout`].
+//!
+//! Anything with [`Metrics`] can be laid out, atlas [`Font`]s as well
+//! as the [`Face`]s of a [`crate::cache::GlyphCache`].
+
+use std::collections::VecDeque;
+use std::ops::Range;
+
+use crate::font::Face;
+use crate::text::{Font, LineMetrics};
+
+/// Ends cut off text, three dots if the font has no glyph for it.
+const ELLIPSIS: char = '\u{2026}';
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Align {
+	#[default]
+	Left,
+	Center,
+	Right,
+	/// Stretches the spaces so that lines fill the width, bar the last
+	/// line of a paragraph.
+	Justify,
+}
+
+/// How text is laid out, see [`Layout::layout`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Layout {
+	/// Pixels per em.
+	pub size:      f32,
+	/// Lines going past it are broken or cut off.
+	pub max_width: f32,
+	pub align:     Align,
+	/// Breaks lines at spaces, within words if they are too long on
+	/// their own. Otherwise lines are cut off with an ellipsis.
+	pub wrap:      bool,
+	/// Lines past it are left out, the last one shown ends with an
+	/// ellipsis.
+	pub max_lines: usize,
+}
+
+/// What text is laid out with. Advances, kerning & line metrics are
+/// for [`Self::size`] pixels per em, a layout scales them to its own.
+pub trait Metrics {
+	fn size(&self) -> f32;
+	fn line_metrics(&self) -> LineMetrics;
+	/// `None` for characters without a glyph, which are left out.
+	fn advance(&self, c: char) -> Option<f32>;
+	fn kerning(&self, left: char, right: char) -> f32;
+}
+
+impl Metrics for Font {
+	fn size(&self) -> f32 {
+		Font::size(self)
+	}
+
+	fn line_metrics(&self) -> LineMetrics {
+		self.metrics()
+	}
+
+	fn advance(&self, c: char) -> Option<f32> {
+		self.glyph(c).map(|g| g.advance)
+	}
+
+	fn kerning(&self, left: char, right: char) -> f32 {
+		Font::kerning(self, left, right)
+	}
+}
+
+/// In font units.
+impl Metrics for Face {
+	fn size(&self) -> f32 {
+		self.units_per_em()
+	}
+
+	fn line_metrics(&self) -> LineMetrics {
+		Face::line_metrics(self, self.units_per_em())
+	}
+
+	fn advance(&self, c: char) -> Option<f32> {
+		self.glyph_index(c).map(|g| Face::advance(self, g))
+	}
+
+	fn kerning(&self, left: char, right: char) -> f32 {
+		match (self.glyph_index(left), self.glyph_index(right)) {
+			(Some(l), Some(r)) => Face::kerning(self, l, r),
+			_                  => 0.0,
+		}
+	}
+}
+
+/// Glyph placed by a [`Layout`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PositionedGlyph {
+	pub c:   char,
+	/// Pen on the baseline, relative to the top-left corner of the
+	/// layout.
+	pub pos: [f32; 2],
+}
+
+/// Run of glyphs sharing a baseline.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Line {
+	/// Into [`TextLayout::glyphs`].
+	pub glyphs:   Range<usize>,
+	pub baseline: f32,
+	/// Without trailing spaces, before justification.
+	pub width:    f32,
+}
+
+/// Text laid out by [`Layout::layout`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TextLayout {
+	/// Pixels per em the glyphs are drawn at.
+	pub size:   f32,
+	pub glyphs: Vec<PositionedGlyph>,
+	pub lines:  Vec<Line>,
+	/// Of the widest line.
+	pub width:  f32,
+	/// Every line takes the line height of the font.
+	pub height: f32,
+}
+
+/// Line before alignment, glyphs with their x & advance.
+#[derive(Default)]
+struct RawLine {
+	glyphs: Vec<(char, f32, f32)>,
+	width:  f32,
+	// Ends a paragraph, or has been cut off.
+	last:   bool,
+	// Has anything but spaces, saves a scan per glyph while breaking.
+	inked:  bool,
+}
+
+impl RawLine {
+	fn push(&mut self, c: char, x: f32, advance: f32) {
+		self.glyphs.push((c, x, advance));
+		self.inked |= !c.is_whitespace();
+	}
+
+	fn pen(&self) -> f32 {
+		self.glyphs.last().map_or(0.0, |g| g.1 + g.2)
+	}
+
+	/// Drops trailing spaces & measures what's left.
+	fn finish(mut self, last: bool) -> Self {
+		while self.glyphs.last().is_some_and(|g| g.0.is_whitespace()) {
+			self.glyphs.pop();
+		}
+		self.width = self.pen();
+		self.last  = last;
+		self
+	}
+}
+
+impl Layout {
+	pub const fn new(size: f32) -> Self {
+		Self {
+			size,
+			max_width: f32::INFINITY,
+			align:     Align::Left,
+			wrap:      true,
+			max_lines: usize::MAX,
+		}
+	}
+
+	pub const fn with_max_width(mut self, max_width: f32) -> Self {
+		self.max_width = max_width;
+		self
+	}
+
+	pub const fn with_align(mut self, align: Align) -> Self {
+		self.align = align;
+		self
+	}
+
+	pub const fn with_wrap(mut self, wrap: bool) -> Self {
+		self.wrap = wrap;
+		self
+	}
+
+	pub const fn with_max_lines(mut self, max_lines: usize) -> Self {
+		self.max_lines = max_lines;
+		self
+	}
+
+	/// Lays `text` out with the glyphs & kerning of `font`, characters
+	/// it has no glyphs for are left out.
+	pub fn layout<M: Metrics + ?Sized>(&self, text: &str, font: &M) -> TextLayout {
+		let scale = self.size / font.size();
+
+		let mut lines = Vec::new();
+		for paragraph in text.split('\n') {
+			if lines.len() > self.max_lines {
+				break;
+			}
+			let paragraph = paragraph.strip_suffix('\r').unwrap_or(paragraph);
+			self.break_paragraph(paragraph, font, scale, &mut lines);
+		}
+
+		if !self.wrap {
+			for line in &mut lines {
+				if line.width > self.max_width {
+					self.cut_off(line, font, scale);
+				}
+			}
+		}
+		if lines.len() > self.max_lines {
+			lines.truncate(self.max_lines);
+			if let Some(line) = lines.last_mut() {
+				self.cut_off(line, font, scale);
+			}
+		}
+
+		self.position(lines, font, scale)
+	}
+
+	/// Appends the lines of `paragraph` to `lines`.
+	fn break_paragraph<M: Metrics + ?Sized>(&self, paragraph: &str, font: &M, scale: f32, lines: &mut Vec<RawLine>) {
+		let mut line = RawLine::default();
+		let mut prev = None;
+		// Where the last word on the line starts.
+		let mut word = 0;
+
+		// Wrapped words go back in front of the rest.
+		let mut chars: VecDeque<char> = paragraph.chars().collect();
+		while let Some(c) = chars.pop_front() {
+			let Some(advance) = font.advance(c) else {
+				continue;
+			};
+
+			let kerning = prev.map_or(0.0, |p| font.kerning(p, c));
+			let x       = line.pen() + kerning * scale;
+			let advance = advance * scale;
+			let space   = c.is_whitespace();
+
+			// Spaces may hang past the end, they are dropped anyway.
+			if self.wrap && !space && x + advance > self.max_width && line.inked {
+				let before_word = line.glyphs[..word].iter().any(|g| !g.0.is_whitespace());
+				let wrapped = if before_word { line.glyphs.split_off(word) } else { Vec::new() };
+
+				chars.push_front(c);
+				for g in wrapped.iter().rev() {
+					chars.push_front(g.0);
+				}
+
+				lines.push(std::mem::take(&mut line).finish(false));
+				prev = None;
+				word = 0;
+				continue;
+			}
+
+			line.push(c, x, advance);
+			prev = Some(c);
+			if space {
+				word = line.glyphs.len();
+			}
+		}
+
+		lines.push(line.finish(true));
+	}
+
+	/// Ends `line` with an ellipsis, dropping glyphs to make room for
+	/// it.
+	fn cut_off<M: Metrics + ?Sized>(&self, line: &mut RawLine, font: &M, scale: f32) {
+		let dots: &[char] = if font.advance(ELLIPSIS).is_some() { &[ELLIPSIS] } else { &['.', '.', '.'] };
+		let width: f32 = dots
+			.iter()
+			.filter_map(|c| font.advance(*c))
+			.map(|advance| advance * scale)
+			.sum();
+
+		while let Some(&(c, x, advance)) = line.glyphs.last() {
+			if !c.is_whitespace() && x + advance + width <= self.max_width {
+				break;
+			}
+			line.glyphs.pop();
+		}
+
+		for &c in dots {
+			if let Some(advance) = font.advance(c) {
+				let x = line.pen();
+				line.push(c, x, advance * scale);
+			}
+		}
+		line.width = line.pen();
+		line.last  = true;
+	}
+
+	/// Aligns `lines` & stacks them up.
+	fn position<M: Metrics + ?Sized>(&self, lines: Vec<RawLine>, font: &M, scale: f32) -> TextLayout {
+		let metrics     = font.line_metrics();
+		let line_height = metrics.line_height() * scale;
+
+		let width = lines.iter().map(|l| l.width).fold(0.0, f32::max);
+		// Without a width to fit, lines align to the widest one.
+		let frame = if self.max_width.is_finite() { self.max_width } else { width };
+
+		let mut layout = TextLayout {
+			size: self.size,
+			width,
+			height: lines.len() as f32 * line_height,
+			..TextLayout::default()
+		};
+
+		for (i, line) in lines.into_iter().enumerate() {
+			let baseline = metrics.ascent * scale + i as f32 * line_height;
+			let spaces   = line.glyphs.iter().filter(|g| g.0.is_whitespace()).count();
+
+			let (mut shift, stretch) = match self.align {
+				Align::Left    => (0.0, 0.0),
+				Align::Center  => ((frame - line.width) * 0.5, 0.0),
+				Align::Right   => (frame - line.width, 0.0),
+				Align::Justify => {
+					if line.last || spaces == 0 {
+						(0.0, 0.0)
+					} else {
+						(0.0, (frame - line.width).max(0.0) / spaces as f32)
+					}
+				},
+			};
+
+			let start = layout.glyphs.len();
+			for (c, x, _) in line.glyphs {
+				layout.glyphs.push(PositionedGlyph { c, pos: [x + shift, baseline] });
+				if c.is_whitespace() {
+					shift += stretch;
+				}
+			}
+			layout.lines.push(Line {
+				glyphs: start..layout.glyphs.len(),
+				baseline,
+				width: line.width,
+			});
+		}
+
+		layout
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::font::dejavu;
+	use crate::text::{Glyph, LineMetrics};
+	use crate::texture::TextureId;
+
+	/// Letters are 10 pixels wide, spaces & dots 5, lines 10 high.
+	fn font() -> Font {
+		let mut font = Font::new(TextureId(0), (64, 64), 10.0, LineMetrics::new(8.0, -2.0, 0.0));
+		for c in ['a', 'b'] {
+			font.add_glyph(c, Glyph { rect: [1, 1, 8, 8], offset: [1.0, -8.0], advance: 10.0 });
+		}
+		for c in [' ', '.'] {
+			font.add_glyph(c, Glyph { rect: [0; 4], offset: [0.0, 0.0], advance: 5.0 });
+		}
+		font
+	}
+
+	fn line_text(layout: &TextLayout, i: usize) -> String {
+		layout.glyphs[layout.lines[i].glyphs.clone()].iter().map(|g| g.c).collect()
+	}
+
+	#[test]
+	fn wraps_words() {
+		let layout = Layout::new(10.0).with_max_width(50.0).layout("aa aa ab", &font());
+
+		assert_eq!(layout.lines.len(), 2);
+		assert_eq!(line_text(&layout, 0), "aa aa");
+		assert_eq!(layout.lines[0].width, 45.0);
+		// The wrapped word starts the next line.
+		assert_eq!(line_text(&layout, 1), "ab");
+		assert_eq!(layout.glyphs[5].pos, [0.0, 18.0]);
+		assert_eq!(layout.glyphs[6].pos, [10.0, 18.0]);
+		assert_eq!((layout.width, layout.height), (45.0, 20.0));
+	}
+
+	#[test]
+	fn breaks_long_words_and_lines() {
+		let layout = Layout::new(10.0).with_max_width(30.0).layout("aaaaaaa\r\n\nb", &font());
+
+		let lines: Vec<_> = (0..layout.lines.len()).map(|i| line_text(&layout, i)).collect();
+		assert_eq!(lines, ["aaa", "aaa", "a", "", "b"]);
+		assert_eq!(layout.lines[4].baseline, 48.0);
+	}
+
+	#[test]
+	fn aligns_lines() {
+		let text = "aa a\nb";
+		let x = |align| {
+			let layout = Layout::new(10.0).with_max_width(40.0).with_align(align).layout(text, &font());
+			[layout.glyphs[0].pos[0], layout.glyphs[3].pos[0], layout.glyphs[4].pos[0]]
+		};
+
+		assert_eq!(x(Align::Left),   [0.0, 25.0, 0.0]);
+		assert_eq!(x(Align::Center), [2.5, 27.5, 15.0]);
+		assert_eq!(x(Align::Right),  [5.0, 30.0, 30.0]);
+		// Lines ending paragraphs are left as they are.
+		assert_eq!(x(Align::Justify), [0.0, 25.0, 0.0]);
+
+		// Wrapped, the first line fills the width.
+		let layout = Layout::new(10.0).with_max_width(35.0).with_align(Align::Justify).layout("a a a", &font());
+		assert_eq!(line_text(&layout, 0), "a a");
+		assert_eq!(layout.glyphs[2].pos[0], 25.0);
+		assert_eq!(layout.glyphs[3].pos[0], 0.0);
+	}
+
+	#[test]
+	fn cuts_off_with_an_ellipsis() {
+		let font = font();
+
+		let layout = Layout::new(10.0).with_max_width(50.0).with_max_lines(1).layout("aa aa aa", &font);
+		assert_eq!(layout.lines.len(), 1);
+		assert_eq!(line_text(&layout, 0), "aa a...");
+		assert_eq!(layout.lines[0].width, 50.0);
+
+		let layout = Layout::new(10.0).with_max_width(35.0).with_wrap(false).layout("ab\nbbbb", &font);
+		assert_eq!(line_text(&layout, 0), "ab");
+		assert_eq!(line_text(&layout, 1), "bb...");
+	}
+
+	#[test]
+	fn scales_to_the_size() {
+		let layout = Layout::new(20.0).layout("ab", &font());
+		assert_eq!(layout.glyphs[1].pos, [20.0, 16.0]);
+		assert_eq!(layout.height, 20.0);
+	}
+
+	#[test]
+	fn lays_out_faces() {
+		let face  = dejavu();
+		let glyph = |c| face.glyph_index(c).unwrap();
+		let layout = Layout::new(20.48).layout("AV", &face);
+
+		// 2048 units per em, scaled to a hundredth.
+		let kerning = face.kerning(glyph('A'), glyph('V'));
+		assert!((layout.glyphs[1].pos[0] - (1401.0 + kerning) * 0.01).abs() < 1e-3);
+		assert!((layout.glyphs[1].pos[1] - 19.01).abs() < 1e-3);
+	}
+}
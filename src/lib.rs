@@ -0,0 +1,27 @@
+//! Renders UIs in the fewest draw-calls possible, all primitives
+//! go through a single indexed draw.
+//!
+//! Primitives are written into a prima buffer via the
+//! [`encoder::PrimaEncoder`], the format is described in [`index`].
+
+
+use std::error::Error;
+
+pub mod cache;
+pub mod encoder;
+pub mod font;
+pub mod headless;
+pub mod index;
+pub mod layout;
+pub mod platform;
+pub mod raster;
+pub mod renderer;
+pub mod text;
+pub mod texture;
+
+mod vulkan;
+
+pub type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+/// Color every frame starts with, linear RGBA.
+pub const CLEAR_COLOR: [f32; 4] = [0.4, 0.6, 0.45, 1.0];
@@ -0,0 +1,89 @@
+//! Windowing, one backend per platform behind the [`Window`] trait.
+
+use std::ffi::CStr;
+
+use ash::{vk, Entry};
+
+use crate::Result;
+
+/// Declares a struct with function pointers from a shared library
+/// loaded at runtime, `static`s become pointers to the data symbols.
+#[cfg(target_os = "linux")]
+macro_rules! library {
+	($name:ident, $file:literal, {
+		$(pub fn $f:ident($($arg:ty),*) $(-> $ret:ty)?;)*
+		$(static $s:ident: $t:ty;)*
+	}) => (
+		pub struct $name {
+			// Keeps the symbols below valid.
+			_library: ::libloading::Library,
+			$(pub $f: unsafe extern "C" fn($($arg),*) $(-> $ret)?,)*
+			$(pub $s: *const $t,)*
+		}
+
+		impl $name {
+			pub fn load() -> Result<Self, ::libloading::Error> {
+				unsafe {
+					let library = ::libloading::Library::new($file)?;
+					$(let $f = *library.get(concat!(stringify!($f), "\0").as_bytes())?;)*
+					$(let $s = *library.get(concat!(stringify!($s), "\0").as_bytes())?;)*
+					Ok(Self { _library: library, $($f,)* $($s,)* })
+				}
+			}
+		}
+	)
+}
+
+#[cfg(target_os = "linux")]
+pub mod wayland;
+#[cfg(windows)]
+pub mod win32;
+#[cfg(target_os = "linux")]
+pub mod x11;
+
+/// A window the renderer can present into.
+pub trait Window {
+	fn show(&self);
+
+	/// Processes pending window events, returns `false` once the
+	/// window has been closed.
+	fn pump(&mut self) -> bool;
+
+	fn client_size(&self) -> (u32, u32);
+
+	/// Instance extension needed by [`Window::create_surface`].
+	fn surface_extension(&self) -> &'static CStr;
+
+	/// # Safety
+	///
+	/// The surface must be destroyed before the window.
+	unsafe fn create_surface(&self, entry: &Entry, instance: &ash::Instance) -> Result<vk::SurfaceKHR>;
+}
+
+/// Creates a window with the backend native to the platform. On Linux
+/// X11 is tried if Wayland fails, the error tells why both did.
+pub fn create_window(width: u32, height: u32) -> Result<Box<dyn Window>> {
+	#[cfg(windows)]
+	return Ok(Box::new(win32::Window::new(width, height)));
+
+	#[cfg(target_os = "linux")]
+	{
+		// XWayland is the fallback, if the compositor lacks something.
+		let mut wayland_error = None;
+		if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+			match wayland::Window::new(width, height) {
+				Ok(window) => return Ok(Box::new(window)),
+				Err(e)     => wayland_error = Some(e),
+			}
+		}
+
+		return match (x11::Window::new(width, height), wayland_error) {
+			(Ok(window), _)       => Ok(Box::new(window)),
+			(Err(e), None)        => Err(e),
+			(Err(e), Some(first)) => Err(format!("Failed to create a Wayland window due to {first}, then an X11 one due to {e}").into()),
+		};
+	}
+
+	#[cfg(not(any(windows, target_os = "linux")))]
+	return Err("There is no windowing backend for this platform.".into());
+}
@@ -0,0 +1,536 @@
+//! Wayland windowing via libwayland-client & an xdg-shell toplevel.
+//!
+//! libwayland-client is loaded at runtime, the xdg-shell interfaces
+//! are not part of it and live down in [`ffi`].
+//!
+//! The window shows up with the first presented frame, there is no
+//! input handling yet.
+
+use std::ffi::{c_char, c_void, CStr};
+use std::ptr;
+
+use ash::{vk, Entry};
+use ash::extensions::khr;
+
+use crate::Result;
+use crate::platform;
+
+use ffi::*;
+
+pub struct Window {
+	wl:          WaylandClient,
+	display:     *mut wl_proxy,
+	registry:    *mut wl_proxy,
+	compositor:  *mut wl_proxy,
+	wm_base:     *mut wl_proxy,
+	surface:     *mut wl_proxy,
+	xdg_surface: *mut wl_proxy,
+	toplevel:    *mut wl_proxy,
+	// Boxed, as the listeners point to it.
+	state:       Box<State>,
+}
+
+/// Written by the listeners, the window acts on it after dispatching.
+struct State {
+	compositor: Option<(u32, u32)>,
+	wm_base:    Option<(u32, u32)>,
+	size:       (u32, u32),
+	pending:    (u32, u32),
+	ping:       Option<u32>,
+	configure:  Option<u32>,
+	configured: bool,
+	closed:     bool,
+}
+
+impl Window {
+	pub fn new(width: u32, height: u32) -> Result<Self> {
+		unsafe {
+			let wl = WaylandClient::load()?;
+
+			let display = (wl.wl_display_connect)(ptr::null());
+			if display.is_null() {
+				return Err("Failed to connect to the Wayland display, is WAYLAND_DISPLAY set?".into());
+			}
+
+			let mut window = Self {
+				wl,
+				display,
+				registry:    ptr::null_mut(),
+				compositor:  ptr::null_mut(),
+				wm_base:     ptr::null_mut(),
+				surface:     ptr::null_mut(),
+				xdg_surface: ptr::null_mut(),
+				toplevel:    ptr::null_mut(),
+				state:       Box::new(State {
+					compositor: None,
+					wm_base:    None,
+					size:       (width, height),
+					pending:    (width, height),
+					ping:       None,
+					configure:  None,
+					configured: false,
+					closed:     false,
+				}),
+			};
+
+			// From now on, drop takes care of the cleanup.
+			window.create_toplevel()?;
+
+			Ok(window)
+		}
+	}
+
+	unsafe fn create_toplevel(&mut self) -> Result<()> {
+		let state = &mut *self.state as *mut State as *mut c_void;
+
+		self.registry = self.request(self.display, WL_DISPLAY_GET_REGISTRY, self.wl.wl_registry_interface, &mut [NEW_ID]);
+		(self.wl.wl_proxy_add_listener)(self.registry, &REGISTRY_LISTENER as *const _ as _, state);
+		self.roundtrip()?;
+
+		let (Some(compositor), Some(wm_base)) = (self.state.compositor, self.state.wm_base) else {
+			return Err("The compositor doesn't support xdg-shell.".into());
+		};
+
+		self.compositor = self.bind(compositor, self.wl.wl_compositor_interface);
+		self.wm_base    = self.bind(wm_base, &XDG_WM_BASE_INTERFACE);
+		(self.wl.wl_proxy_add_listener)(self.wm_base, &WM_BASE_LISTENER as *const _ as _, state);
+
+		self.surface     = self.request(self.compositor, WL_COMPOSITOR_CREATE_SURFACE, self.wl.wl_surface_interface, &mut [NEW_ID]);
+		self.xdg_surface = self.request(self.wm_base, XDG_WM_BASE_GET_XDG_SURFACE, &XDG_SURFACE_INTERFACE, &mut [
+			NEW_ID,
+			wl_argument { o: self.surface },
+		]);
+		(self.wl.wl_proxy_add_listener)(self.xdg_surface, &XDG_SURFACE_LISTENER as *const _ as _, state);
+
+		self.toplevel = self.request(self.xdg_surface, XDG_SURFACE_GET_TOPLEVEL, &XDG_TOPLEVEL_INTERFACE, &mut [NEW_ID]);
+		(self.wl.wl_proxy_add_listener)(self.toplevel, &XDG_TOPLEVEL_LISTENER as *const _ as _, state);
+		self.request(self.toplevel, XDG_TOPLEVEL_SET_TITLE, ptr::null(), &mut [wl_argument { s: c"Prima!".as_ptr() }]);
+
+		// Nothing can be presented before the first configure is acked.
+		self.request(self.surface, WL_SURFACE_COMMIT, ptr::null(), &mut []);
+		while !self.state.configured {
+			self.roundtrip()?;
+			self.respond();
+		}
+
+		Ok(())
+	}
+
+	unsafe fn request(
+		&self,
+		proxy: *mut wl_proxy,
+		opcode: u32,
+		interface: *const wl_interface,
+		args: &mut [wl_argument],
+	) -> *mut wl_proxy {
+		self.marshal(proxy, opcode, interface, 0, args)
+	}
+
+	unsafe fn destroy(&self, proxy: *mut wl_proxy, opcode: u32) {
+		if !proxy.is_null() {
+			self.marshal(proxy, opcode, ptr::null(), WL_MARSHAL_FLAG_DESTROY, &mut []);
+		}
+	}
+
+	unsafe fn marshal(
+		&self,
+		proxy: *mut wl_proxy,
+		opcode: u32,
+		interface: *const wl_interface,
+		flags: u32,
+		args: &mut [wl_argument],
+	) -> *mut wl_proxy {
+		let version = (self.wl.wl_proxy_get_version)(proxy);
+		(self.wl.wl_proxy_marshal_array_flags)(proxy, opcode, interface, version, flags, args.as_mut_ptr())
+	}
+
+	unsafe fn bind(&self, (name, version): (u32, u32), interface: *const wl_interface) -> *mut wl_proxy {
+		(self.wl.wl_proxy_marshal_array_flags)(self.registry, WL_REGISTRY_BIND, interface, version, 0, [
+			wl_argument { u: name },
+			wl_argument { s: (*interface).name },
+			wl_argument { u: version },
+			NEW_ID,
+		].as_mut_ptr())
+	}
+
+	unsafe fn roundtrip(&self) -> Result<()> {
+		if (self.wl.wl_display_roundtrip)(self.display) < 0 {
+			return Err("Lost the connection to the Wayland display.".into());
+		}
+		Ok(())
+	}
+
+	/// Answers whatever the listeners have collected.
+	unsafe fn respond(&mut self) {
+		if let Some(serial) = self.state.ping.take() {
+			self.request(self.wm_base, XDG_WM_BASE_PONG, ptr::null(), &mut [wl_argument { u: serial }]);
+		}
+
+		if let Some(serial) = self.state.configure.take() {
+			self.request(self.xdg_surface, XDG_SURFACE_ACK_CONFIGURE, ptr::null(), &mut [wl_argument { u: serial }]);
+			self.state.size       = self.state.pending;
+			self.state.configured = true;
+		}
+	}
+
+	/// Reads & dispatches the pending events without blocking.
+	unsafe fn dispatch(&mut self) -> bool {
+		let wl = &self.wl;
+
+		while (wl.wl_display_prepare_read)(self.display) != 0 {
+			if (wl.wl_display_dispatch_pending)(self.display) < 0 {
+				return false;
+			}
+		}
+		(wl.wl_display_flush)(self.display);
+
+		let mut fd = pollfd {
+			fd:      (wl.wl_display_get_fd)(self.display),
+			events:  POLLIN,
+			revents: 0,
+		};
+		if poll(&mut fd, 1, 0) > 0 {
+			if (wl.wl_display_read_events)(self.display) < 0 {
+				return false;
+			}
+		} else {
+			(wl.wl_display_cancel_read)(self.display);
+		}
+
+		(wl.wl_display_dispatch_pending)(self.display) >= 0
+	}
+}
+
+impl platform::Window for Window {
+	fn show(&self) {
+		// Wayland surfaces become visible once something is presented.
+	}
+
+	fn pump(&mut self) -> bool {
+		unsafe {
+			if !self.dispatch() {
+				self.state.closed = true;
+			}
+			self.respond();
+			(self.wl.wl_display_flush)(self.display);
+		}
+
+		!self.state.closed
+	}
+
+	fn client_size(&self) -> (u32, u32) {
+		self.state.size
+	}
+
+	fn surface_extension(&self) -> &'static CStr {
+		khr::WaylandSurface::name()
+	}
+
+	unsafe fn create_surface(&self, entry: &Entry, instance: &ash::Instance) -> Result<vk::SurfaceKHR> {
+		let surface_create_info = vk::WaylandSurfaceCreateInfoKHR::builder()
+			.display(self.display as *mut vk::wl_display)
+			.surface(self.surface as *mut vk::wl_surface);
+
+		let khr_wayland_surface = khr::WaylandSurface::new(entry, instance);
+		let surface             = khr_wayland_surface.create_wayland_surface(&surface_create_info, None)?;
+
+		Ok(surface)
+	}
+}
+
+impl Drop for Window {
+	fn drop(&mut self) {
+		unsafe {
+			self.destroy(self.toplevel, XDG_TOPLEVEL_DESTROY);
+			self.destroy(self.xdg_surface, XDG_SURFACE_DESTROY);
+			self.destroy(self.surface, WL_SURFACE_DESTROY);
+			self.destroy(self.wm_base, XDG_WM_BASE_DESTROY);
+			// Neither has a destructor request.
+			for proxy in [self.compositor, self.registry] {
+				if !proxy.is_null() {
+					(self.wl.wl_proxy_destroy)(proxy);
+				}
+			}
+			(self.wl.wl_display_disconnect)(self.display);
+		}
+	}
+}
+
+// Listeners.
+
+static REGISTRY_LISTENER: wl_registry_listener = wl_registry_listener {
+	global:        registry_global,
+	global_remove: registry_global_remove,
+};
+
+static WM_BASE_LISTENER: xdg_wm_base_listener = xdg_wm_base_listener {
+	ping: wm_base_ping,
+};
+
+static XDG_SURFACE_LISTENER: xdg_surface_listener = xdg_surface_listener {
+	configure: xdg_surface_configure,
+};
+
+static XDG_TOPLEVEL_LISTENER: xdg_toplevel_listener = xdg_toplevel_listener {
+	configure: xdg_toplevel_configure,
+	close:     xdg_toplevel_close,
+};
+
+unsafe extern "C" fn registry_global(
+	data: *mut c_void,
+	_registry: *mut wl_proxy,
+	name: u32,
+	interface: *const c_char,
+	version: u32,
+) {
+	let state = &mut *(data as *mut State);
+	match CStr::from_ptr(interface).to_bytes() {
+		b"wl_compositor" => state.compositor = Some((name, version.min(4))),
+		b"xdg_wm_base"   => state.wm_base    = Some((name, 1)),
+		_ => {},
+	}
+}
+
+unsafe extern "C" fn registry_global_remove(_data: *mut c_void, _registry: *mut wl_proxy, _name: u32) {}
+
+unsafe extern "C" fn wm_base_ping(data: *mut c_void, _wm_base: *mut wl_proxy, serial: u32) {
+	let state = &mut *(data as *mut State);
+	state.ping = Some(serial);
+}
+
+unsafe extern "C" fn xdg_surface_configure(data: *mut c_void, _xdg_surface: *mut wl_proxy, serial: u32) {
+	let state = &mut *(data as *mut State);
+	state.configure = Some(serial);
+}
+
+unsafe extern "C" fn xdg_toplevel_configure(
+	data: *mut c_void,
+	_toplevel: *mut wl_proxy,
+	width: i32,
+	height: i32,
+	_states: *mut wl_array,
+) {
+	// Zero means it is up to us, so the size stays as is.
+	let state = &mut *(data as *mut State);
+	if width > 0 && height > 0 {
+		state.pending = (width as u32, height as u32);
+	}
+}
+
+unsafe extern "C" fn xdg_toplevel_close(data: *mut c_void, _toplevel: *mut wl_proxy) {
+	let state = &mut *(data as *mut State);
+	state.closed = true;
+}
+
+#[allow(non_camel_case_types)]
+#[allow(non_snake_case)]
+#[allow(dead_code)]
+mod ffi {
+	use std::ffi::{self, c_char, c_void};
+	use std::ptr;
+
+	pub enum wl_proxy {}
+
+	#[repr(C)]
+	pub struct wl_message {
+		pub name: *const c_char,
+		pub signature: *const c_char,
+		pub types: *const *const wl_interface,
+	}
+
+	#[repr(C)]
+	pub struct wl_interface {
+		pub name: *const c_char,
+		pub version: ffi::c_int,
+		pub method_count: ffi::c_int,
+		pub methods: *const wl_message,
+		pub event_count: ffi::c_int,
+		pub events: *const wl_message,
+	}
+
+	// Only ever pointing to other statics.
+	unsafe impl Sync for wl_message {}
+	unsafe impl Sync for wl_interface {}
+
+	#[repr(C)]
+	pub struct wl_array {
+		pub size: usize,
+		pub alloc: usize,
+		pub data: *mut c_void,
+	}
+
+	#[repr(C)]
+	#[derive(Clone, Copy)]
+	pub union wl_argument {
+		pub i: i32,
+		pub u: u32,
+		pub s: *const c_char,
+		pub o: *mut wl_proxy,
+		pub n: u32,
+		pub a: *mut wl_array,
+		pub h: i32,
+	}
+
+	/// Placeholder for the new object of a request, libwayland fills it in.
+	pub const NEW_ID: wl_argument = wl_argument { o: ptr::null_mut() };
+
+	pub const WL_MARSHAL_FLAG_DESTROY: u32 = 1 << 0;
+
+	// Request opcodes, in the order of the protocol XML.
+	pub const WL_DISPLAY_GET_REGISTRY: u32      = 1;
+	pub const WL_REGISTRY_BIND: u32             = 0;
+	pub const WL_COMPOSITOR_CREATE_SURFACE: u32 = 0;
+	pub const WL_SURFACE_DESTROY: u32           = 0;
+	pub const WL_SURFACE_COMMIT: u32            = 6;
+	pub const XDG_WM_BASE_DESTROY: u32          = 0;
+	pub const XDG_WM_BASE_GET_XDG_SURFACE: u32  = 2;
+	pub const XDG_WM_BASE_PONG: u32             = 3;
+	pub const XDG_SURFACE_DESTROY: u32          = 0;
+	pub const XDG_SURFACE_GET_TOPLEVEL: u32     = 1;
+	pub const XDG_SURFACE_ACK_CONFIGURE: u32    = 4;
+	pub const XDG_TOPLEVEL_DESTROY: u32         = 0;
+	pub const XDG_TOPLEVEL_SET_TITLE: u32       = 2;
+
+	#[repr(C)]
+	pub struct wl_registry_listener {
+		pub global: unsafe extern "C" fn(*mut c_void, *mut wl_proxy, u32, *const c_char, u32),
+		pub global_remove: unsafe extern "C" fn(*mut c_void, *mut wl_proxy, u32),
+	}
+
+	#[repr(C)]
+	pub struct xdg_wm_base_listener {
+		pub ping: unsafe extern "C" fn(*mut c_void, *mut wl_proxy, u32),
+	}
+
+	#[repr(C)]
+	pub struct xdg_surface_listener {
+		pub configure: unsafe extern "C" fn(*mut c_void, *mut wl_proxy, u32),
+	}
+
+	#[repr(C)]
+	pub struct xdg_toplevel_listener {
+		pub configure: unsafe extern "C" fn(*mut c_void, *mut wl_proxy, i32, i32, *mut wl_array),
+		pub close: unsafe extern "C" fn(*mut c_void, *mut wl_proxy),
+	}
+
+	// xdg-shell, version 1.
+
+	macro_rules! message {
+		($name:literal, $signature:literal, $types:expr) => (
+			wl_message { name: $name.as_ptr(), signature: $signature.as_ptr(), types: $types.as_ptr() }
+		)
+	}
+
+	macro_rules! interface {
+		($name:literal, $methods:ident, $events:ident) => (
+			wl_interface {
+				name: $name.as_ptr(),
+				version: 1,
+				method_count: $methods.len() as ffi::c_int,
+				methods: $methods.as_ptr(),
+				event_count: $events.len() as ffi::c_int,
+				events: $events.as_ptr(),
+			}
+		)
+	}
+
+	struct Types<const N: usize>([*const wl_interface; N]);
+
+	unsafe impl<const N: usize> Sync for Types<N> {}
+
+	// Only new_id & object arguments have a type, wl_surface and
+	// xdg_positioner/xdg_popup aren't known statically, hence null.
+	static NO_TYPES: Types<4> = Types([ptr::null(); 4]);
+	static XDG_SURFACE_TYPES: Types<2> = Types([&XDG_SURFACE_INTERFACE, ptr::null()]);
+	static XDG_TOPLEVEL_TYPES: Types<1> = Types([&XDG_TOPLEVEL_INTERFACE]);
+
+	static XDG_WM_BASE_REQUESTS: [wl_message; 4] = [
+		message!(c"destroy", c"", NO_TYPES.0),
+		message!(c"create_positioner", c"n", NO_TYPES.0),
+		message!(c"get_xdg_surface", c"no", XDG_SURFACE_TYPES.0),
+		message!(c"pong", c"u", NO_TYPES.0),
+	];
+
+	static XDG_WM_BASE_EVENTS: [wl_message; 1] = [
+		message!(c"ping", c"u", NO_TYPES.0),
+	];
+
+	pub static XDG_WM_BASE_INTERFACE: wl_interface = interface!(c"xdg_wm_base", XDG_WM_BASE_REQUESTS, XDG_WM_BASE_EVENTS);
+
+	static XDG_SURFACE_REQUESTS: [wl_message; 5] = [
+		message!(c"destroy", c"", NO_TYPES.0),
+		message!(c"get_toplevel", c"n", XDG_TOPLEVEL_TYPES.0),
+		message!(c"get_popup", c"n?oo", NO_TYPES.0),
+		message!(c"set_window_geometry", c"iiii", NO_TYPES.0),
+		message!(c"ack_configure", c"u", NO_TYPES.0),
+	];
+
+	static XDG_SURFACE_EVENTS: [wl_message; 1] = [
+		message!(c"configure", c"u", NO_TYPES.0),
+	];
+
+	pub static XDG_SURFACE_INTERFACE: wl_interface = interface!(c"xdg_surface", XDG_SURFACE_REQUESTS, XDG_SURFACE_EVENTS);
+
+	static XDG_TOPLEVEL_REQUESTS: [wl_message; 14] = [
+		message!(c"destroy", c"", NO_TYPES.0),
+		message!(c"set_parent", c"?o", XDG_TOPLEVEL_TYPES.0),
+		message!(c"set_title", c"s", NO_TYPES.0),
+		message!(c"set_app_id", c"s", NO_TYPES.0),
+		message!(c"show_window_menu", c"ouii", NO_TYPES.0),
+		message!(c"move", c"ou", NO_TYPES.0),
+		message!(c"resize", c"ouu", NO_TYPES.0),
+		message!(c"set_max_size", c"ii", NO_TYPES.0),
+		message!(c"set_min_size", c"ii", NO_TYPES.0),
+		message!(c"set_maximized", c"", NO_TYPES.0),
+		message!(c"unset_maximized", c"", NO_TYPES.0),
+		message!(c"set_fullscreen", c"?o", NO_TYPES.0),
+		message!(c"unset_fullscreen", c"", NO_TYPES.0),
+		message!(c"set_minimized", c"", NO_TYPES.0),
+	];
+
+	static XDG_TOPLEVEL_EVENTS: [wl_message; 2] = [
+		message!(c"configure", c"iia", NO_TYPES.0),
+		message!(c"close", c"", NO_TYPES.0),
+	];
+
+	pub static XDG_TOPLEVEL_INTERFACE: wl_interface = interface!(c"xdg_toplevel", XDG_TOPLEVEL_REQUESTS, XDG_TOPLEVEL_EVENTS);
+
+	library!(WaylandClient, "libwayland-client.so.0", {
+		pub fn wl_display_connect(*const c_char) -> *mut wl_proxy;
+		pub fn wl_display_disconnect(*mut wl_proxy);
+		pub fn wl_display_roundtrip(*mut wl_proxy) -> ffi::c_int;
+		pub fn wl_display_dispatch_pending(*mut wl_proxy) -> ffi::c_int;
+		pub fn wl_display_flush(*mut wl_proxy) -> ffi::c_int;
+		pub fn wl_display_prepare_read(*mut wl_proxy) -> ffi::c_int;
+		pub fn wl_display_read_events(*mut wl_proxy) -> ffi::c_int;
+		pub fn wl_display_cancel_read(*mut wl_proxy);
+		pub fn wl_display_get_fd(*mut wl_proxy) -> ffi::c_int;
+		pub fn wl_proxy_add_listener(*mut wl_proxy, *const Option<unsafe extern "C" fn()>, *mut c_void) -> ffi::c_int;
+		pub fn wl_proxy_marshal_array_flags(
+			*mut wl_proxy,
+			u32,
+			*const wl_interface,
+			u32,
+			u32,
+			*mut wl_argument
+		) -> *mut wl_proxy;
+		pub fn wl_proxy_get_version(*mut wl_proxy) -> u32;
+		pub fn wl_proxy_destroy(*mut wl_proxy);
+		static wl_registry_interface: wl_interface;
+		static wl_compositor_interface: wl_interface;
+		static wl_surface_interface: wl_interface;
+	});
+
+	pub const POLLIN: ffi::c_short = 0x001;
+
+	#[repr(C)]
+	pub struct pollfd {
+		pub fd: ffi::c_int,
+		pub events: ffi::c_short,
+		pub revents: ffi::c_short,
+	}
+
+	extern "C" {
+		pub fn poll(fds: *mut pollfd, nfds: ffi::c_ulong, timeout: ffi::c_int) -> ffi::c_int;
+	}
+}
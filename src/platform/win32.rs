@@ -0,0 +1,364 @@
+//! Win32 windowing.
+
+use std::ffi::CStr;
+use std::ptr;
+
+use ash::{vk, Entry};
+use ash::extensions::khr;
+
+use crate::Result;
+use crate::platform;
+
+use ffi::*;
+
+const WINDOW_CLASS_NAME: &CStr = c"PRIMA_CLASS";
+
+pub struct Window {
+	hinstance: HINSTANCE,
+	hwnd:      HWND,
+}
+
+impl Window {
+	pub fn new(width: u32, height: u32) -> Self {
+		unsafe {
+			let hinstance = GetModuleHandleA(ptr::null());
+
+			register_window_class(hinstance);
+			let hwnd = create_window(hinstance, width, height);
+
+			Self { hinstance, hwnd }
+		}
+	}
+}
+
+impl platform::Window for Window {
+	fn show(&self) {
+		unsafe { ShowWindow(self.hwnd, SW_SHOW) };
+	}
+
+	fn pump(&mut self) -> bool {
+		let mut msg = MSG::default();
+		loop {
+			// Lazy drawing.
+			// let got_msg = unsafe { GetMessageA(&mut msg, ptr::null_mut(), 0, 0) };
+			// The usual v-synced drawing.
+			let got_msg = unsafe { PeekMessageA(&mut msg, ptr::null_mut(), 0, 0, PM_REMOVE) };
+			match got_msg {
+				0  => return true, // Nothing left, time to render.
+				-1 => {
+					let last_error = unsafe { GetLastError() };
+					panic!("Failed to peek window messages, error code = {last_error}");
+				},
+				_ => unsafe {
+					TranslateMessage(&msg);
+					DispatchMessageA(&msg);
+
+					if msg.message == WM_QUIT {
+						return false;
+					}
+				},
+			}
+		}
+	}
+
+	fn client_size(&self) -> (u32, u32) {
+		get_window_client_size(self.hwnd)
+	}
+
+	fn surface_extension(&self) -> &'static CStr {
+		khr::Win32Surface::name()
+	}
+
+	unsafe fn create_surface(&self, entry: &Entry, instance: &ash::Instance) -> Result<vk::SurfaceKHR> {
+		let surface_create_info = vk::Win32SurfaceCreateInfoKHR::builder()
+			.hinstance(self.hinstance)
+			.hwnd(self.hwnd);
+
+		let khr_w32_surface = khr::Win32Surface::new(entry, instance);
+		let surface         = khr_w32_surface.create_win32_surface(&surface_create_info, None)?;
+
+		Ok(surface)
+	}
+}
+
+unsafe fn register_window_class(hinstance: HINSTANCE) -> ATOM {
+	let wc = WNDCLASSA {
+		lpfnWndProc: Some(window_procedure),
+		hInstance: hinstance,
+		lpszClassName: WINDOW_CLASS_NAME.as_ptr(),
+		hCursor: LoadCursorA(ptr::null_mut(), IDC_ARROW),
+		..Default::default()
+	};
+
+	let atom = RegisterClassA(&wc);
+	if atom == 0 {
+		let last_error = GetLastError();
+		panic!("Failed to register the window class, error code = {last_error}");
+	}
+
+	atom
+}
+
+unsafe fn create_window(hinstance: HINSTANCE, width: u32, height: u32) -> HWND {
+	let window_name = c"Prima!";
+	let hwnd = CreateWindowExA(
+			0,
+			WINDOW_CLASS_NAME.as_ptr(),
+			window_name.as_ptr(),
+			WS_OVERLAPPEDWINDOW,
+			// TODO: Center the window or load/save the last position.
+			CW_USEDEFAULT,
+			CW_USEDEFAULT,
+			// TODO: Deal with scaling/HI-DPI & window title.
+			width as i32,
+			height as i32,
+			ptr::null_mut(),
+			ptr::null_mut(),
+			hinstance,
+			ptr::null_mut(),
+	);
+
+	if hwnd.is_null() {
+		panic!("Failed to create a window.");
+	}
+
+	hwnd
+}
+
+fn get_window_client_size(hwnd: HWND) -> (u32, u32) {
+	let mut client_rect = RECT::default();
+	let err = unsafe { GetClientRect(hwnd, &mut client_rect) };
+	if err != 1 {
+		panic!("Failed to get window client rect.");
+	}
+
+	(
+		(client_rect.right - client_rect.left) as u32,
+		(client_rect.bottom - client_rect.top) as u32,
+	)
+}
+
+#[allow(non_snake_case)]
+unsafe extern "stdcall" fn window_procedure(
+	hwnd: ffi::HWND,
+	uMsg: ffi::UINT,
+	wParam: ffi::WPARAM,
+	lParam: ffi::LPARAM,
+) -> ffi::LRESULT {
+	use ffi::*;
+
+	match uMsg {
+		WM_CLOSE | WM_KEYDOWN if wParam == VK_ESCAPE => drop(DestroyWindow(hwnd)),
+		WM_DESTROY => PostQuitMessage(0),
+		_ => return DefWindowProcA(hwnd, uMsg, wParam, lParam),
+	};
+	0
+}
+
+#[allow(non_camel_case_types)]
+#[allow(non_snake_case)]
+#[allow(dead_code)]
+#[allow(clippy::upper_case_acronyms)]
+mod ffi {
+	use std::ffi;
+
+	pub type WORD      = ffi::c_ushort;
+	pub type DWORD     = ffi::c_ulong;
+	pub type ATOM      = WORD;
+	pub type BOOL      = ffi::c_int;
+	pub type LPCSTR    = *const i8;
+	pub type HINSTANCE = HANDLE;
+	pub type HANDLE    = PVOID;
+	pub type PVOID     = *mut ffi::c_void;
+	pub type HICON     = HANDLE;
+	pub type HBRUSH    = HANDLE;
+	pub type HCURSOR   = HANDLE;
+	pub type HMENU     = HANDLE;
+	pub type LPVOID    = *mut ffi::c_void;
+	pub type HWND      = HANDLE;
+	pub type WPARAM    = UINT_PTR;
+	pub type LPARAM    = LONG_PTR;
+	pub type LRESULT   = LONG_PTR;
+	pub type LONG      = ffi::c_long;
+	pub type LONG_PTR  = isize;
+	pub type ULONG_PTR = usize;
+	pub type UINT      = ffi::c_uint;
+	pub type UINT_PTR  = usize;
+	pub type LPRECT    = *mut RECT;
+
+	pub const WS_OVERLAPPED: u32       = 0x00000000;
+	pub const WS_CAPTION: u32          = 0x00C00000;
+	pub const WS_SYSMENU: u32          = 0x00080000;
+	pub const WS_THICKFRAME: u32       = 0x00040000;
+	pub const WS_MINIMIZEBOX: u32      = 0x00020000;
+	pub const WS_MAXIMIZEBOX: u32      = 0x00010000;
+	pub const WS_OVERLAPPEDWINDOW: u32 = WS_OVERLAPPED
+		| WS_CAPTION
+		| WS_SYSMENU
+		| WS_THICKFRAME
+		| WS_MINIMIZEBOX
+		| WS_MAXIMIZEBOX;
+	pub const CW_USEDEFAULT: ffi::c_int = 0x80000000_u32 as ffi::c_int;
+
+	pub const SW_SHOW: ffi::c_int = 5;
+
+	pub const PM_REMOVE:  u32 = 0x0001;
+
+	pub const WM_DESTROY: u32 = 0x0002;
+	pub const WM_CLOSE:   u32 = 0x0010;
+	pub const WM_QUIT:    u32 = 0x0012;
+	pub const WM_KEYDOWN: u32 = 0x0100;
+
+	pub const VK_ESCAPE: usize = 0x1B;
+
+	pub const fn MAKEINTRESOURCEA(i: WORD) -> LPCSTR {
+		i as ULONG_PTR as LPCSTR
+	}
+
+	pub const IDC_ARROW: LPCSTR = MAKEINTRESOURCEA(32512);
+
+	pub type WNDPROC = Option<
+		unsafe extern "stdcall" fn(
+			hwnd: HWND,
+			uMsg: UINT,
+			wParam: WPARAM,
+			lParam: LPARAM,
+		) -> LRESULT,
+	>;
+
+	macro_rules! impl_zeroed_default {
+		($name:ident) => (
+			impl Default for $name {
+				#[inline]
+				fn default() -> Self {
+					unsafe { core::mem::zeroed() }
+				}
+			}
+		)
+	}
+
+	#[repr(C)]
+	pub struct WNDCLASSA {
+		pub style: UINT,
+		pub lpfnWndProc: WNDPROC,
+		pub cbClsExtra: ffi::c_int,
+		pub cbWndExtra: ffi::c_int,
+		pub hInstance: HINSTANCE,
+		pub hIcon: HICON,
+		pub hCursor: HCURSOR,
+		pub hbrBackground: HBRUSH,
+		pub lpszMenuName: LPCSTR,
+		pub lpszClassName: LPCSTR,
+	}
+
+	impl_zeroed_default!(WNDCLASSA);
+
+	#[repr(C)]
+	pub struct POINT {
+		pub x: LONG,
+		pub y: LONG,
+	}
+
+	impl_zeroed_default!(POINT);
+
+	#[repr(C)]
+	pub struct MSG {
+		pub hwnd: HWND,
+		pub message: UINT,
+		pub wParam: WPARAM,
+		pub lParam: LPARAM,
+		pub time: DWORD,
+		pub pt: POINT,
+		pub lPrivate: DWORD,
+	}
+
+	impl_zeroed_default!(MSG);
+
+	#[repr(C)]
+	pub struct RECT {
+		pub left: LONG,
+		pub top: LONG,
+		pub right: LONG,
+		pub bottom: LONG,
+	}
+
+	impl_zeroed_default!(RECT);
+
+	#[link(name = "User32")]
+	extern "stdcall" {
+		pub fn RegisterClassA(
+			lpWndClass: *const WNDCLASSA,
+		) -> ATOM;
+
+		pub fn UnregisterClassA(
+			lpClassName: LPCSTR,
+			hInstance: HINSTANCE,
+		) -> BOOL;
+
+		pub fn LoadCursorA(
+			hInstance: HINSTANCE,
+			lpCursorName: LPCSTR,
+		) -> HCURSOR;
+
+		pub fn CreateWindowExA(
+			dwExStyle: DWORD,
+			lpClassName: LPCSTR,
+			lpWindowName: LPCSTR,
+			dwStyle: DWORD,
+			X: ffi::c_int,
+			Y: ffi::c_int,
+			nWidth: ffi::c_int,
+			nHeight: ffi::c_int,
+			hWndParent: HWND,
+			hMenu: HMENU,
+			hInstance: HINSTANCE,
+			lpParam: LPVOID,
+		) -> HWND;
+
+		pub fn ShowWindow(
+			hWnd: HWND,
+			nCmdShow: ffi::c_int,
+		) -> BOOL;
+
+		pub fn DefWindowProcA(
+			hWnd: HWND,
+			Msg: UINT,
+			wParam: WPARAM,
+			lParam: LPARAM,
+		) -> LRESULT;
+
+		pub fn PeekMessageA(
+			lpMsg: *mut MSG,
+			hWnd: HWND,
+			wMsgFilterMin: UINT,
+			wMsgFilterMax: UINT,
+			wRemoveMsg: UINT,
+		) -> BOOL;
+
+		pub fn GetMessageA(
+			lpMsg: *mut MSG,
+			hWnd: HWND,
+			wMsgFilterMin: UINT,
+			wMsgFilterMax: UINT,
+		) -> BOOL;
+
+		pub fn TranslateMessage(lpMsg: *const MSG) -> BOOL;
+
+		pub fn DispatchMessageA(lpMsg: *const MSG) -> LRESULT;
+
+		pub fn DestroyWindow(hWnd: HWND) -> BOOL;
+
+		pub fn PostQuitMessage(nExitCode: ffi::c_int);
+
+		pub fn GetClientRect(hwnd: HWND, lpRect: LPRECT) -> BOOL;
+	}
+
+	#[link(name = "Kernel32")]
+	extern "stdcall" {
+		pub fn GetLastError() -> DWORD;
+
+		pub fn GetModuleHandleA(
+			lpModuleName: LPCSTR,
+		) -> HINSTANCE;
+	}
+}
@@ -0,0 +1,251 @@
+//! X11 windowing via Xlib.
+//!
+//! libX11 is loaded at runtime, so nothing has to be installed to build
+//! and a missing X server is just an error.
+
+use std::ffi::CStr;
+use std::ptr;
+
+use ash::{vk, Entry};
+use ash::extensions::khr;
+
+use crate::Result;
+use crate::platform;
+
+use ffi::*;
+
+pub struct Window {
+	xlib:             Xlib,
+	display:          *mut Display,
+	window:           XWindow,
+	wm_delete_window: Atom,
+	// Kept up to date by ConfigureNotify, saves a round-trip per query.
+	size:             (u32, u32),
+	closed:           bool,
+}
+
+impl Window {
+	pub fn new(width: u32, height: u32) -> Result<Self> {
+		unsafe {
+			let xlib = Xlib::load()?;
+
+			let display = (xlib.XOpenDisplay)(ptr::null());
+			if display.is_null() {
+				return Err("Failed to open the X display, is DISPLAY set?".into());
+			}
+
+			let screen = (xlib.XDefaultScreen)(display);
+			let root   = (xlib.XRootWindow)(display, screen);
+			let black  = (xlib.XBlackPixel)(display, screen);
+
+			let window = (xlib.XCreateSimpleWindow)(display, root, 0, 0, width, height, 0, black, black);
+			if window == 0 {
+				(xlib.XCloseDisplay)(display);
+				return Err("Failed to create a window.".into());
+			}
+
+			(xlib.XStoreName)(display, window, c"Prima!".as_ptr());
+			(xlib.XSelectInput)(display, window, KeyPressMask | StructureNotifyMask);
+
+			// Otherwise the window manager just kills the connection on close.
+			let mut wm_delete_window = (xlib.XInternAtom)(display, c"WM_DELETE_WINDOW".as_ptr(), False);
+			(xlib.XSetWMProtocols)(display, window, &mut wm_delete_window, 1);
+
+			Ok(Self {
+				xlib,
+				display,
+				window,
+				wm_delete_window,
+				size: (width, height),
+				closed: false,
+			})
+		}
+	}
+}
+
+impl platform::Window for Window {
+	fn show(&self) {
+		unsafe {
+			(self.xlib.XMapWindow)(self.display, self.window);
+			(self.xlib.XFlush)(self.display);
+		}
+	}
+
+	// Xlib names its event types in CamelCase.
+	#[allow(non_upper_case_globals)]
+	fn pump(&mut self) -> bool {
+		let xlib = &self.xlib;
+		unsafe {
+			while (xlib.XPending)(self.display) > 0 {
+				let mut event = XEvent { pad: [0; 24] };
+				(xlib.XNextEvent)(self.display, &mut event);
+
+				let event_type = event.type_;
+				match event_type {
+					ClientMessage if event.xclient.data[0] as Atom == self.wm_delete_window => {
+						self.closed = true;
+					},
+					KeyPress if (xlib.XLookupKeysym)(&mut event.xkey, 0) == XK_Escape => {
+						self.closed = true;
+					},
+					ConfigureNotify if event.xconfigure.window == self.window => {
+						let XConfigureEvent { width, height, .. } = event.xconfigure;
+						self.size = (width.max(0) as u32, height.max(0) as u32);
+					},
+					DestroyNotify => self.closed = true,
+					_ => {},
+				}
+			}
+		}
+
+		!self.closed
+	}
+
+	fn client_size(&self) -> (u32, u32) {
+		self.size
+	}
+
+	fn surface_extension(&self) -> &'static CStr {
+		khr::XlibSurface::name()
+	}
+
+	unsafe fn create_surface(&self, entry: &Entry, instance: &ash::Instance) -> Result<vk::SurfaceKHR> {
+		let surface_create_info = vk::XlibSurfaceCreateInfoKHR::builder()
+			.dpy(self.display as *mut vk::Display)
+			.window(self.window);
+
+		let khr_xlib_surface = khr::XlibSurface::new(entry, instance);
+		let surface          = khr_xlib_surface.create_xlib_surface(&surface_create_info, None)?;
+
+		Ok(surface)
+	}
+}
+
+impl Drop for Window {
+	fn drop(&mut self) {
+		unsafe {
+			if !self.closed {
+				(self.xlib.XDestroyWindow)(self.display, self.window);
+			}
+			(self.xlib.XCloseDisplay)(self.display);
+		}
+	}
+}
+
+#[allow(non_camel_case_types)]
+#[allow(non_snake_case)]
+#[allow(non_upper_case_globals)]
+#[allow(dead_code)]
+mod ffi {
+	use std::ffi;
+
+	pub enum Display {}
+
+	pub type XWindow = ffi::c_ulong;
+	pub type Atom    = ffi::c_ulong;
+	pub type KeySym  = ffi::c_ulong;
+	pub type Time    = ffi::c_ulong;
+	pub type Bool    = ffi::c_int;
+	pub type Status  = ffi::c_int;
+
+	pub const False: Bool = 0;
+
+	pub const KeyPressMask: ffi::c_long        = 1 << 0;
+	pub const StructureNotifyMask: ffi::c_long = 1 << 17;
+
+	pub const KeyPress: ffi::c_int        = 2;
+	pub const DestroyNotify: ffi::c_int   = 17;
+	pub const ConfigureNotify: ffi::c_int = 22;
+	pub const ClientMessage: ffi::c_int   = 33;
+
+	pub const XK_Escape: KeySym = 0xFF1B;
+
+	#[repr(C)]
+	#[derive(Clone, Copy)]
+	pub struct XKeyEvent {
+		pub type_: ffi::c_int,
+		pub serial: ffi::c_ulong,
+		pub send_event: Bool,
+		pub display: *mut Display,
+		pub window: XWindow,
+		pub root: XWindow,
+		pub subwindow: XWindow,
+		pub time: Time,
+		pub x: ffi::c_int,
+		pub y: ffi::c_int,
+		pub x_root: ffi::c_int,
+		pub y_root: ffi::c_int,
+		pub state: ffi::c_uint,
+		pub keycode: ffi::c_uint,
+		pub same_screen: Bool,
+	}
+
+	#[repr(C)]
+	#[derive(Clone, Copy)]
+	pub struct XConfigureEvent {
+		pub type_: ffi::c_int,
+		pub serial: ffi::c_ulong,
+		pub send_event: Bool,
+		pub display: *mut Display,
+		pub event: XWindow,
+		pub window: XWindow,
+		pub x: ffi::c_int,
+		pub y: ffi::c_int,
+		pub width: ffi::c_int,
+		pub height: ffi::c_int,
+		pub border_width: ffi::c_int,
+		pub above: XWindow,
+		pub override_redirect: Bool,
+	}
+
+	#[repr(C)]
+	#[derive(Clone, Copy)]
+	pub struct XClientMessageEvent {
+		pub type_: ffi::c_int,
+		pub serial: ffi::c_ulong,
+		pub send_event: Bool,
+		pub display: *mut Display,
+		pub window: XWindow,
+		pub message_type: Atom,
+		pub format: ffi::c_int,
+		pub data: [ffi::c_long; 5],
+	}
+
+	#[repr(C)]
+	pub union XEvent {
+		pub type_: ffi::c_int,
+		pub xkey: XKeyEvent,
+		pub xconfigure: XConfigureEvent,
+		pub xclient: XClientMessageEvent,
+		pub pad: [ffi::c_long; 24],
+	}
+
+	library!(Xlib, "libX11.so.6", {
+		pub fn XOpenDisplay(*const ffi::c_char) -> *mut Display;
+		pub fn XCloseDisplay(*mut Display) -> ffi::c_int;
+		pub fn XDefaultScreen(*mut Display) -> ffi::c_int;
+		pub fn XRootWindow(*mut Display, ffi::c_int) -> XWindow;
+		pub fn XBlackPixel(*mut Display, ffi::c_int) -> ffi::c_ulong;
+		pub fn XCreateSimpleWindow(
+			*mut Display,
+			XWindow,
+			ffi::c_int,
+			ffi::c_int,
+			ffi::c_uint,
+			ffi::c_uint,
+			ffi::c_uint,
+			ffi::c_ulong,
+			ffi::c_ulong
+		) -> XWindow;
+		pub fn XDestroyWindow(*mut Display, XWindow) -> ffi::c_int;
+		pub fn XStoreName(*mut Display, XWindow, *const ffi::c_char) -> ffi::c_int;
+		pub fn XSelectInput(*mut Display, XWindow, ffi::c_long) -> ffi::c_int;
+		pub fn XInternAtom(*mut Display, *const ffi::c_char, Bool) -> Atom;
+		pub fn XSetWMProtocols(*mut Display, XWindow, *mut Atom, ffi::c_int) -> Status;
+		pub fn XMapWindow(*mut Display, XWindow) -> ffi::c_int;
+		pub fn XFlush(*mut Display) -> ffi::c_int;
+		pub fn XPending(*mut Display) -> ffi::c_int;
+		pub fn XNextEvent(*mut Display, *mut XEvent) -> ffi::c_int;
+		pub fn XLookupKeysym(*mut XKeyEvent, ffi::c_int) -> KeySym;
+	});
+}
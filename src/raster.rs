@@ -0,0 +1,1226 @@
+//! Software rasterizer interpreting the prima buffers.
+//!
+//! Decodes exactly the same stream as `tri.vert.glsl`/`tri.frag.glsl`
+//! do, so it serves both as a GPU-free reference for tests and as a
+//! fallback renderer. The stages mirror the shaders, keep them in
+//! sync.
+
+use crate::{Result, CLEAR_COLOR};
+use crate::encoder::PrimaEncoder;
+use crate::index::{
+	decode_corner,
+	decode_gradient,
+	decode_offset,
+	decode_type,
+	GRADIENT_LINEAR,
+	GRADIENT_RADIAL,
+	JOIN_BEVEL,
+	JOIN_MITER,
+	JOIN_ROUND,
+	LINE_BUTT,
+	LINE_ROUND,
+	LINE_SQUARE,
+	MITER_LIMIT,
+	PRIMA_ARC,
+	PRIMA_BORDER,
+	PRIMA_ELLIPSE,
+	PRIMA_GLYPH,
+	PRIMA_IMAGE,
+	PRIMA_JOIN,
+	PRIMA_LINE,
+	PRIMA_MSDF_GLYPH,
+	PRIMA_NINE_SLICE,
+	PRIMA_RECT,
+	PRIMA_ROUNDED_RECT,
+	PRIMA_SDF_GLYPH,
+	PRIMA_SHADOW,
+	PRIMA_TRI,
+};
+use crate::texture::{self, TextureId, TextureUpdate};
+
+/// Same as the per-frame region of the GPU renderers.
+const PRIMA_SIZE: usize = 64 * 1024;
+
+/// Amount of interpolated values passed from the vertex stage to
+/// the fragment one, the color & the texture coordinates.
+const VARYINGS: usize = 6;
+
+/// RGBA8 pixels, top row first.
+pub struct Framebuffer {
+	pub width:  u32,
+	pub height: u32,
+	pub pixels: Vec<u8>,
+}
+
+impl Framebuffer {
+	pub fn new(width: u32, height: u32) -> Self {
+		Self {
+			width,
+			height,
+			pixels: vec![0; width as usize * height as usize * 4],
+		}
+	}
+
+	pub fn clear(&mut self, color: [f32; 4]) {
+		let color = color.map(to_unorm8);
+		for pixel in self.pixels.chunks_exact_mut(4) {
+			pixel.copy_from_slice(&color);
+		}
+	}
+
+	/// Straight alpha "over", the same blend state the pipeline has.
+	fn blend(&mut self, x: u32, y: u32, src: [f32; 4]) {
+		let at  = (y as usize * self.width as usize + x as usize) * 4;
+		let dst = &mut self.pixels[at..at + 4];
+
+		let a = src[3];
+		let mut out = [0.0; 4];
+		for i in 0..3 {
+			out[i] = src[i] * a + dst[i] as f32 / 255.0 * (1.0 - a);
+		}
+		out[3] = a + dst[3] as f32 / 255.0 * (1.0 - a);
+
+		dst.copy_from_slice(&out.map(to_unorm8));
+	}
+}
+
+/// RGBA8 texels with straight alpha, top row first.
+pub struct Texture {
+	pub width:  u32,
+	pub height: u32,
+	pub pixels: Vec<u8>,
+}
+
+impl Texture {
+	/// Bilinear, clamped to the edges, as the pipeline's sampler.
+	fn sample(&self, uv: [f32; 2]) -> [f32; 4] {
+		let x = uv[0] * self.width  as f32 - 0.5;
+		let y = uv[1] * self.height as f32 - 0.5;
+		let (x0, y0) = (x.floor(), y.floor());
+		let (fx, fy) = (x - x0, y - y0);
+
+		let texel = |x: f32, y: f32| {
+			let x  = (x as i64).clamp(0, self.width  as i64 - 1) as usize;
+			let y  = (y as i64).clamp(0, self.height as i64 - 1) as usize;
+			let at = (y * self.width as usize + x) * 4;
+			let t  = &self.pixels[at..at + 4];
+			[t[0], t[1], t[2], t[3]].map(|v| v as f32 / 255.0)
+		};
+
+		let t00 = texel(x0, y0);
+		let t10 = texel(x0 + 1.0, y0);
+		let t01 = texel(x0, y0 + 1.0);
+		let t11 = texel(x0 + 1.0, y0 + 1.0);
+
+		let mut out = [0.0; 4];
+		for i in 0..4 {
+			let top    = t00[i] + (t10[i] - t00[i]) * fx;
+			let bottom = t01[i] + (t11[i] - t01[i]) * fx;
+			out[i] = top + (bottom - top) * fy;
+		}
+		out
+	}
+}
+
+/// CPU counterpart of the [`crate::headless::Headless`] renderer.
+pub struct Software {
+	prima:       Vec<u8>,
+	index:       Vec<u8>,
+	textures:    Vec<Texture>,
+	framebuffer: Framebuffer,
+}
+
+impl Software {
+	pub fn new(width: u32, height: u32) -> Self {
+		Self {
+			prima:       vec![0; PRIMA_SIZE],
+			index:       vec![0; PRIMA_SIZE],
+			textures:    Vec::new(),
+			framebuffer: Framebuffer::new(width, height),
+		}
+	}
+
+	/// Uploads tightly packed RGBA8 `pixels`, top row first.
+	pub fn upload_texture(&mut self, width: u32, height: u32, pixels: &[u8]) -> Result<TextureId> {
+		let id = texture::next_id(self.textures.len(), width, height, pixels)?;
+		self.textures.push(Texture { width, height, pixels: pixels.to_vec() });
+		Ok(id)
+	}
+
+	/// Overwrites parts of the `id` texture, all of `updates` or none.
+	pub fn update_texture(&mut self, id: TextureId, updates: &[TextureUpdate]) -> Result<()> {
+		let Some(texture) = self.textures.get_mut(id.0 as usize) else {
+			return Err(format!("There is no texture {}.", id.0).into());
+		};
+		for update in updates {
+			texture::check_update((texture.width, texture.height), update)?;
+		}
+
+		for TextureUpdate { rect, pixels } in updates {
+			let row = rect[2] as usize * 4;
+			for (y, line) in pixels.chunks_exact(row).enumerate() {
+				let at = ((rect[1] as usize + y) * texture.width as usize + rect[0] as usize) * 4;
+				texture.pixels[at..at + row].copy_from_slice(line);
+			}
+		}
+		Ok(())
+	}
+
+	/// Size of the frame in pixels.
+	pub fn size(&self) -> (u32, u32) {
+		(self.framebuffer.width, self.framebuffer.height)
+	}
+
+	/// Encodes a frame via `encode`, renders it and returns its
+	/// tightly packed RGBA8 pixels, top row first.
+	pub fn render<F>(&mut self, encode: F) -> Result<Vec<u8>>
+	where
+		F: FnOnce(&mut PrimaEncoder) -> Result<()>,
+	{
+		let w = self.framebuffer.width  as f32;
+		let h = self.framebuffer.height as f32;
+
+		let mut encoder = PrimaEncoder::new(&mut self.prima, &mut self.index, w, h)?;
+		encode(&mut encoder)?;
+		let indices = encoder.finish();
+
+		self.framebuffer.clear(CLEAR_COLOR);
+		draw(&self.prima, &self.index, indices, &self.textures, &mut self.framebuffer);
+
+		Ok(self.framebuffer.pixels.clone())
+	}
+}
+
+/// Draws `indices` indices from `index` on top of `target`, the
+/// same way a `cmd_draw_indexed` does. Images sample `textures` by
+/// their slot, the missing ones are white.
+pub fn draw(prima: &[u8], index: &[u8], indices: u32, textures: &[Texture], target: &mut Framebuffer) {
+	let prima = Prima(prima);
+	let proj  = prima.proj();
+
+	let indices = index[..indices as usize * 4]
+		.chunks_exact(4)
+		.map(|i| u32::from_ne_bytes([i[0], i[1], i[2], i[3]]));
+
+	let mut tri = [VertexOut::default(); 3];
+	for (n, id) in indices.enumerate() {
+		tri[n % 3] = vertex(&prima, &proj, id);
+		if n % 3 == 2 {
+			rasterize(&prima, textures, &tri, target);
+		}
+	}
+}
+
+/// Word-addressed view of a prima buffer.
+struct Prima<'a>(&'a [u8]);
+
+impl Prima<'_> {
+	fn word(&self, offset: u32) -> u32 {
+		let at = offset as usize * 4;
+		u32::from_ne_bytes([self.0[at], self.0[at + 1], self.0[at + 2], self.0[at + 3]])
+	}
+
+	fn float(&self, offset: u32) -> f32 {
+		f32::from_bits(self.word(offset))
+	}
+
+	fn vec4(&self, offset: u32) -> [f32; 4] {
+		[
+			self.float(offset),
+			self.float(offset + 1),
+			self.float(offset + 2),
+			self.float(offset + 3),
+		]
+	}
+
+	/// Column-major, as GLSL's `mat4`.
+	fn proj(&self) -> [[f32; 4]; 4] {
+		[self.vec4(0), self.vec4(4), self.vec4(8), self.vec4(12)]
+	}
+}
+
+#[derive(Clone, Copy, Default)]
+struct VertexOut {
+	/// Normalized device coordinates.
+	pos:  [f32; 2],
+	vary: [f32; VARYINGS],
+	/// Flat, the first vertex of a triangle provides it.
+	id:   u32,
+}
+
+/// `tri.vert.glsl`
+fn vertex(prima: &Prima, proj: &[[f32; 4]; 4], id: u32) -> VertexOut {
+	let ptype  = decode_type(id);
+	let corner = decode_corner(id);
+	let offset = decode_offset(id);
+
+	// Anti-aliased primitives are placed as is.
+	let mut snap = true;
+	let mut uv   = [0.0; 2];
+
+	let (mut v, c) = match ptype {
+		PRIMA_TRI => {
+			let vx = prima.float(offset);
+			let vy = prima.float(offset + 1);
+			let c  = decode_color(prima.word(offset + 2));
+			([vx, vy], c)
+		},
+		PRIMA_RECT => {
+			let r = prima.vec4(offset);
+			(rect_corner(r, corner), decode_color(prima.word(offset + 4)))
+		},
+		PRIMA_IMAGE | PRIMA_NINE_SLICE | PRIMA_GLYPH | PRIMA_SDF_GLYPH | PRIMA_MSDF_GLYPH => {
+			let r = prima.vec4(offset);
+			uv = rect_corner(prima.vec4(offset + 6), corner);
+			(rect_corner(r, corner), decode_color(prima.word(offset + 4)))
+		},
+		PRIMA_LINE => {
+			let ab   = prima.vec4(offset);
+			let hw   = prima.float(offset + 5) * 0.5;
+			let ends = prima.word(offset + 6);
+
+			let (a, b) = ([ab[0], ab[1]], [ab[2], ab[3]]);
+			let d = normalize(sub(b, a));
+			let n = [-d[1], d[0]];
+
+			// Caps stick out by half of the width, a pixel of room for
+			// the anti-aliased edges on top.
+			let extent = |end: u32| if end == LINE_SQUARE || end == LINE_ROUND { hw + 1.0 } else { 1.0 };
+			let e0 = extent(ends & 0xFF);
+			let e1 = extent((ends >> 8) & 0xFF);
+
+			let u = if corner == 2 || corner == 3 { length(sub(b, a)) + e1 } else { -e0 };
+			let s = if corner == 0 || corner == 3 { hw + 1.0 } else { -(hw + 1.0) };
+			snap = false;
+			([a[0] + d[0] * u + n[0] * s, a[1] + d[1] * u + n[1] * s], decode_color(prima.word(offset + 4)))
+		},
+		_ => {
+			// Anti-aliased shapes, the fragment stage cuts them out of
+			// their bounds.
+			let r = match ptype {
+				// @Speed The inside of a border is shaded for nothing.
+				PRIMA_ROUNDED_RECT | PRIMA_BORDER => prima.vec4(offset),
+				PRIMA_ELLIPSE => {
+					let e = prima.vec4(offset);
+					[e[0] - e[2], e[1] - e[3], e[2] * 2.0, e[3] * 2.0]
+				},
+				PRIMA_SHADOW => {
+					// Past 3 sigmas the blur is all but gone.
+					let blur = prima.float(offset + 6) * 3.0;
+					let r    = prima.vec4(offset);
+					[r[0] - blur, r[1] - blur, r[2] + blur * 2.0, r[3] + blur * 2.0]
+				},
+				PRIMA_JOIN => {
+					let j  = prima.vec4(offset);
+					let hw = prima.float(offset + 5) * 0.5;
+					let e  = if prima.word(offset + 6) == JOIN_MITER { hw * MITER_LIMIT } else { hw };
+					[j[0] - e, j[1] - e, e * 2.0, e * 2.0]
+				},
+				_ => {
+					debug_assert!(ptype == PRIMA_ARC);
+					let a = prima.vec4(offset);
+					let e = a[2] + a[3] * 0.5;
+					[a[0] - e, a[1] - e, e * 2.0, e * 2.0]
+				},
+			};
+
+			// A pixel of room for the anti-aliased edges.
+			let r = [r[0] - 1.0, r[1] - 1.0, r[2] + 2.0, r[3] + 2.0];
+			snap = false;
+			(rect_corner(r, corner), decode_color(prima.word(offset + 4)))
+		},
+	};
+
+	if snap {
+		v = [(v[0] + 0.5).floor(), (v[1] + 0.5).floor()];
+	}
+
+	// gl_Position = proj * vec4(v.xy, 0.0, 1.0), then the viewport
+	// transform. The projection is orthographic, so w stays 1.
+	let mut clip = [0.0; 4];
+	for (row, out) in clip.iter_mut().enumerate() {
+		*out = proj[0][row] * v[0] + proj[1][row] * v[1] + proj[3][row];
+	}
+
+	VertexOut {
+		pos:  [clip[0] / clip[3], clip[1] / clip[3]],
+		vary: [c[0], c[1], c[2], c[3], uv[0], uv[1]],
+		id,
+	}
+}
+
+fn rect_corner(r: [f32; 4], corner: u8) -> [f32; 2] {
+	[
+		r[0] + if corner == 2 || corner == 3 { r[2] } else { 0.0 },
+		r[1] + if corner == 0 || corner == 3 { r[3] } else { 0.0 },
+	]
+}
+
+/// `tri.frag.glsl`, `frag_coord` is the pixel center. `None` is a
+/// discarded fragment.
+fn fragment(
+	prima: &Prima,
+	textures: &[Texture],
+	id: u32,
+	frag_coord: [f32; 2],
+	vary: &[f32; VARYINGS],
+) -> Option<[f32; 4]> {
+	let ptype  = decode_type(id);
+	let offset = decode_offset(id);
+
+	let mut color = [vary[0], vary[1], vary[2], vary[3]];
+
+	// Fully covered, unless an anti-aliased shape says otherwise.
+	let d = match ptype {
+		PRIMA_ROUNDED_RECT => {
+			let r     = prima.vec4(offset);
+			let radii = prima.vec4(offset + 5);
+			let b     = [r[2] * 0.5, r[3] * 0.5];
+			sd_rounded_box([frag_coord[0] - (r[0] + b[0]), frag_coord[1] - (r[1] + b[1])], b, radii)
+		},
+		PRIMA_ELLIPSE => {
+			let e = prima.vec4(offset);
+			sd_ellipse([frag_coord[0] - e[0], frag_coord[1] - e[1]], [e[2], e[3]])
+		},
+		PRIMA_ARC => {
+			let a    = prima.vec4(offset);
+			let dirs = prima.vec4(offset + 5);
+			let p    = [frag_coord[0] - a[0], frag_coord[1] - a[1]];
+			sd_arc(p, a[2], a[3], [dirs[0], dirs[1]], [dirs[2], dirs[3]])
+		},
+		PRIMA_LINE => {
+			let ab = prima.vec4(offset);
+			let hw = prima.float(offset + 5) * 0.5;
+			sd_line(frag_coord, [ab[0], ab[1]], [ab[2], ab[3]], hw, prima.word(offset + 6))?
+		},
+		PRIMA_JOIN => {
+			let j    = prima.vec4(offset);
+			let hw   = prima.float(offset + 5) * 0.5;
+			let next = [prima.float(offset + 7), prima.float(offset + 8)];
+			sd_join(frag_coord, [j[0], j[1]], [j[2], j[3]], next, hw, prima.word(offset + 6))?
+		},
+		PRIMA_BORDER => {
+			let r      = prima.vec4(offset);
+			let widths = prima.vec4(offset + 5);
+			let radii  = prima.vec4(offset + 12);
+			color = border_color(prima, offset, frag_coord);
+			sd_border(frag_coord, r, widths, radii)
+		},
+		PRIMA_SHADOW => {
+			let r      = prima.vec4(offset);
+			let radius = prima.float(offset + 5);
+			let sigma  = prima.float(offset + 6);
+			// Not a distance, the coverage is computed directly.
+			0.5 - box_shadow(frag_coord, r, radius, sigma)
+		},
+		PRIMA_SDF_GLYPH | PRIMA_MSDF_GLYPH => {
+			let texture = textures.get(prima.word(offset + 5) as usize);
+			sd_glyph(prima, offset, ptype, texture, [vary[4], vary[5]])
+		},
+		_ => -0.5,
+	};
+
+	if decode_gradient(id) {
+		color = gradient(prima, prima.word(offset + 4), frag_coord);
+		let noise = (dither(frag_coord) - 0.5) / 255.0;
+		for c in &mut color[..3] {
+			*c += noise;
+		}
+	}
+
+	if ptype == PRIMA_IMAGE || ptype == PRIMA_NINE_SLICE {
+		let texture = textures.get(prima.word(offset + 5) as usize);
+		let mut uv  = [vary[4], vary[5]];
+		if ptype == PRIMA_NINE_SLICE {
+			let size = texture.map_or([1.0, 1.0], |t| [t.width as f32, t.height as f32]);
+			uv = nine_slice(prima, offset, size, frag_coord);
+		}
+
+		let texel = texture.map_or([1.0; 4], |t| t.sample(uv));
+		for (c, t) in color.iter_mut().zip(texel) {
+			*c *= t;
+		}
+	}
+
+	if ptype == PRIMA_GLYPH {
+		let texel = textures
+			.get(prima.word(offset + 5) as usize)
+			.map_or([1.0; 4], |t| t.sample([vary[4], vary[5]]));
+		color[3] *= texel[3];
+	}
+
+	let coverage = (0.5 - d).clamp(0.0, 1.0);
+	Some([color[0], color[1], color[2], color[3] * coverage])
+}
+
+/// Signed distance in pixels to the outline of the distance field
+/// glyph at `at`, positive outside like the shapes.
+fn sd_glyph(prima: &Prima, at: u32, ptype: u32, texture: Option<&Texture>, uv: [f32; 2]) -> f32 {
+	let Some(texture) = texture else {
+		// A white texel is all inside.
+		return -0.5;
+	};
+
+	let r      = prima.vec4(at);
+	let region = prima.vec4(at + 6);
+	let range  = prima.float(at + 10);
+
+	let texel = texture.sample(uv);
+	let v = if ptype == PRIMA_MSDF_GLYPH { median(texel[0], texel[1], texel[2]) } else { texel[3] };
+
+	let pixels_per_texel = r[2] / (region[2] * texture.width as f32);
+	(0.5 - v) * 2.0 * range * pixels_per_texel
+}
+
+fn median(a: f32, b: f32, c: f32) -> f32 {
+	a.min(b).max(a.max(b).min(c))
+}
+
+/// Texture coordinates of the pixel `p` of the nine-slice at `at`,
+/// its texture is `size` texels.
+fn nine_slice(prima: &Prima, at: u32, size: [f32; 2], p: [f32; 2]) -> [f32; 2] {
+	let r      = prima.vec4(at);
+	let uv     = prima.vec4(at + 6);
+	let insets = prima.vec4(at + 10);
+
+	// The rect the vertex stage has snapped.
+	let min = [(r[0] + 0.5).floor(), (r[1] + 0.5).floor()];
+	let max = [(r[0] + r[2] + 0.5).floor(), (r[1] + r[3] + 0.5).floor()];
+
+	let u = nine_slice_axis(p[0] - min[0], max[0] - min[0], uv[2] * size[0], insets[3], insets[1]);
+	let v = nine_slice_axis(p[1] - min[1], max[1] - min[1], uv[3] * size[1], insets[0], insets[2]);
+	[uv[0] + u / size[0], uv[1] + v / size[1]]
+}
+
+/// Maps `p` along `length` pixels to a texel along `region` texels,
+/// `lo` & `hi` are the insets at the ends.
+fn nine_slice_axis(p: f32, length: f32, region: f32, lo: f32, hi: f32) -> f32 {
+	// Insets not fitting shrink evenly.
+	let f = (length / (lo + hi).max(1e-6)).min(1.0);
+	if p < lo * f {
+		p / f
+	} else if p > length - hi * f {
+		region - (length - p) / f
+	} else {
+		lo + (p - lo * f) * (region - lo - hi) / (length - (lo + hi) * f).max(1e-6)
+	}
+}
+
+/// Color of the gradient at `at` in the pixel `p`.
+fn gradient(prima: &Prima, at: u32, p: [f32; 2]) -> [f32; 4] {
+	let kind = prima.word(at);
+	let g    = prima.vec4(at + 1);
+
+	let t = match kind {
+		GRADIENT_LINEAR => {
+			let ab = [g[2] - g[0], g[3] - g[1]];
+			dot(sub(p, [g[0], g[1]]), ab) / dot(ab, ab).max(1e-6)
+		},
+		GRADIENT_RADIAL => length(sub(p, [g[0], g[1]])) / g[2].max(1e-6),
+		_ => {
+			let q = sub(p, [g[0], g[1]]);
+			fract((q[1].atan2(q[0]) - g[2]) / std::f32::consts::TAU)
+		},
+	};
+	let t = t.clamp(0.0, 1.0);
+
+	// Stops are sorted, every one takes over from the previous one
+	// once `t` gets past it.
+	let count    = prima.word(at + 5);
+	let mut prev = prima.float(at + 6);
+	let mut c    = premultiply(decode_color(prima.word(at + 7)));
+	for i in 1..count {
+		let o  = prima.float(at + 6 + i * 2);
+		let ci = premultiply(decode_color(prima.word(at + 7 + i * 2)));
+		let f  = ((t - prev) / (o - prev).max(1e-6)).clamp(0.0, 1.0);
+		for (c, ci) in c.iter_mut().zip(ci) {
+			*c = *c * (1.0 - f) + ci * f;
+		}
+		prev = o;
+	}
+
+	if c[3] > 0.0 { [c[0] / c[3], c[1] / c[3], c[2] / c[3], c[3]] } else { [0.0; 4] }
+}
+
+fn premultiply(c: [f32; 4]) -> [f32; 4] {
+	[c[0] * c[3], c[1] * c[3], c[2] * c[3], c[3]]
+}
+
+/// Interleaved gradient noise in [0, 1), breaks up gradient banding.
+fn dither(p: [f32; 2]) -> f32 {
+	fract(52.982_918 * fract(dot(p, [0.067_110_56, 0.005_837_15])))
+}
+
+fn fract(v: f32) -> f32 {
+	v - v.floor()
+}
+
+/// Signed distance from `p` to a box with `b` half-extents centered
+/// at the origin, corner radii go as top-left, top-right,
+/// bottom-right & bottom-left.
+fn sd_rounded_box(p: [f32; 2], b: [f32; 2], radii: [f32; 4]) -> f32 {
+	let r = if p[0] > 0.0 {
+		if p[1] > 0.0 { radii[2] } else { radii[1] }
+	} else {
+		if p[1] > 0.0 { radii[3] } else { radii[0] }
+	};
+	let r = r.min(b[0].min(b[1]));
+
+	let q = [p[0].abs() - b[0] + r, p[1].abs() - b[1] + r];
+	q[0].max(q[1]).min(0.0) + length([q[0].max(0.0), q[1].max(0.0)]) - r
+}
+
+/// Approximate, but exact enough around the edge to anti-alias it.
+fn sd_ellipse(p: [f32; 2], r: [f32; 2]) -> f32 {
+	let k0 = length([p[0] / r[0], p[1] / r[1]]);
+	let k1 = length([p[0] / (r[0] * r[0]), p[1] / (r[1] * r[1])]);
+	if k1 > 0.0 { k0 * (k0 - 1.0) / k1 } else { -r[0].min(r[1]) }
+}
+
+/// Signed distance from `p` to an arc around the origin with butt
+/// ends. `mid` points to the middle of the arc, `end` to its ends,
+/// relative to `mid`, as the arc is symmetric. `end` of (-1, 0) is a
+/// full ring.
+fn sd_arc(p: [f32; 2], radius: f32, thickness: f32, mid: [f32; 2], end: [f32; 2]) -> f32 {
+	let q = [dot(p, mid), (mid[0] * p[1] - mid[1] * p[0]).abs()];
+
+	let ring = (length(q) - radius).abs() - thickness * 0.5;
+	if end[0] <= -1.0 {
+		return ring;
+	}
+
+	// Positive past the end.
+	let side = end[0] * q[1] - end[1] * q[0];
+	if side > 0.0 {
+		let a  = [end[0] * (radius - thickness * 0.5), end[1] * (radius - thickness * 0.5)];
+		let ba = [end[0] * thickness, end[1] * thickness];
+		let pa = [q[0] - a[0], q[1] - a[1]];
+		let h  = (dot(pa, ba) / dot(ba, ba).max(1e-6)).clamp(0.0, 1.0);
+		return length([pa[0] - ba[0] * h, pa[1] - ba[1] * h]);
+	}
+
+	if dot(q, end) > 0.0 { ring.max(side) } else { ring }
+}
+
+/// Signed distance from `p` to the ring between the edge of the `r`
+/// rect & its inside, `widths` go as top, right, bottom & left.
+fn sd_border(p: [f32; 2], r: [f32; 4], widths: [f32; 4], radii: [f32; 4]) -> f32 {
+	let b     = [r[2] * 0.5, r[3] * 0.5];
+	let outer = sd_rounded_box(sub(p, [r[0] + b[0], r[1] + b[1]]), b, radii);
+
+	let inner_min = [r[0] + widths[3], r[1] + widths[0]];
+	let inner_max = [r[0] + r[2] - widths[1], r[1] + r[3] - widths[2]];
+	if inner_max[0] <= inner_min[0] || inner_max[1] <= inner_min[1] {
+		return outer;
+	}
+
+	// Corners get thinner by the wider of the sides they join.
+	let inner_radii = [
+		(radii[0] - widths[3].max(widths[0])).max(0.0),
+		(radii[1] - widths[0].max(widths[1])).max(0.0),
+		(radii[2] - widths[1].max(widths[2])).max(0.0),
+		(radii[3] - widths[2].max(widths[3])).max(0.0),
+	];
+	let ib    = [(inner_max[0] - inner_min[0]) * 0.5, (inner_max[1] - inner_min[1]) * 0.5];
+	let inner = sd_rounded_box(sub(p, [inner_min[0] + ib[0], inner_min[1] + ib[1]]), ib, inner_radii);
+	outer.max(-inner)
+}
+
+/// Color of the side of the border at `at` that `p` is on, the corners
+/// are split along the line from the outer to the inner corner.
+fn border_color(prima: &Prima, at: u32, p: [f32; 2]) -> [f32; 4] {
+	let r      = prima.vec4(at);
+	let widths = prima.vec4(at + 5);
+
+	let left = p[0] < r[0] + r[2] * 0.5;
+	let top  = p[1] < r[1] + r[3] * 0.5;
+
+	// From the nearest outer corner, inwards.
+	let q = [
+		if left { p[0] - r[0] } else { r[0] + r[2] - p[0] },
+		if top  { p[1] - r[1] } else { r[1] + r[3] - p[1] },
+	];
+	let w = [if left { widths[3] } else { widths[1] }, if top { widths[0] } else { widths[2] }];
+
+	let horizontal = decode_color(prima.word(if top { at + 4 } else { at + 10 }));
+	let vertical   = decode_color(prima.word(if left { at + 11 } else { at + 9 }));
+
+	// Positive on the vertical side of the split.
+	let s = (w[0] * q[1] - w[1] * q[0]) / length(w).max(1e-6);
+	let f = (0.5 + s).clamp(0.0, 1.0);
+
+	let (h, v) = (premultiply(horizontal), premultiply(vertical));
+	let c: [f32; 4] = std::array::from_fn(|i| h[i] * (1.0 - f) + v[i] * f);
+	if c[3] > 0.0 { [c[0] / c[3], c[1] / c[3], c[2] / c[3], c[3]] } else { [0.0; 4] }
+}
+
+fn gaussian(x: f32, sigma: f32) -> f32 {
+	(-(x * x) / (2.0 * sigma * sigma)).exp() / (std::f32::consts::TAU.sqrt() * sigma)
+}
+
+/// Abramowitz & Stegun approximation of the error function.
+fn erf(x: f32) -> f32 {
+	let s = x.signum();
+	let a = x.abs();
+	let x = 1.0 + (0.278_393 + (0.230_389 + 0.078_108 * (a * a)) * a) * a;
+	let x = x * x;
+	s - s / (x * x)
+}
+
+/// Blurred row `y` of a rounded box with `b` half-extents, the blur
+/// along x is exact.
+fn shadow_row(x: f32, y: f32, sigma: f32, radius: f32, b: [f32; 2]) -> f32 {
+	let delta  = (b[1] - radius - y.abs()).min(0.0);
+	let curved = b[0] - radius + (radius * radius - delta * delta).max(0.0).sqrt();
+	let scale  = 0.5f32.sqrt() / sigma;
+	let low    = 0.5 + 0.5 * erf((x - curved) * scale);
+	let high   = 0.5 + 0.5 * erf((x + curved) * scale);
+	high - low
+}
+
+/// Coverage of the `r` rect with corners of `radius` blurred by a
+/// Gaussian of `sigma` at `p`. Exact along x, along y the rows are
+/// sampled.
+fn box_shadow(p: [f32; 2], r: [f32; 4], radius: f32, sigma: f32) -> f32 {
+	let b      = [r[2] * 0.5, r[3] * 0.5];
+	let p      = sub(p, [r[0] + b[0], r[1] + b[1]]);
+	let radius = radius.min(b[0].min(b[1]));
+
+	// Too sharp to sample, just a rounded box.
+	if sigma < 0.1 {
+		return (0.5 - sd_rounded_box(p, b, [radius; 4])).clamp(0.0, 1.0);
+	}
+
+	let low   = p[1] - b[1];
+	let high  = p[1] + b[1];
+	let start = (-3.0 * sigma).clamp(low, high);
+	let end   = (3.0 * sigma).clamp(low, high);
+
+	let step      = (end - start) / 4.0;
+	let mut y     = start + step * 0.5;
+	let mut value = 0.0;
+	for _ in 0..4 {
+		value += shadow_row(p[0], p[1] - y, sigma, radius, b) * gaussian(y, sigma) * step;
+		y     += step;
+	}
+	value
+}
+
+/// Adds an end of `kind` to the line distance `dist`, `t` is how far
+/// past the end `p` is, `pe` is `p` relative to the end. `None` past
+/// a joint, those pixels belong to the join.
+fn line_end(dist: f32, t: f32, pe: [f32; 2], hw: f32, kind: u32) -> Option<f32> {
+	match kind {
+		LINE_BUTT   => Some(dist.max(t)),
+		LINE_SQUARE => Some(dist.max(t - hw)),
+		LINE_ROUND  => Some(if t > 0.0 { length(pe) - hw } else { dist }),
+		_ => (t <= 0.0).then_some(dist),
+	}
+}
+
+/// Signed distance from `p` to a line of `hw` half-width from `a` to
+/// `b`, `ends` as in PRIMA_LINE.
+fn sd_line(p: [f32; 2], a: [f32; 2], b: [f32; 2], hw: f32, ends: u32) -> Option<f32> {
+	let d = normalize(sub(b, a));
+	let n = [-d[1], d[0]];
+
+	let dist = dot(sub(p, a), n).abs() - hw;
+	// Written the same way as in `sd_join`, so that joints are split
+	// exactly.
+	let dist = line_end(dist, -dot(sub(p, a), d), sub(p, a), hw, ends & 0xFF)?;
+	line_end(dist, dot(sub(p, b), d), sub(p, b), hw, (ends >> 8) & 0xFF)
+}
+
+/// Signed distance from `p` to the join at `j` of the lines coming
+/// from `prev` & going to `next`. Only pixels past the ends of both
+/// lines are kept.
+fn sd_join(p: [f32; 2], j: [f32; 2], prev: [f32; 2], next: [f32; 2], hw: f32, kind: u32) -> Option<f32> {
+	let d0 = normalize(sub(j, prev));
+	let d1 = normalize(sub(next, j));
+	let q  = sub(p, j);
+
+	if !(dot(q, d0) > 0.0 && dot(q, d1) < 0.0) {
+		return None;
+	}
+
+	if kind == JOIN_ROUND {
+		return Some(length(q) - hw);
+	}
+
+	let n0 = [-d0[1], d0[0]];
+	let n1 = [-d1[1], d1[0]];
+	let mut dist = dot(q, n0).abs().max(dot(q, n1).abs()) - hw;
+
+	// |d0 + d1| / 2 is the sine of half of the angle between the lines,
+	// the miter is as long as the width over it.
+	let m = [d0[0] + d1[0], d0[1] + d1[1]];
+	let too_long = dot(m, m) * 0.25 * MITER_LIMIT * MITER_LIMIT < 1.0;
+
+	let nb = sub(d0, d1);
+	if (kind == JOIN_BEVEL || too_long) && dot(nb, nb) > 1e-12 {
+		let nb = normalize(nb);
+		dist = dist.max(dot(q, nb) - hw * dot(n0, nb).abs());
+	}
+	Some(dist)
+}
+
+fn sub(a: [f32; 2], b: [f32; 2]) -> [f32; 2] {
+	[a[0] - b[0], a[1] - b[1]]
+}
+
+fn normalize(v: [f32; 2]) -> [f32; 2] {
+	let l = length(v);
+	[v[0] / l, v[1] / l]
+}
+
+fn dot(a: [f32; 2], b: [f32; 2]) -> f32 {
+	a[0] * b[0] + a[1] * b[1]
+}
+
+fn length(v: [f32; 2]) -> f32 {
+	dot(v, v).sqrt()
+}
+
+/// Fills the pixels whose centers are covered by the triangle,
+/// following the top-left rule like the GPU does.
+fn rasterize(prima: &Prima, textures: &[Texture], tri: &[VertexOut; 3], target: &mut Framebuffer) {
+	let w = target.width  as f32;
+	let h = target.height as f32;
+
+	// NDC to framebuffer.
+	let p = tri.map(|v| [(v.pos[0] * 0.5 + 0.5) * w, (v.pos[1] * 0.5 + 0.5) * h]);
+
+	let (p0, mut p1, mut p2) = (p[0], p[1], p[2]);
+	let (v0, mut v1, mut v2) = (&tri[0], &tri[1], &tri[2]);
+
+	let mut area = edge(p0, p1, p2);
+	if area == 0.0 {
+		return;
+	}
+	// No culling, flip the back-facing ones to keep the math simple.
+	if area < 0.0 {
+		std::mem::swap(&mut p1, &mut p2);
+		std::mem::swap(&mut v1, &mut v2);
+		area = -area;
+	}
+
+	let min_x = p0[0].min(p1[0]).min(p2[0]).floor().max(0.0) as u32;
+	let min_y = p0[1].min(p1[1]).min(p2[1]).floor().max(0.0) as u32;
+	let max_x = p0[0].max(p1[0]).max(p2[0]).ceil().min(w) as u32;
+	let max_y = p0[1].max(p1[1]).max(p2[1]).ceil().min(h) as u32;
+
+	for y in min_y..max_y {
+		for x in min_x..max_x {
+			let c = [x as f32 + 0.5, y as f32 + 0.5];
+
+			let w0 = edge(p1, p2, c);
+			let w1 = edge(p2, p0, c);
+			let w2 = edge(p0, p1, c);
+
+			let inside = covers(w0, p1, p2) && covers(w1, p2, p0) && covers(w2, p0, p1);
+			if !inside {
+				continue;
+			}
+
+			let (b0, b1, b2) = (w0 / area, w1 / area, w2 / area);
+
+			let mut vary = [0.0; VARYINGS];
+			for (i, v) in vary.iter_mut().enumerate() {
+				*v = v0.vary[i] * b0 + v1.vary[i] * b1 + v2.vary[i] * b2;
+			}
+
+			if let Some(color) = fragment(prima, textures, v0.id, c, &vary) {
+				target.blend(x, y, color);
+			}
+		}
+	}
+}
+
+/// Positive when `p` is to the right of `a` -> `b`, in the y-down
+/// framebuffer space.
+fn edge(a: [f32; 2], b: [f32; 2], p: [f32; 2]) -> f32 {
+	(b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
+
+/// Samples exactly on an edge belong to it only if it is a top or a
+/// left one.
+fn covers(w: f32, a: [f32; 2], b: [f32; 2]) -> bool {
+	if w != 0.0 {
+		return w > 0.0;
+	}
+
+	let dx = b[0] - a[0];
+	let dy = b[1] - a[1];
+	let is_top  = dy == 0.0 && dx > 0.0;
+	let is_left = dy < 0.0;
+	is_top || is_left
+}
+
+fn decode_color(c: u32) -> [f32; 4] {
+	c.to_le_bytes().map(|v| v as f32 / 255.0)
+}
+
+fn to_unorm8(v: f32) -> u8 {
+	(v.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::encoder::{Border, BorderSide, Color, ColorStop, CornerRadii, Gradient, Image, Insets, Stroke, Vertex};
+
+	fn pixel(pixels: &[u8], width: u32, x: u32, y: u32) -> [u8; 4] {
+		let at = ((y * width + x) * 4) as usize;
+		[pixels[at], pixels[at + 1], pixels[at + 2], pixels[at + 3]]
+	}
+
+	#[test]
+	fn clears_to_the_clear_color() {
+		let mut software = Software::new(4, 4);
+		let pixels = software.render(|_| Ok(())).unwrap();
+		assert_eq!(pixel(&pixels, 4, 3, 3), [102, 153, 115, 255]);
+	}
+
+	const ORANGE: Color = Color::rgb(255, 179, 102);
+
+	#[test]
+	fn rect_covers_exactly_its_pixels() {
+		let mut software = Software::new(8, 8);
+		let pixels = software.render(|encoder| {
+			encoder.rect(2.0, 1.0, 3.0, 4.0, ORANGE)?;
+			Ok(())
+		}).unwrap();
+
+		let orange = ORANGE.0.to_le_bytes();
+		for y in 0..8 {
+			for x in 0..8 {
+				let inside = (2..5).contains(&x) && (1..5).contains(&y);
+				assert_eq!(pixel(&pixels, 8, x, y) == orange, inside, "({x}, {y})");
+			}
+		}
+	}
+
+	#[test]
+	fn rect_snaps_to_whole_pixels() {
+		let mut software = Software::new(8, 8);
+		let snapped = software.render(|encoder| Ok(encoder.rect(2.0, 1.0, 3.0, 4.0, ORANGE)?)).unwrap();
+		let nudged  = software.render(|encoder| Ok(encoder.rect(2.3, 0.6, 2.9, 4.2, ORANGE)?)).unwrap();
+		assert!(snapped == nudged);
+	}
+
+	#[test]
+	fn rounded_rect_cuts_its_corners() {
+		let mut software = Software::new(16, 16);
+		let pixels = software.render(|encoder| {
+			encoder.rounded_rect(0.0, 0.0, 16.0, 16.0, CornerRadii::new(8.0, 0.0, 0.0, 0.0), Color::WHITE)?;
+			Ok(())
+		}).unwrap();
+
+		assert_eq!(pixel(&pixels, 16, 0, 0), [102, 153, 115, 255]);
+		assert_eq!(pixel(&pixels, 16, 15, 0), [255, 255, 255, 255]);
+		assert_eq!(pixel(&pixels, 16, 8, 8), [255, 255, 255, 255]);
+
+		// Somewhere in between on the arc.
+		let edge = pixel(&pixels, 16, 2, 2);
+		assert!(edge[0] > 102 && edge[0] < 255, "{edge:?}");
+	}
+
+	#[test]
+	fn rounded_rect_anti_aliases_fractional_edges() {
+		let mut software = Software::new(8, 8);
+		let pixels = software.render(|encoder| {
+			encoder.rounded_rect(2.0, 2.0, 3.5, 4.0, CornerRadii::all(0.0), Color::BLACK)?;
+			Ok(())
+		}).unwrap();
+
+		assert_eq!(pixel(&pixels, 8, 4, 3), [0, 0, 0, 255]);
+		// Half of the pixel is covered.
+		assert_eq!(pixel(&pixels, 8, 5, 3), [51, 77, 58, 255]);
+		assert_eq!(pixel(&pixels, 8, 6, 3), [102, 153, 115, 255]);
+	}
+
+	#[test]
+	fn circle_covers_its_disk() {
+		let mut software = Software::new(16, 16);
+		let pixels = software.render(|encoder| Ok(encoder.circle(8.0, 8.0, 6.0, Color::WHITE)?)).unwrap();
+
+		assert_eq!(pixel(&pixels, 16, 8, 8), [255, 255, 255, 255]);
+		assert_eq!(pixel(&pixels, 16, 12, 8), [255, 255, 255, 255]);
+		assert_eq!(pixel(&pixels, 16, 1, 1), [102, 153, 115, 255]);
+		assert_eq!(pixel(&pixels, 16, 15, 8), [102, 153, 115, 255]);
+	}
+
+	#[test]
+	fn arc_stops_at_its_ends() {
+		use std::f32::consts::PI;
+
+		// Bottom right quarter, clockwise from the +x axis.
+		let mut software = Software::new(32, 32);
+		let pixels = software.render(|encoder| {
+			Ok(encoder.arc(16.0, 16.0, 10.0, 4.0, 0.0, PI * 0.5, Color::WHITE)?)
+		}).unwrap();
+
+		let white = [255, 255, 255, 255];
+		let clear = [102, 153, 115, 255];
+		// Middle of the arc & the center.
+		assert_eq!(pixel(&pixels, 32, 23, 23), white);
+		assert_eq!(pixel(&pixels, 32, 16, 16), clear);
+		// Other quarters.
+		assert_eq!(pixel(&pixels, 32, 8, 8), clear);
+		assert_eq!(pixel(&pixels, 32, 23, 8), clear);
+		assert_eq!(pixel(&pixels, 32, 8, 23), clear);
+		// Right inside of the butt ends.
+		assert_eq!(pixel(&pixels, 32, 26, 17), white);
+		assert_eq!(pixel(&pixels, 32, 17, 26), white);
+		assert_eq!(pixel(&pixels, 32, 26, 14), clear);
+	}
+
+	#[test]
+	fn full_arc_is_a_ring() {
+		use std::f32::consts::TAU;
+
+		let mut software = Software::new(32, 32);
+		let ring = software.render(|encoder| Ok(encoder.arc(16.0, 16.0, 10.0, 4.0, 1.0, TAU, Color::WHITE)?)).unwrap();
+		let more = software.render(|encoder| Ok(encoder.arc(16.0, 16.0, 10.0, 4.0, 0.0, -TAU * 2.0, Color::WHITE)?)).unwrap();
+		assert!(ring == more);
+		assert_eq!(pixel(&ring, 32, 5, 16), [255, 255, 255, 255]);
+		assert_eq!(pixel(&ring, 32, 26, 15), [255, 255, 255, 255]);
+	}
+
+	#[test]
+	fn polyline_joints_are_blended_once() {
+		let stroke = Stroke::new(4.0, Color::rgba(255, 255, 255, 128));
+
+		let mut software = Software::new(16, 16);
+		let pixels = software.render(|encoder| {
+			Ok(encoder.polyline(&[[2.0, 8.0], [12.0, 8.0], [12.0, 15.0]], &stroke)?)
+		}).unwrap();
+
+		let once = pixel(&pixels, 16, 6, 8);
+		// Right at the joint & in the miter.
+		assert_eq!(pixel(&pixels, 16, 12, 7), once);
+		assert_eq!(pixel(&pixels, 16, 13, 6), once);
+		// Outside of the miter.
+		assert_eq!(pixel(&pixels, 16, 14, 5), [102, 153, 115, 255]);
+	}
+
+	#[test]
+	fn border_sides_take_their_colors() {
+		let red   = Color::rgb(255, 0, 0);
+		let green = Color::rgb(0, 255, 0);
+		let blue  = Color::rgb(0, 0, 255);
+
+		let border = Border::new(
+			BorderSide::new(4.0, red),
+			BorderSide::new(2.0, green),
+			BorderSide::new(1.0, blue),
+			BorderSide::new(0.0, Color::BLACK),
+		);
+
+		let mut software = Software::new(16, 16);
+		let pixels = software.render(|encoder| {
+			Ok(encoder.border(0.0, 0.0, 16.0, 16.0, &border, CornerRadii::all(0.0))?)
+		}).unwrap();
+
+		assert_eq!(pixel(&pixels, 16, 8, 0), [255, 0, 0, 255]);
+		assert_eq!(pixel(&pixels, 16, 8, 3), [255, 0, 0, 255]);
+		assert_eq!(pixel(&pixels, 16, 15, 8), [0, 255, 0, 255]);
+		assert_eq!(pixel(&pixels, 16, 8, 15), [0, 0, 255, 255]);
+		// No left side, the top one goes all the way to the edge.
+		assert_eq!(pixel(&pixels, 16, 0, 1), [255, 0, 0, 255]);
+		assert_eq!(pixel(&pixels, 16, 0, 8), [102, 153, 115, 255]);
+		assert_eq!(pixel(&pixels, 16, 8, 8), [102, 153, 115, 255]);
+	}
+
+	#[test]
+	fn shadow_falls_off_across_the_edge() {
+		let r = [16.0, 0.0, 32.0, 64.0];
+		let at = |x: f32| box_shadow([x, 32.0], r, 4.0, 2.0);
+
+		assert!((at(16.0) - 0.5).abs() < 0.01, "{}", at(16.0));
+		assert!(at(32.0) > 0.99);
+		assert!(at(10.0) < 0.01);
+		// Symmetric around the edge.
+		assert!((at(14.0) + at(18.0) - 1.0).abs() < 0.01);
+	}
+
+	#[test]
+	fn linear_gradient_pads_its_ends() {
+		let stops = [
+			ColorStop::new(0.0, Color::BLACK),
+			ColorStop::new(0.5, Color::rgb(255, 0, 0)),
+			ColorStop::new(1.0, Color::WHITE),
+		];
+
+		let mut software = Software::new(32, 4);
+		let pixels = software.render(|encoder| {
+			let paint = encoder.gradient(&Gradient::Linear { start: [8.0, 0.0], end: [24.0, 0.0], stops: &stops })?;
+			Ok(encoder.rect(0.0, 0.0, 32.0, 4.0, paint)?)
+		}).unwrap();
+
+		// Off by the dithering at most.
+		let near = |a: [u8; 4], b: [u8; 4]| a.iter().zip(b).all(|(a, b)| a.abs_diff(b) <= 1);
+		assert!(near(pixel(&pixels, 32, 2, 1), [0, 0, 0, 255]));
+		assert!(near(pixel(&pixels, 32, 29, 1), [255, 255, 255, 255]));
+		// 16.5 is right past the middle stop.
+		let middle = pixel(&pixels, 32, 16, 1);
+		assert!(near(middle, [255, 16, 16, 255]), "{middle:?}");
+	}
+
+	#[test]
+	fn gradient_hard_stops() {
+		let stops = [
+			ColorStop::new(0.0, Color::BLACK),
+			ColorStop::new(0.5, Color::BLACK),
+			ColorStop::new(0.5, Color::WHITE),
+		];
+
+		let mut software = Software::new(16, 1);
+		let pixels = software.render(|encoder| {
+			let paint = encoder.gradient(&Gradient::Radial { center: [0.0, 0.5], radius: 16.0, stops: &stops })?;
+			Ok(encoder.rect(0.0, 0.0, 16.0, 1.0, paint)?)
+		}).unwrap();
+
+		assert!(pixel(&pixels, 16, 7, 0)[0] <= 1);
+		assert!(pixel(&pixels, 16, 8, 0)[0] >= 254);
+	}
+
+	#[test]
+	fn degenerate_gradients_are_their_last_stop() {
+		let stops = [
+			ColorStop::new(0.0, Color::BLACK),
+			ColorStop::new(1.0, Color::WHITE),
+		];
+
+		let mut software = Software::new(8, 2);
+		let pixels = software.render(|encoder| {
+			let zero_length = encoder.gradient(&Gradient::Linear { start: [4.0, 0.5], end: [4.0, 0.5], stops: &stops })?;
+			let zero_radius = encoder.gradient(&Gradient::Radial { center: [4.0, 1.5], radius: 0.0, stops: &stops })?;
+			encoder.rect(0.0, 0.0, 8.0, 1.0, zero_length)?;
+			Ok(encoder.rect(0.0, 1.0, 8.0, 1.0, zero_radius)?)
+		}).unwrap();
+
+		for y in 0..2 {
+			for x in 0..8 {
+				assert_eq!(pixel(&pixels, 8, x, y), [255, 255, 255, 255], "{x}, {y}");
+			}
+		}
+	}
+
+	#[test]
+	fn triangle_interpolates_vertex_colors() {
+		let red   = Color::rgb(255, 0, 0);
+		let green = Color::rgb(0, 255, 0);
+
+		let mut software = Software::new(16, 16);
+		let pixels = software.render(|encoder| {
+			encoder.triangle(
+				Vertex::new(0.0,  0.0, red),
+				Vertex::new(16.0, 0.0, green),
+				Vertex::new(0.0, 16.0, red),
+			)?;
+			Ok(())
+		}).unwrap();
+
+		let left  = pixel(&pixels, 16, 0, 0);
+		let right = pixel(&pixels, 16, 14, 0);
+		assert!(left[0] > 240 && left[1] < 15);
+		assert!(right[0] < 30 && right[1] > 225);
+		// Bottom right half is outside.
+		assert_eq!(pixel(&pixels, 16, 15, 15), [102, 153, 115, 255]);
+	}
+
+	#[test]
+	fn shared_edges_are_drawn_once() {
+		// Both clockwise in the y-down space.
+		let a = [[0.0, 0.0], [8.0, 0.0], [0.0, 8.0]];
+		let b = [[8.0, 0.0], [8.0, 8.0], [0.0, 8.0]];
+
+		let inside = |t: [[f32; 2]; 3], c: [f32; 2]| {
+			covers(edge(t[1], t[2], c), t[1], t[2])
+				&& covers(edge(t[2], t[0], c), t[2], t[0])
+				&& covers(edge(t[0], t[1], c), t[0], t[1])
+		};
+
+		for y in 0..8 {
+			for x in 0..8 {
+				let c = [x as f32 + 0.5, y as f32 + 0.5];
+				assert!(inside(a, c) != inside(b, c), "({x}, {y})");
+			}
+		}
+	}
+
+	#[test]
+	fn image_samples_its_texture() {
+		let texels = [
+			255, 0, 0, 255,   0, 255, 0, 255,
+			0, 0, 255, 255,   255, 255, 255, 255,
+		];
+
+		let mut software = Software::new(16, 8);
+		let texture = software.upload_texture(2, 2, &texels).unwrap();
+		let pixels = software.render(|encoder| {
+			encoder.image(0.0, 0.0, 8.0, 8.0, &Image::new(texture), Color::WHITE)?;
+			// The center of the bottom-left texel all over, tinted.
+			let image = Image::new(texture).with_uv(0.25, 0.75, 0.0, 0.0);
+			Ok(encoder.image(8.0, 0.0, 8.0, 8.0, &image, Color::rgb(255, 255, 0))?)
+		}).unwrap();
+
+		// Corners are clamped to the edge texels.
+		assert_eq!(pixel(&pixels, 16, 0, 0), [255, 0, 0, 255]);
+		assert_eq!(pixel(&pixels, 16, 7, 0), [0, 255, 0, 255]);
+		assert_eq!(pixel(&pixels, 16, 7, 7), [255, 255, 255, 255]);
+		// 5/8 of the way to the bottom-right texel on both axes.
+		assert_eq!(pixel(&pixels, 16, 4, 4), [135, 159, 159, 255]);
+		assert_eq!(pixel(&pixels, 16, 12, 4), [0, 0, 0, 255]);
+	}
+
+	#[test]
+	fn missing_texture_is_white() {
+		let mut software = Software::new(4, 4);
+		let pixels = software.render(|encoder| {
+			let image = Image::new(TextureId(7));
+			Ok(encoder.image(0.0, 0.0, 4.0, 4.0, &image, Color::rgb(255, 0, 0))?)
+		}).unwrap();
+		assert_eq!(pixel(&pixels, 4, 2, 2), [255, 0, 0, 255]);
+	}
+
+	#[test]
+	fn updates_texture_regions() {
+		let mut software = Software::new(2, 2);
+		let texture = software.upload_texture(2, 2, &[0, 0, 0, 255].repeat(4)).unwrap();
+		let column = TextureUpdate { rect: [1, 0, 1, 2], pixels: vec![255; 8] };
+		let outside = TextureUpdate { rect: [1, 1, 2, 1], pixels: vec![255; 8] };
+		software.update_texture(texture, std::slice::from_ref(&column)).unwrap();
+		// All or nothing.
+		assert!(software.update_texture(texture, &[outside, column]).is_err());
+
+		let pixels = software.render(|encoder| {
+			Ok(encoder.image(0.0, 0.0, 2.0, 2.0, &Image::new(texture), Color::WHITE)?)
+		}).unwrap();
+		assert_eq!(pixel(&pixels, 2, 0, 1), [0, 0, 0, 255]);
+		assert_eq!(pixel(&pixels, 2, 1, 1), [255, 255, 255, 255]);
+	}
+
+	#[test]
+	fn nine_slice_keeps_the_corners() {
+		// A texel per slice, all different.
+		let texels: Vec<u8> = (0..9u8).flat_map(|i| [i * 28, 255 - i * 28, 0, 255]).collect();
+
+		let mut software = Software::new(9, 9);
+		let texture = software.upload_texture(3, 3, &texels).unwrap();
+		let pixels = software.render(|encoder| {
+			Ok(encoder.nine_slice(0.0, 0.0, 9.0, 9.0, &Image::new(texture), Insets::all(1.0), Color::WHITE)?)
+		}).unwrap();
+
+		let slice = |i: u8| [i * 28, 255 - i * 28, 0, 255];
+		assert_eq!(pixel(&pixels, 9, 0, 0), slice(0));
+		assert_eq!(pixel(&pixels, 9, 8, 0), slice(2));
+		assert_eq!(pixel(&pixels, 9, 0, 8), slice(6));
+		assert_eq!(pixel(&pixels, 9, 8, 8), slice(8));
+		// Stretched edges & center.
+		assert_eq!(pixel(&pixels, 9, 4, 0), slice(1));
+		assert_eq!(pixel(&pixels, 9, 0, 4), slice(3));
+		assert_eq!(pixel(&pixels, 9, 4, 4), slice(4));
+	}
+}
@@ -0,0 +1,595 @@
+//! Vulkan renderer drawing the prima buffers into a window.
+
+use std::mem;
+use std::slice;
+
+use ash::{vk, Entry};
+use ash::extensions::khr;
+
+use crate::Result;
+use crate::encoder::PrimaEncoder;
+use crate::texture::{TextureId, TextureUpdate};
+use crate::vulkan::{self, Pipeline, PrimaBuffers, Textures};
+use crate::platform::Window;
+
+const MAX_FRAMES_IN_FLIGHT: u32 = 2;
+
+pub struct Renderer {
+	// Keeps the Vulkan library loaded.
+	_entry:                 Entry,
+	instance:               ash::Instance,
+	khr_surface:            khr::Surface,
+	surface:                vk::SurfaceKHR,
+	device:                 ash::Device,
+	queue:                  vk::Queue,
+	physical_device:        vk::PhysicalDevice,
+	device_name:            String,
+	surface_format:         vk::SurfaceFormatKHR,
+	present_mode:           vk::PresentModeKHR,
+	khr_swapchain:          khr::Swapchain,
+	swapchain:              Swapchain,
+	render_pass:            vk::RenderPass,
+	frames:                 Vec<Frame>,
+	// Frame slot to record next, independent of the swapchain images.
+	frame_index:            usize,
+	prima:                  PrimaBuffers,
+	textures:               Textures,
+	tri_pipeline:           Pipeline,
+}
+
+/// Everything a frame in flight needs for itself.
+struct Frame {
+	command_pool:      vk::CommandPool,
+	cmd_buffer:        vk::CommandBuffer,
+	acquire_semaphore: vk::Semaphore,
+	// Signaled once the GPU is done with the frame.
+	fence:             vk::Fence,
+}
+
+struct Swapchain {
+	handle:       vk::SwapchainKHR,
+	// Window size the swapchain has been created for.
+	window_size:  (u32, u32),
+	extent:       vk::Extent2D,
+	images:       Vec<vk::Image>,
+	views:        Vec<vk::ImageView>,
+	framebuffers: Vec<vk::Framebuffer>,
+	// Per image rather than per frame slot, the present waiting on it
+	// isn't covered by the slot's fence.
+	release_semaphores: Vec<vk::Semaphore>,
+}
+
+impl Renderer {
+	/// # Safety
+	///
+	/// The window must outlive the renderer.
+	pub unsafe fn new(window: &dyn Window) -> Result<Self> {
+		let entry    = Entry::load()?;
+		let instance = vulkan::create_instance(&entry, &[khr::Surface::name(), window.surface_extension()])?;
+
+		// TODO: Make a nice callback, then enable this back.
+		// let debug_utils = ext::DebugUtils::new(&entry, &instance);
+		// let dbg_messenger_create_info = vk::DebugUtilsMessengerCreateInfoEXT::builder()
+		// 	.pfn_user_callback(Some(vulkan::vulkan_debug_message_callback));
+		// let dbg_messenger = debug_utils.create_debug_utils_messenger(&dbg_messenger_create_info, None)?;
+
+		let khr_surface = khr::Surface::new(&entry, &instance);
+		let surface     = window.create_surface(&entry, &instance)?;
+
+		let (physical_device, queue_family) = pick_physical_device_and_queue_family(
+			&instance,
+			&khr_surface,
+			surface,
+		)?;
+
+		let props       = instance.get_physical_device_properties(physical_device);
+		let device_name = vulkan::str_from_null_terminated_bytes(&props.device_name).to_owned();
+
+		let (device, queue) = vulkan::create_device_and_queue(
+			&instance,
+			physical_device,
+			queue_family,
+			&[khr::Swapchain::name()],
+		)?;
+
+		let surface_formats = khr_surface.get_physical_device_surface_formats(physical_device, surface)?;
+		let Some(surface_format) = surface_formats
+			.iter()
+			.copied()
+			.find(|f| f.format == vk::Format::B8G8R8A8_UNORM && f.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR)
+		else {
+			return Err("Swapchain doesn't support the srgb_bgra8.".into());
+		};
+
+		// let present_modes = khr_surface.get_physical_device_surface_present_modes(physical_device, surface)?;
+		// let present_mode = present_modes
+		// 	.iter()
+		// 	.copied()
+		// 	.find(|m| *m == vk::PresentModeKHR::MAILBOX) // more energy but lowest latency
+		// 	.unwrap_or(vk::PresentModeKHR::FIFO); // guaranteed to be available.
+		let present_mode = vk::PresentModeKHR::FIFO;
+
+		let window_client_size = window.client_size();
+
+		let render_pass   = vulkan::create_render_pass(&device, surface_format.format)?;
+		let khr_swapchain = khr::Swapchain::new(&instance, &device);
+		let swapchain     = create_swapchain(
+			&khr_surface,
+			surface,
+			physical_device,
+			&device,
+			&khr_swapchain,
+			surface_format,
+			present_mode,
+			render_pass,
+			window_client_size,
+			vk::SwapchainKHR::null(),
+		)?.ok_or("The window has no area to present into.")?;
+
+		let mut frames = Vec::with_capacity(MAX_FRAMES_IN_FLIGHT as usize);
+		for _ in 0..MAX_FRAMES_IN_FLIGHT {
+			frames.push(Frame::new(&device, queue_family)?);
+		}
+
+		let prima        = PrimaBuffers::new(&instance, physical_device, &device, MAX_FRAMES_IN_FLIGHT)?;
+		let textures     = Textures::new(&instance, physical_device, &device, queue, queue_family, MAX_FRAMES_IN_FLIGHT)?;
+		let set_layouts  = [prima.descriptor_set_layout, textures.descriptor_set_layout];
+		let tri_pipeline = vulkan::create_tri_pipeline(&device, render_pass, &set_layouts)?;
+
+		Ok(Self {
+			_entry: entry,
+			instance,
+			khr_surface,
+			surface,
+			device,
+			queue,
+			physical_device,
+			device_name,
+			surface_format,
+			present_mode,
+			khr_swapchain,
+			swapchain,
+			render_pass,
+			frames,
+			frame_index: 0,
+			prima,
+			textures,
+			tri_pipeline,
+		})
+	}
+
+	/// Size of the frame in pixels.
+	pub fn size(&self) -> (u32, u32) {
+		(self.swapchain.extent.width, self.swapchain.extent.height)
+	}
+
+	/// Name of the GPU rendering the frames.
+	pub fn device_name(&self) -> &str {
+		&self.device_name
+	}
+
+	/// Uploads tightly packed RGBA8 `pixels`, top row first.
+	///
+	/// Waits for the frames in flight, meant for load time rather than
+	/// every frame.
+	pub fn upload_texture(&mut self, width: u32, height: u32, pixels: &[u8]) -> Result<TextureId> {
+		unsafe {
+			self.device.device_wait_idle()?;
+			self.textures.upload(&self.device, width, height, pixels)
+		}
+	}
+
+	/// Overwrites parts of the `id` texture, all of `updates` or none,
+	/// before the next frame is drawn.
+	///
+	/// Only waits for the previous frame of the slot drawn next.
+	pub fn update_texture(&mut self, id: TextureId, updates: &[TextureUpdate]) -> Result<()> {
+		let slot  = self.frame_index;
+		let fence = self.frames[slot].fence;
+		unsafe {
+			self.device.wait_for_fences(slice::from_ref(&fence), true, u64::MAX)?;
+			self.textures.stage(&self.device, slot, id, updates)
+		}
+	}
+
+	/// Encodes a frame via `encode` and presents it into `window`.
+	///
+	/// The swapchain follows the window size, nothing is drawn while
+	/// the window is minimized.
+	pub fn frame<F>(&mut self, window: &dyn Window, encode: F) -> Result<()>
+	where
+		F: FnOnce(&mut PrimaEncoder) -> Result<()>,
+	{
+		let window_size = window.client_size();
+		if window_size.0 == 0 || window_size.1 == 0 {
+			return Ok(());
+		}
+
+		unsafe {
+			// The surface might be minimized already, even if the window
+			// size said otherwise a moment ago.
+			if window_size != self.swapchain.window_size && !self.recreate_swapchain(window_size)? {
+				return Ok(());
+			}
+
+			let outdated = self.draw(encode)?;
+			if outdated {
+				self.recreate_swapchain(window_size)?;
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Returns `true` if the swapchain has to be recreated.
+	unsafe fn draw<F>(&mut self, encode: F) -> Result<bool>
+	where
+		F: FnOnce(&mut PrimaEncoder) -> Result<()>,
+	{
+		let slot = self.frame_index;
+
+		// The slot's buffers are free to be overwritten once its previous
+		// submission is done, the other slots might still be in flight.
+		let fence = self.frames[slot].fence;
+		self.device.wait_for_fences(slice::from_ref(&fence), true, u64::MAX)?;
+
+		// Preparation
+
+		let w = self.swapchain.extent.width  as f32;
+		let h = self.swapchain.extent.height as f32;
+
+		let (pbuf, ibuf) = self.prima.frame(slot);
+		let mut encoder  = PrimaEncoder::new(pbuf, ibuf, w, h)?;
+		encode(&mut encoder)?;
+		let indices = encoder.finish();
+
+		let frame = &self.frames[slot];
+
+		let acquired = self.khr_swapchain.acquire_next_image(
+			self.swapchain.handle,
+			u64::MAX,
+			frame.acquire_semaphore,
+			vk::Fence::null(),
+		);
+		// Suboptimal is still presentable, so it is handled after the present.
+		let (i, suboptimal) = match acquired {
+			Ok(acquired) => acquired,
+			Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => return Ok(true),
+			Err(e) => return Err(e.into()),
+		};
+
+		// Only reset once something is going to be submitted, otherwise
+		// the next wait would never return.
+		self.device.reset_fences(slice::from_ref(&fence))?;
+
+		let acquire_semaphore = frame.acquire_semaphore;
+		let release_semaphore = self.swapchain.release_semaphores[i as usize];
+		let command_pool      = frame.command_pool;
+		let cmd_buffer        = frame.cmd_buffer;
+
+		// Rendering
+
+		let device = &self.device;
+
+		device.reset_command_pool(command_pool, vk::CommandPoolResetFlags::empty())?;
+
+		let cmd_buffer_begin_info = vk::CommandBufferBeginInfo::builder()
+			.flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+		device.begin_command_buffer(cmd_buffer, &cmd_buffer_begin_info)?;
+
+		self.textures.cmd_updates(device, cmd_buffer, slot);
+
+		let image = self.swapchain.images[i as usize];
+		let render_begin_barrier = vulkan::image_barrier(
+			&image,
+			vk::AccessFlags::empty(),
+			vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+			vk::ImageLayout::UNDEFINED,
+			vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+		);
+		device.cmd_pipeline_barrier(
+			cmd_buffer,
+			vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+			vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+			vk::DependencyFlags::BY_REGION,
+			&[],
+			&[],
+			slice::from_ref(&render_begin_barrier),
+		);
+
+		let clear_value = vulkan::clear_value();
+		let render_pass_begin_info = vk::RenderPassBeginInfo::builder()
+			.render_pass(self.render_pass)
+			.framebuffer(self.swapchain.framebuffers[i as usize])
+			.render_area(vk::Rect2D {
+				offset: vk::Offset2D::default(),
+				extent: self.swapchain.extent,
+			})
+			.clear_values(slice::from_ref(&clear_value));
+
+		device.cmd_begin_render_pass(cmd_buffer, &render_pass_begin_info, vk::SubpassContents::INLINE);
+		self.prima.cmd_draw(device, cmd_buffer, &self.tri_pipeline, &self.textures, self.swapchain.extent, slot, indices);
+		device.cmd_end_render_pass(cmd_buffer);
+
+		let render_end_barrier = vulkan::image_barrier(
+			&image,
+			vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+			vk::AccessFlags::empty(),
+			vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+			vk::ImageLayout::PRESENT_SRC_KHR,
+		);
+		device.cmd_pipeline_barrier(
+			cmd_buffer,
+			vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+			vk::PipelineStageFlags::TOP_OF_PIPE,
+			vk::DependencyFlags::BY_REGION,
+			&[],
+			&[],
+			slice::from_ref(&render_end_barrier),
+		);
+
+		device.end_command_buffer(cmd_buffer)?;
+
+		let submit_stage_mask = vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT;
+		let submit_info       = vk::SubmitInfo::builder()
+			.wait_semaphores(slice::from_ref(&acquire_semaphore))
+			.signal_semaphores(slice::from_ref(&release_semaphore))
+			.wait_dst_stage_mask(slice::from_ref(&submit_stage_mask))
+			.command_buffers(slice::from_ref(&cmd_buffer));
+		device.queue_submit(self.queue, slice::from_ref(&*submit_info), fence)?;
+
+		self.frame_index = (slot + 1) % self.frames.len();
+
+		let present_info = vk::PresentInfoKHR::builder()
+			.wait_semaphores(slice::from_ref(&release_semaphore))
+			.swapchains(slice::from_ref(&self.swapchain.handle))
+			.image_indices(slice::from_ref(&i));
+		let outdated = match self.khr_swapchain.queue_present(self.queue, &present_info) {
+			Ok(suboptimal_present) => suboptimal || suboptimal_present,
+			Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => true,
+			Err(e) => return Err(e.into()),
+		};
+
+		Ok(outdated)
+	}
+
+	/// Returns `false` if the surface has no area, the current swapchain
+	/// is kept then.
+	unsafe fn recreate_swapchain(&mut self, window_size: (u32, u32)) -> Result<bool> {
+		self.device.device_wait_idle()?;
+
+		let swapchain = create_swapchain(
+			&self.khr_surface,
+			self.surface,
+			self.physical_device,
+			&self.device,
+			&self.khr_swapchain,
+			self.surface_format,
+			self.present_mode,
+			self.render_pass,
+			window_size,
+			self.swapchain.handle,
+		)?;
+		let Some(swapchain) = swapchain else {
+			return Ok(false);
+		};
+
+		let mut old = mem::replace(&mut self.swapchain, swapchain);
+		old.destroy(&self.device, &self.khr_swapchain);
+
+		Ok(true)
+	}
+}
+
+impl Frame {
+	unsafe fn new(device: &ash::Device, queue_family: u32) -> Result<Self> {
+		let (command_pool, cmd_buffer) = vulkan::create_command_buffer(device, queue_family)?;
+
+		let semaphore_create_info = vk::SemaphoreCreateInfo::default();
+		let acquire_semaphore     = device.create_semaphore(&semaphore_create_info, None)?;
+
+		// Signaled, so the very first wait on it returns right away.
+		let fence_create_info = vk::FenceCreateInfo::builder()
+			.flags(vk::FenceCreateFlags::SIGNALED);
+		let fence = device.create_fence(&fence_create_info, None)?;
+
+		Ok(Self {
+			command_pool,
+			cmd_buffer,
+			acquire_semaphore,
+			fence,
+		})
+	}
+
+	unsafe fn destroy(self, device: &ash::Device) {
+		device.destroy_fence(self.fence, None);
+		device.destroy_semaphore(self.acquire_semaphore, None);
+		device.destroy_command_pool(self.command_pool, None);
+	}
+}
+
+impl Swapchain {
+	unsafe fn destroy(&mut self, device: &ash::Device, khr_swapchain: &khr::Swapchain) {
+		for framebuffer in self.framebuffers.drain(..) {
+			device.destroy_framebuffer(framebuffer, None);
+		}
+		for view in self.views.drain(..) {
+			device.destroy_image_view(view, None);
+		}
+		for semaphore in self.release_semaphores.drain(..) {
+			device.destroy_semaphore(semaphore, None);
+		}
+		khr_swapchain.destroy_swapchain(self.handle, None);
+	}
+}
+
+impl Drop for Renderer {
+	fn drop(&mut self) {
+		unsafe {
+			let device = &self.device;
+
+			let _ = device.device_wait_idle();
+
+			self.tri_pipeline.destroy(device);
+			self.textures.destroy(device);
+			self.prima.destroy(device);
+			for frame in self.frames.drain(..) {
+				frame.destroy(device);
+			}
+			self.swapchain.destroy(device, &self.khr_swapchain);
+			device.destroy_render_pass(self.render_pass, None);
+			self.khr_surface.destroy_surface(self.surface, None);
+			device.destroy_device(None);
+			// debug_utils.destroy_debug_utils_messenger(dbg_messenger, None);
+			self.instance.destroy_instance(None);
+		}
+	}
+}
+
+/// Creates a swapchain fitting the window, along with the image views,
+/// framebuffers & release semaphores. `old` is the swapchain being
+/// replaced, if any. Returns `None` while the surface has no area.
+#[allow(clippy::too_many_arguments)]
+unsafe fn create_swapchain(
+	khr_surface: &khr::Surface,
+	surface: vk::SurfaceKHR,
+	physical_device: vk::PhysicalDevice,
+	device: &ash::Device,
+	khr_swapchain: &khr::Swapchain,
+	surface_format: vk::SurfaceFormatKHR,
+	present_mode: vk::PresentModeKHR,
+	render_pass: vk::RenderPass,
+	window_size: (u32, u32),
+	old: vk::SwapchainKHR,
+) -> Result<Option<Swapchain>> {
+	let surface_caps = khr_surface.get_physical_device_surface_capabilities(physical_device, surface)?;
+	let Some(extent) = swapchain_extent(&surface_caps, window_size) else {
+		return Ok(None);
+	};
+
+	let swapchain_create_info = vk::SwapchainCreateInfoKHR::builder()
+		.surface(surface)
+		.min_image_count(surface_caps.min_image_count)
+		.image_format(surface_format.format)
+		.image_color_space(surface_format.color_space)
+		.image_extent(extent)
+		.image_array_layers(1)
+		.image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT)
+		.image_sharing_mode(vk::SharingMode::EXCLUSIVE) // we have same queue for graphics & presentation
+		.pre_transform(surface_caps.current_transform)
+		.composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
+		.present_mode(present_mode)
+		.clipped(true)
+		.old_swapchain(old);
+
+	let handle = khr_swapchain.create_swapchain(&swapchain_create_info, None)?;
+	let images = khr_swapchain.get_swapchain_images(handle)?;
+
+	let mut views = Vec::with_capacity(images.len());
+	for image in images.iter() {
+		let view = vulkan::create_image_view(device, *image, surface_format.format)?;
+		views.push(view);
+	}
+
+	let mut framebuffers = Vec::with_capacity(views.len());
+	for view in views.iter() {
+		let framebuffer = vulkan::create_framebuffer(device, render_pass, *view, extent)?;
+		framebuffers.push(framebuffer);
+	}
+
+	let mut release_semaphores = Vec::with_capacity(images.len());
+	for _ in 0..images.len() {
+		let semaphore = device.create_semaphore(&vk::SemaphoreCreateInfo::default(), None)?;
+		release_semaphores.push(semaphore);
+	}
+
+	Ok(Some(Swapchain {
+		handle,
+		window_size,
+		extent,
+		images,
+		views,
+		framebuffers,
+		release_semaphores,
+	}))
+}
+
+/// The surface either dictates the extent or lets the window size
+/// decide, within the supported bounds. `None` if the surface has no
+/// area, e.g. minimized, no swapchain can be created then.
+fn swapchain_extent(caps: &vk::SurfaceCapabilitiesKHR, window_size: (u32, u32)) -> Option<vk::Extent2D> {
+	let extent = if caps.current_extent.width != u32::MAX {
+		caps.current_extent
+	} else {
+		vk::Extent2D {
+			width:  window_size.0.clamp(caps.min_image_extent.width,  caps.max_image_extent.width),
+			height: window_size.1.clamp(caps.min_image_extent.height, caps.max_image_extent.height),
+		}
+	};
+
+	(extent.width != 0 && extent.height != 0).then_some(extent)
+}
+
+unsafe fn pick_physical_device_and_queue_family(
+	instance: &ash::Instance,
+	khr_surface: &khr::Surface,
+	surface: vk::SurfaceKHR,
+) -> Result<(vk::PhysicalDevice, u32)> {
+	let physical_devices = instance.enumerate_physical_devices()?;
+
+	let found = physical_devices
+		.iter()
+		.find_map(|pdevice| {
+			instance
+				.get_physical_device_queue_family_properties(*pdevice)
+				.iter()
+				.enumerate()
+				.find_map(|(i, props)| {
+					let has_gfx     = props.queue_flags.contains(vk::QueueFlags::GRAPHICS);
+					let can_present = khr_surface.get_physical_device_surface_support(*pdevice, i as u32, surface).ok()?;
+					if has_gfx && can_present {
+						Some((*pdevice, i as u32))
+					} else {
+						None
+					}
+				})
+		});
+
+	found.ok_or_else(|| "Failed to find a suitable physical device.".into())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn caps(current: (u32, u32)) -> vk::SurfaceCapabilitiesKHR {
+		vk::SurfaceCapabilitiesKHR {
+			current_extent:   vk::Extent2D { width: current.0, height: current.1 },
+			min_image_extent: vk::Extent2D { width: 16, height: 16 },
+			max_image_extent: vk::Extent2D { width: 4096, height: 2048 },
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn extent_follows_the_surface() {
+		let extent = swapchain_extent(&caps((640, 480)), (800, 600)).unwrap();
+		assert_eq!((extent.width, extent.height), (640, 480));
+	}
+
+	#[test]
+	fn extent_clamps_the_window_size() {
+		let extent = swapchain_extent(&caps((u32::MAX, u32::MAX)), (800, 600)).unwrap();
+		assert_eq!((extent.width, extent.height), (800, 600));
+
+		let extent = swapchain_extent(&caps((u32::MAX, u32::MAX)), (8, 3000)).unwrap();
+		assert_eq!((extent.width, extent.height), (16, 2048));
+	}
+
+	#[test]
+	fn no_extent_while_minimized() {
+		// The window was minimized after its size was checked.
+		assert!(swapchain_extent(&caps((0, 0)), (800, 600)).is_none());
+		assert!(swapchain_extent(&caps((640, 0)), (800, 600)).is_none());
+	}
+}
@@ -0,0 +1,282 @@
+//! Glyph atlases & the fonts drawn out of them.
+//!
+//! Glyph bitmaps are packed into a [`GlyphAtlas`], which is uploaded
+//! as a regular texture. A [`Font`] maps characters to their spots in
+//! it, [`crate::encoder::PrimaEncoder::text`] turns strings into
+//! PRIMA_GLYPH quads sampling the atlas, so text goes into the same
+//! draw as everything else.
+//!
+//! Glyphs are either plain coverage, crisp at the size they have been
+//! rasterized at, or distance fields, which stay crisp scaled up, see
+//! [`GlyphFormat`].
+
+use std::collections::HashMap;
+
+use crate::texture::TextureId;
+
+/// Empty texels kept around every glyph, so bilinear sampling never
+/// bleeds into the neighbours.
+const PADDING: u32 = 1;
+
+/// Vertical metrics of a font at its size, in pixels. Descent goes
+/// below the baseline, so it is negative.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LineMetrics {
+	pub ascent:   f32,
+	pub descent:  f32,
+	pub line_gap: f32,
+}
+
+impl LineMetrics {
+	pub const fn new(ascent: f32, descent: f32, line_gap: f32) -> Self {
+		Self { ascent, descent, line_gap }
+	}
+
+	/// Distance between the baselines of two lines.
+	pub fn line_height(&self) -> f32 {
+		self.ascent - self.descent + self.line_gap
+	}
+}
+
+/// How the glyphs of a font are stored in its atlas & drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum GlyphFormat {
+	/// Coverage in alpha, PRIMA_GLYPH.
+	#[default]
+	Coverage,
+	/// Signed distance to the outline in alpha, 0 & 255 are `range`
+	/// texels outside & inside, PRIMA_SDF_GLYPH. Corners come out
+	/// rounded when scaled up.
+	Sdf { range: f32 },
+	/// Signed distances to differently colored edges in RGB, whose
+	/// median keeps corners sharp, PRIMA_MSDF_GLYPH. Alpha holds the
+	/// same as with `Sdf`.
+	Msdf { range: f32 },
+}
+
+/// Bitmap of a glyph in its atlas & how it sits on the baseline, at
+/// the size of its font.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Glyph {
+	/// x, y, w & h in atlas texels, empty for blank glyphs.
+	pub rect:    [u32; 4],
+	/// From the pen on the baseline to the top-left corner of the
+	/// bitmap, y goes down.
+	pub offset:  [f32; 2],
+	pub advance: f32,
+}
+
+/// Coverage glyph drawn straight out of a texture, see
+/// [`crate::encoder::PrimaEncoder::glyphs`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GlyphQuad {
+	/// x, y, w & h in pixels.
+	pub rect: [f32; 4],
+	/// x, y, w & h in normalized texture coordinates.
+	pub uv:   [f32; 4],
+}
+
+/// Glyphs packed into shelves of an RGBA8 texture. Coverage goes in
+/// white with the coverage in alpha, so it is a fine image on its own.
+pub struct GlyphAtlas {
+	pub width:  u32,
+	pub height: u32,
+	pub pixels: Vec<u8>,
+
+	// Top-left corner of the free space on the current shelf & its
+	// height so far.
+	shelf_x: u32,
+	shelf_y: u32,
+	shelf_h: u32,
+}
+
+impl GlyphAtlas {
+	pub fn new(width: u32, height: u32) -> Self {
+		Self {
+			width,
+			height,
+			pixels:  vec![0; width as usize * height as usize * 4],
+			shelf_x: 0,
+			shelf_y: 0,
+			shelf_h: 0,
+		}
+	}
+
+	/// Copies `width` x `height` bytes of `coverage` into a free spot,
+	/// returns the rect it has landed at or `None` if the atlas is
+	/// full.
+	pub fn insert(&mut self, width: u32, height: u32, coverage: &[u8]) -> Option<[u32; 4]> {
+		assert_eq!(coverage.len(), width as usize * height as usize);
+
+		let rect = self.allocate(width, height)?;
+		for (row, line) in coverage.chunks_exact(width.max(1) as usize).enumerate() {
+			let texels = self.row(rect, row);
+			for (texel, a) in texels.chunks_exact_mut(4).zip(line) {
+				texel.copy_from_slice(&[255, 255, 255, *a]);
+			}
+		}
+
+		Some(rect)
+	}
+
+	/// Same as [`Self::insert`], for `width` x `height` RGBA8 `pixels`
+	/// such as distance fields.
+	pub fn insert_rgba(&mut self, width: u32, height: u32, pixels: &[u8]) -> Option<[u32; 4]> {
+		assert_eq!(pixels.len(), width as usize * height as usize * 4);
+
+		let rect = self.allocate(width, height)?;
+		for (row, line) in pixels.chunks_exact(width.max(1) as usize * 4).enumerate() {
+			self.row(rect, row).copy_from_slice(line);
+		}
+
+		Some(rect)
+	}
+
+	/// Free spot of `width` x `height` texels, blank glyphs take no
+	/// room.
+	fn allocate(&mut self, width: u32, height: u32) -> Option<[u32; 4]> {
+		if width == 0 || height == 0 {
+			return Some([0; 4]);
+		}
+
+		let w = width  + PADDING * 2;
+		let h = height + PADDING * 2;
+		if w > self.width {
+			return None;
+		}
+
+		if self.shelf_x + w > self.width {
+			self.shelf_y += self.shelf_h;
+			self.shelf_x  = 0;
+			self.shelf_h  = 0;
+		}
+		if self.shelf_y + h > self.height {
+			return None;
+		}
+
+		let x = self.shelf_x + PADDING;
+		let y = self.shelf_y + PADDING;
+		self.shelf_x += w;
+		self.shelf_h  = self.shelf_h.max(h);
+
+		Some([x, y, width, height])
+	}
+
+	/// Texels of the `row`-th row of `rect`.
+	fn row(&mut self, rect: [u32; 4], row: usize) -> &mut [u8] {
+		let at = ((rect[1] as usize + row) * self.width as usize + rect[0] as usize) * 4;
+		&mut self.pixels[at..at + rect[2] as usize * 4]
+	}
+}
+
+/// Glyphs rasterized at `size` pixels into an atlas, uploaded as
+/// `texture`. Drawn at other sizes they are scaled.
+#[derive(Clone, Debug)]
+pub struct Font {
+	texture:    TextureId,
+	atlas_size: [f32; 2],
+	size:       f32,
+	metrics:    LineMetrics,
+	format:     GlyphFormat,
+	glyphs:     HashMap<char, Glyph>,
+	kerning:    HashMap<(char, char), f32>,
+}
+
+impl Font {
+	pub fn new(texture: TextureId, atlas_size: (u32, u32), size: f32, metrics: LineMetrics) -> Self {
+		Self {
+			texture,
+			atlas_size: [atlas_size.0 as f32, atlas_size.1 as f32],
+			size,
+			metrics,
+			format:  GlyphFormat::Coverage,
+			glyphs:  HashMap::new(),
+			kerning: HashMap::new(),
+		}
+	}
+
+	/// The glyphs of the atlas are in `format` rather than coverage.
+	pub fn with_format(mut self, format: GlyphFormat) -> Self {
+		self.format = format;
+		self
+	}
+
+	pub fn add_glyph(&mut self, c: char, glyph: Glyph) {
+		self.glyphs.insert(c, glyph);
+	}
+
+	/// Adjusts the advance of `left` when followed by `right`, in
+	/// pixels at the size of the font.
+	pub fn add_kerning(&mut self, left: char, right: char, amount: f32) {
+		self.kerning.insert((left, right), amount);
+	}
+
+	/// Characters without a glyph are not drawn & don't advance.
+	pub fn glyph(&self, c: char) -> Option<&Glyph> {
+		self.glyphs.get(&c)
+	}
+
+	pub fn kerning(&self, left: char, right: char) -> f32 {
+		self.kerning.get(&(left, right)).copied().unwrap_or(0.0)
+	}
+
+	pub fn texture(&self) -> TextureId {
+		self.texture
+	}
+
+	/// Size of the atlas in texels.
+	pub fn atlas_size(&self) -> [f32; 2] {
+		self.atlas_size
+	}
+
+	/// Pixel size the glyphs have been rasterized at.
+	pub fn size(&self) -> f32 {
+		self.size
+	}
+
+	pub fn metrics(&self) -> LineMetrics {
+		self.metrics
+	}
+
+	pub fn format(&self) -> GlyphFormat {
+		self.format
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn packs_glyphs_into_shelves() {
+		let mut atlas = GlyphAtlas::new(16, 16);
+
+		assert_eq!(atlas.insert(4, 6, &[255; 24]), Some([1, 1, 4, 6]));
+		assert_eq!(atlas.insert(5, 3, &[255; 15]), Some([7, 1, 5, 3]));
+		// Doesn't fit into the rest of the shelf, starts the next one
+		// below the tallest glyph.
+		assert_eq!(atlas.insert(6, 2, &[255; 12]), Some([1, 9, 6, 2]));
+
+		let texel = |x: usize, y: usize| &atlas.pixels[(y * 16 + x) * 4..(y * 16 + x) * 4 + 4];
+		assert_eq!(texel(1, 1), [255, 255, 255, 255]);
+		assert_eq!(texel(0, 0), [0, 0, 0, 0]);
+	}
+
+	#[test]
+	fn reports_a_full_atlas() {
+		let mut atlas = GlyphAtlas::new(8, 8);
+		assert!(atlas.insert(6, 6, &[0; 36]).is_some());
+		assert!(atlas.insert(1, 1, &[0; 1]).is_none());
+		assert!(atlas.insert(7, 1, &[0; 7]).is_none());
+		// Blank glyphs take no room.
+		assert_eq!(atlas.insert(0, 0, &[]), Some([0; 4]));
+	}
+
+	#[test]
+	fn inserts_rgba_texels() {
+		let mut atlas = GlyphAtlas::new(8, 8);
+		assert!(atlas.insert(2, 1, &[255; 2]).is_some());
+		assert_eq!(atlas.insert_rgba(2, 1, &[1, 2, 3, 4, 5, 6, 7, 8]), Some([5, 1, 2, 1]));
+		assert_eq!(&atlas.pixels[(8 + 5) * 4..(8 + 7) * 4], [1, 2, 3, 4, 5, 6, 7, 8]);
+	}
+}
@@ -0,0 +1,101 @@
+//! Textures sampled by the PRIMA_IMAGE primitives.
+//!
+//! Every renderer owns its textures. Ids are handed out in the upload
+//! order and only mean something to the renderer that made them.
+
+use crate::Result;
+use crate::index::MAX_TEXTURES;
+
+/// Slot of an uploaded texture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TextureId(pub(crate) u32);
+
+/// Texels overwriting a part of an uploaded texture.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TextureUpdate {
+	/// x, y, w & h in texels.
+	pub rect:   [u32; 4],
+	/// Tightly packed RGBA8, top row first.
+	pub pixels: Vec<u8>,
+}
+
+/// Checks an upload of tightly packed RGBA8 `pixels`, returns the
+/// slot it goes into after `uploaded` ones.
+pub(crate) fn next_id(uploaded: usize, width: u32, height: u32, pixels: &[u8]) -> Result<TextureId> {
+	if width == 0 || height == 0 {
+		return Err("Texture must not be empty.".into());
+	}
+
+	let size = width as usize * height as usize * 4;
+	if pixels.len() != size {
+		return Err(format!("Texture of {width}x{height} needs {size} bytes, got {}.", pixels.len()).into());
+	}
+
+	if uploaded >= MAX_TEXTURES {
+		return Err(format!("All of the {MAX_TEXTURES} texture slots are taken.").into());
+	}
+
+	Ok(TextureId(uploaded as u32))
+}
+
+/// Checks `update` of a texture of `size`.
+pub(crate) fn check_update(size: (u32, u32), update: &TextureUpdate) -> Result<()> {
+	let TextureUpdate { rect, pixels } = update;
+	let [x, y, width, height] = *rect;
+	if width == 0 || height == 0 {
+		return Err("Texture update must not be empty.".into());
+	}
+
+	if x.checked_add(width).is_none_or(|right| right > size.0) || y.checked_add(height).is_none_or(|bottom| bottom > size.1) {
+		return Err(format!("Texture update {rect:?} is out of the {}x{} texture.", size.0, size.1).into());
+	}
+
+	let bytes = width as usize * height as usize * 4;
+	if pixels.len() != bytes {
+		return Err(format!("Texture update of {width}x{height} needs {bytes} bytes, got {}.", pixels.len()).into());
+	}
+
+	Ok(())
+}
+
+/// Whether the rects `a` & `b`, x, y, w & h in texels, share a texel.
+pub(crate) fn overlaps(a: [u32; 4], b: [u32; 4]) -> bool {
+	a[0] < b[0] + b[2] && b[0] < a[0] + a[2] && a[1] < b[1] + b[3] && b[1] < a[1] + a[3]
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn ids_follow_the_uploads() {
+		assert_eq!(next_id(0, 2, 2, &[0; 16]).unwrap(), TextureId(0));
+		assert_eq!(next_id(5, 1, 1, &[0; 4]).unwrap(), TextureId(5));
+	}
+
+	#[test]
+	fn rejects_bad_uploads() {
+		assert!(next_id(0, 0, 4, &[]).is_err());
+		assert!(next_id(0, 2, 2, &[0; 12]).is_err());
+		assert!(next_id(MAX_TEXTURES, 1, 1, &[0; 4]).is_err());
+	}
+
+	#[test]
+	fn checks_updates() {
+		let update = |rect, bytes| TextureUpdate { rect, pixels: vec![0; bytes] };
+		assert!(check_update((4, 4), &update([2, 1, 2, 3], 24)).is_ok());
+		assert!(check_update((4, 4), &update([3, 0, 2, 1], 8)).is_err());
+		assert!(check_update((4, 4), &update([0, 0, 1, 1], 8)).is_err());
+		assert!(check_update((4, 4), &update([0, 0, 0, 1], 0)).is_err());
+		assert!(check_update((4, 4), &update([u32::MAX, 0, 1, 1], 4)).is_err());
+	}
+
+	#[test]
+	fn overlapping_rects() {
+		assert!(overlaps([0, 0, 4, 4], [3, 3, 2, 2]));
+		assert!(overlaps([2, 0, 1, 8], [0, 4, 8, 1]));
+		// Touching isn't overlapping.
+		assert!(!overlaps([0, 0, 4, 4], [4, 0, 4, 4]));
+		assert!(!overlaps([0, 0, 4, 4], [0, 4, 4, 4]));
+	}
+}
@@ -0,0 +1,1098 @@
+//! Vulkan plumbing shared by the window & the headless renderers.
+
+use std::ffi::{c_void, CStr};
+use std::slice;
+use std::str;
+
+use ash::{vk, Entry};
+use ash::extensions::ext;
+
+use crate::{Result, CLEAR_COLOR};
+use crate::index::MAX_TEXTURES;
+use crate::texture::{self, TextureId, TextureUpdate};
+
+const TRI_VERT_SPV: &[u8] = include_bytes!("../shaders/tri.vert.spv");
+const TRI_FRAG_SPV: &[u8] = include_bytes!("../shaders/tri.frag.spv");
+
+pub(crate) const PRIMA_SIZE_PER_FRAME: u64 = 64 * 1024;
+
+pub(crate) unsafe fn create_instance(entry: &Entry, extensions: &[&CStr]) -> Result<ash::Instance> {
+	// TODO: Setup the debug callback to print messages.
+
+	let app_info = vk::ApplicationInfo {
+		// Descriptor indexing is core since 1.2.
+		api_version: vk::make_api_version(0, 1, 2, 0),
+		..Default::default()
+	};
+
+	let available_extensions = entry.enumerate_instance_extension_properties(None)?;
+	let has_extension = |name: &CStr| available_extensions
+		.iter()
+		.any(|e| CStr::from_ptr(e.extension_name.as_ptr()) == name);
+
+	let mut instance_extensions: Vec<_> = extensions
+		.iter()
+		.map(|e| e.as_ptr())
+		.collect();
+	if has_extension(ext::DebugUtils::name()) {
+		instance_extensions.push(ext::DebugUtils::name().as_ptr());
+	}
+
+	// Validation is nice to have, but build machines usually
+	// come without the SDK installed.
+	let validation = c"VK_LAYER_KHRONOS_validation";
+	let available_layers = entry.enumerate_instance_layer_properties()?;
+	let has_validation = available_layers
+		.iter()
+		.any(|l| CStr::from_ptr(l.layer_name.as_ptr()) == validation);
+
+	let mut layers = Vec::new();
+	if has_validation {
+		// TODO: Make it a debug/cmd-line flag only.
+		layers.push(validation.as_ptr());
+	}
+
+	let create_info = vk::InstanceCreateInfo::builder()
+		.application_info(&app_info)
+		.enabled_extension_names(&instance_extensions)
+		.enabled_layer_names(&layers);
+
+	let instance = entry.create_instance(&create_info, None)?;
+
+	Ok(instance)
+}
+
+pub(crate) struct Pipeline {
+	pub(crate) handle: vk::Pipeline,
+	pub(crate) layout: vk::PipelineLayout,
+}
+
+impl Pipeline {
+	pub(crate) unsafe fn destroy(&self, device: &ash::Device) {
+		device.destroy_pipeline(self.handle, None);
+		device.destroy_pipeline_layout(self.layout, None);
+	}
+}
+
+pub(crate) unsafe fn create_device_and_queue(
+	instance: &ash::Instance,
+	physical_device: vk::PhysicalDevice,
+	queue_family: u32,
+	extensions: &[&CStr],
+) -> Result<(ash::Device, vk::Queue)> {
+	let queue_priority = [1.0];
+
+	let queue_create_info = vk::DeviceQueueCreateInfo::builder()
+		.queue_family_index(queue_family)
+		.queue_priorities(&queue_priority);
+
+	// Every image indexes the texture array on its own, see `Textures`.
+	let mut indexing_features = vk::PhysicalDeviceDescriptorIndexingFeatures::default();
+	let mut features = vk::PhysicalDeviceFeatures2::builder()
+		.push_next(&mut indexing_features);
+	instance.get_physical_device_features2(physical_device, &mut features);
+	if indexing_features.shader_sampled_image_array_non_uniform_indexing == vk::FALSE {
+		return Err("Physical device doesn't support non-uniform texture indexing.".into());
+	}
+
+	let mut enabled_indexing_features = vk::PhysicalDeviceDescriptorIndexingFeatures::builder()
+		.shader_sampled_image_array_non_uniform_indexing(true);
+
+	let device_extensions: Vec<_> = extensions
+		.iter()
+		.map(|e| e.as_ptr())
+		.collect();
+	let device_create_info = vk::DeviceCreateInfo::builder()
+		.queue_create_infos(slice::from_ref(&queue_create_info))
+		.enabled_extension_names(&device_extensions)
+		.push_next(&mut enabled_indexing_features);
+
+	let device = instance.create_device(physical_device, &device_create_info, None)?;
+	let queue  = device.get_device_queue(queue_family, 0);
+
+	Ok((device, queue))
+}
+
+pub(crate) unsafe fn create_tri_pipeline(
+	device: &ash::Device,
+	render_pass: vk::RenderPass,
+	set_layouts: &[vk::DescriptorSetLayout],
+) -> Result<Pipeline> {
+	let vs_shader_spv = read_spv(TRI_VERT_SPV);
+	let shader_create_info = vk::ShaderModuleCreateInfo::builder()
+		.code(&vs_shader_spv);
+	let vs_shader = device.create_shader_module(&shader_create_info, None)?;
+
+	let fs_shader_spv = read_spv(TRI_FRAG_SPV);
+	let shader_create_info = vk::ShaderModuleCreateInfo::builder()
+		.code(&fs_shader_spv);
+	let fs_shader = device.create_shader_module(&shader_create_info, None)?;
+
+	let pipeline_cache = vk::PipelineCache::null(); // TODO:
+
+	let shader_main_name = c"main";
+	let vs_stage_create_info = vk::PipelineShaderStageCreateInfo::builder()
+		.stage(vk::ShaderStageFlags::VERTEX)
+		.module(vs_shader)
+		.name(shader_main_name);
+	let fs_stage_create_info = vk::PipelineShaderStageCreateInfo::builder()
+		.stage(vk::ShaderStageFlags::FRAGMENT)
+		.module(fs_shader)
+		.name(shader_main_name);
+
+	let stages = [
+		*vs_stage_create_info,
+		*fs_stage_create_info,
+	];
+
+	let vertex_input_state_create_info = vk::PipelineVertexInputStateCreateInfo::default();
+
+	let input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo::builder()
+		.topology(vk::PrimitiveTopology::TRIANGLE_LIST);
+
+	let viewport_state = vk::PipelineViewportStateCreateInfo::builder()
+		.viewport_count(1)
+		.scissor_count(1);
+
+	let rasterization_state = vk::PipelineRasterizationStateCreateInfo::builder()
+		.line_width(1.0);
+
+	let multisample_state = vk::PipelineMultisampleStateCreateInfo::builder()
+		.rasterization_samples(vk::SampleCountFlags::TYPE_1);
+
+	let depth_stencil_state = vk::PipelineDepthStencilStateCreateInfo::default();
+
+	// Straight alpha "over", mirrored by the software rasterizer.
+	let color_blend_state_attachment = vk::PipelineColorBlendAttachmentState::builder()
+		.blend_enable(true)
+		.src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
+		.dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+		.color_blend_op(vk::BlendOp::ADD)
+		.src_alpha_blend_factor(vk::BlendFactor::ONE)
+		.dst_alpha_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+		.alpha_blend_op(vk::BlendOp::ADD)
+		.color_write_mask(vk::ColorComponentFlags::RGBA);
+	let color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder()
+		.attachments(slice::from_ref(&color_blend_state_attachment));
+
+	let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+	let dynamic_states = vk::PipelineDynamicStateCreateInfo::builder()
+		.dynamic_states(&dynamic_states);
+
+	let pipeline_layout_create_info = vk::PipelineLayoutCreateInfo::builder()
+		.set_layouts(set_layouts);
+	let pipeline_layout = device.create_pipeline_layout(&pipeline_layout_create_info, None)?;
+
+	let gfx_pipeline_create_info = vk::GraphicsPipelineCreateInfo::builder()
+		.stages(&stages)
+		.vertex_input_state(&vertex_input_state_create_info)
+		.input_assembly_state(&input_assembly_state)
+		.viewport_state(&viewport_state)
+		.rasterization_state(&rasterization_state)
+		.multisample_state(&multisample_state)
+		.depth_stencil_state(&depth_stencil_state)
+		.color_blend_state(&color_blend_state)
+		.dynamic_state(&dynamic_states)
+		.layout(pipeline_layout)
+		.render_pass(render_pass);
+
+	let pipelines = device
+		.create_graphics_pipelines(pipeline_cache, slice::from_ref(&gfx_pipeline_create_info), None)
+		.map_err(|(_, e)| e)?;
+
+	device.destroy_shader_module(fs_shader, None);
+	device.destroy_shader_module(vs_shader, None);
+
+	let pipeline = Pipeline {
+		handle: pipelines[0],
+		layout: pipeline_layout,
+	};
+
+	Ok(pipeline)
+}
+
+/// Single color attachment render pass, the layout transitions
+/// are done by explicit barriers around it.
+pub(crate) unsafe fn create_render_pass(device: &ash::Device, format: vk::Format) -> Result<vk::RenderPass> {
+	let attachment = vk::AttachmentDescription::builder()
+		.format(format)
+		.samples(vk::SampleCountFlags::TYPE_1)
+		.load_op(vk::AttachmentLoadOp::CLEAR)
+		.store_op(vk::AttachmentStoreOp::STORE)
+		.stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+		.stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+		.initial_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+		.final_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
+
+	let attachment_ref = vk::AttachmentReference::builder()
+		.attachment(0)
+		.layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
+	let subpass = vk::SubpassDescription::builder()
+		.pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
+		.color_attachments(slice::from_ref(&attachment_ref));
+
+	let render_pass_create_info = vk::RenderPassCreateInfo::builder()
+		.subpasses(slice::from_ref(&subpass))
+		.attachments(slice::from_ref(&attachment));
+	let render_pass = device.create_render_pass(&render_pass_create_info, None)?;
+
+	Ok(render_pass)
+}
+
+pub(crate) unsafe fn create_image_view(device: &ash::Device, image: vk::Image, format: vk::Format) -> Result<vk::ImageView> {
+	let image_view_create_info = vk::ImageViewCreateInfo::builder()
+		.view_type(vk::ImageViewType::TYPE_2D)
+		.format(format)
+		.components(vk::ComponentMapping {
+			r: vk::ComponentSwizzle::R,
+			g: vk::ComponentSwizzle::G,
+			b: vk::ComponentSwizzle::B,
+			a: vk::ComponentSwizzle::A,
+		})
+		.subresource_range(vk::ImageSubresourceRange {
+			aspect_mask: vk::ImageAspectFlags::COLOR,
+			base_mip_level: 0,
+			level_count: 1,
+			base_array_layer: 0,
+			layer_count: 1,
+		})
+		.image(image);
+	let image_view = device.create_image_view(&image_view_create_info, None)?;
+
+	Ok(image_view)
+}
+
+pub(crate) unsafe fn create_framebuffer(
+	device: &ash::Device,
+	render_pass: vk::RenderPass,
+	image_view: vk::ImageView,
+	extent: vk::Extent2D,
+) -> Result<vk::Framebuffer> {
+	let framebuffer_create_info = vk::FramebufferCreateInfo::builder()
+		.render_pass(render_pass)
+		.attachments(slice::from_ref(&image_view))
+		.width(extent.width)
+		.height(extent.height)
+		.layers(1);
+	let framebuffer = device.create_framebuffer(&framebuffer_create_info, None)?;
+
+	Ok(framebuffer)
+}
+
+pub(crate) unsafe fn create_command_buffer(
+	device: &ash::Device,
+	queue_family: u32,
+) -> Result<(vk::CommandPool, vk::CommandBuffer)> {
+	let pool_create_info = vk::CommandPoolCreateInfo::builder()
+		.queue_family_index(queue_family)
+		.flags(vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER);
+	let command_pool = device.create_command_pool(&pool_create_info, None)?;
+
+	let cmd_buffer_alloc_info = vk::CommandBufferAllocateInfo::builder()
+		.command_buffer_count(1)
+		.command_pool(command_pool)
+		.level(vk::CommandBufferLevel::PRIMARY);
+
+	let cmd_buffers = device.allocate_command_buffers(&cmd_buffer_alloc_info)?;
+	assert!(!cmd_buffers.is_empty());
+
+	Ok((command_pool, cmd_buffers[0]))
+}
+
+/// Picks the last memory type fitting both `type_bits` and `flags`.
+pub(crate) fn find_memory_type(
+	mem_props: &vk::PhysicalDeviceMemoryProperties,
+	type_bits: u32,
+	flags: vk::MemoryPropertyFlags,
+) -> Option<u32> {
+	let mut mem_index = None;
+	for i in 0..mem_props.memory_type_count {
+		let mem_type_is_fine   = type_bits & (1 << i) != 0;
+		let mem_type_flags     = mem_props.memory_types[i as usize].property_flags;
+		let mem_flags_are_fine = mem_type_flags.contains(flags);
+		if mem_type_is_fine && mem_flags_are_fine {
+			mem_index = Some(i);
+		}
+	}
+	mem_index
+}
+
+/// Host-visible prima & index buffers, split into `frames` equal
+/// regions, each with its own descriptor set.
+pub(crate) struct PrimaBuffers {
+	pub(crate) descriptor_set_layout: vk::DescriptorSetLayout,
+
+	frames:          u32,
+	descriptor_pool: vk::DescriptorPool,
+	descriptor_sets: Vec<vk::DescriptorSet>,
+	pbuffer:         vk::Buffer,
+	ibuffer:         vk::Buffer,
+	buffer_mem:      vk::DeviceMemory,
+	buffer_data:     *mut u8,
+}
+
+impl PrimaBuffers {
+	pub(crate) unsafe fn new(
+		instance: &ash::Instance,
+		physical_device: vk::PhysicalDevice,
+		device: &ash::Device,
+		frames: u32,
+	) -> Result<Self> {
+		let descriptor_set_layout_binding = vk::DescriptorSetLayoutBinding::builder()
+			.binding(0)
+			.descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+			.descriptor_count(1)
+			.stage_flags(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT);
+
+		let descriptor_set_layout_create_info = vk::DescriptorSetLayoutCreateInfo::builder()
+			.bindings(slice::from_ref(&descriptor_set_layout_binding));
+		let descriptor_set_layout = device.create_descriptor_set_layout(&descriptor_set_layout_create_info, None)?;
+
+		let descriptor_sizes = [
+			vk::DescriptorPoolSize {
+				ty: vk::DescriptorType::STORAGE_BUFFER,
+				descriptor_count: frames,
+			},
+		];
+		let descriptor_pool_create_info = vk::DescriptorPoolCreateInfo::builder()
+			.max_sets(frames)
+			.pool_sizes(&descriptor_sizes);
+		let descriptor_pool = device.create_descriptor_pool(&descriptor_pool_create_info, None)?;
+
+		let set_layouts = vec![descriptor_set_layout; frames as usize];
+		let descriptor_set_alloc_info = vk::DescriptorSetAllocateInfo::builder()
+			.descriptor_pool(descriptor_pool)
+			.set_layouts(&set_layouts);
+		let descriptor_sets = device.allocate_descriptor_sets(&descriptor_set_alloc_info)?;
+
+		let pbuffer_create_info = vk::BufferCreateInfo::builder()
+			.size(PRIMA_SIZE_PER_FRAME * frames as u64)
+			.usage(vk::BufferUsageFlags::STORAGE_BUFFER)
+			.sharing_mode(vk::SharingMode::EXCLUSIVE);
+		let pbuffer = device.create_buffer(&pbuffer_create_info, None)?;
+
+		let ibuffer_create_info = vk::BufferCreateInfo::builder()
+			.size(PRIMA_SIZE_PER_FRAME * frames as u64)
+			.usage(vk::BufferUsageFlags::INDEX_BUFFER)
+			.sharing_mode(vk::SharingMode::EXCLUSIVE);
+		let ibuffer = device.create_buffer(&ibuffer_create_info, None)?;
+		// @Incomplete Need to check the requirements, as well.
+		// Sub-allocate, etc.
+
+		let mem_req       = device.get_buffer_memory_requirements(pbuffer);
+		let mem_props     = instance.get_physical_device_memory_properties(physical_device);
+		let host_coherent = vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT;
+		let Some(mem_index) = find_memory_type(&mem_props, mem_req.memory_type_bits, host_coherent) else {
+			return Err("Failed to find a suitable SSBO memory.".into());
+		};
+
+		let total_memory   = pbuffer_create_info.size + ibuffer_create_info.size;
+		let mem_alloc_info = vk::MemoryAllocateInfo::builder()
+			.allocation_size(total_memory)
+			.memory_type_index(mem_index);
+		let buffer_mem = device.allocate_memory(&mem_alloc_info, None)?;
+
+		device.bind_buffer_memory(pbuffer, buffer_mem, 0)?;
+		device.bind_buffer_memory(ibuffer, buffer_mem, pbuffer_create_info.size)?;
+
+		let buffer_data = device.map_memory(buffer_mem, 0, total_memory, vk::MemoryMapFlags::default())?;
+
+		for (i, set) in descriptor_sets.iter().enumerate() {
+			let buffer_info = vk::DescriptorBufferInfo::builder()
+				.buffer(pbuffer)
+				.offset(i as u64 * PRIMA_SIZE_PER_FRAME)
+				.range(PRIMA_SIZE_PER_FRAME);
+			let descriptor_write = vk::WriteDescriptorSet::builder()
+				.dst_set(*set)
+				.dst_binding(0)
+				.dst_array_element(0)
+				.descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+				.buffer_info(slice::from_ref(&buffer_info));
+			device.update_descriptor_sets(slice::from_ref(&descriptor_write), &[]);
+		}
+
+		Ok(Self {
+			descriptor_set_layout,
+			frames,
+			descriptor_pool,
+			descriptor_sets,
+			pbuffer,
+			ibuffer,
+			buffer_mem,
+			buffer_data: buffer_data as *mut u8,
+		})
+	}
+
+	/// Mapped prima & index regions of the `i`-th frame.
+	pub(crate) fn frame(&mut self, i: usize) -> (&mut [u8], &mut [u8]) {
+		assert!(i < self.frames as usize);
+
+		let size  = PRIMA_SIZE_PER_FRAME as usize;
+		// Prima buffers of all frames go first, index buffers follow.
+		let total = size * self.frames as usize;
+		unsafe {
+			let pbuf = slice::from_raw_parts_mut(self.buffer_data.add(i * size), size);
+			let ibuf = slice::from_raw_parts_mut(self.buffer_data.add(total + i * size), size);
+			(pbuf, ibuf)
+		}
+	}
+
+	/// Records the draw of `indices` from the `i`-th frame region.
+	#[allow(clippy::too_many_arguments)]
+	pub(crate) unsafe fn cmd_draw(
+		&self,
+		device: &ash::Device,
+		cmd_buffer: vk::CommandBuffer,
+		pipeline: &Pipeline,
+		textures: &Textures,
+		extent: vk::Extent2D,
+		i: usize,
+		indices: u32,
+	) {
+		let descriptor_sets = [self.descriptor_sets[i], textures.descriptor_set];
+		device.cmd_bind_descriptor_sets(
+			cmd_buffer,
+			vk::PipelineBindPoint::GRAPHICS,
+			pipeline.layout,
+			0,
+			&descriptor_sets,
+			&[]);
+		let ibuffer_offset = i as u64 * PRIMA_SIZE_PER_FRAME;
+		device.cmd_bind_index_buffer(cmd_buffer, self.ibuffer, ibuffer_offset, vk::IndexType::UINT32);
+
+		let viewport = vk::Viewport {
+			x: 0.0,
+			y: 0.0,
+			width:  extent.width  as f32,
+			height: extent.height as f32,
+			min_depth: 0.0,
+			max_depth: 0.1,
+		};
+		let scissor = vk::Rect2D {
+			offset: vk::Offset2D::default(),
+			extent,
+		};
+		device.cmd_set_viewport(cmd_buffer, 0, slice::from_ref(&viewport));
+		device.cmd_set_scissor(cmd_buffer, 0, slice::from_ref(&scissor));
+		device.cmd_bind_pipeline(cmd_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline.handle);
+		device.cmd_draw_indexed(cmd_buffer, indices, 1, 0, 0, 0);
+	}
+
+	pub(crate) unsafe fn destroy(&mut self, device: &ash::Device) {
+		device.destroy_buffer(self.ibuffer, None);
+		device.destroy_buffer(self.pbuffer, None);
+		device.destroy_descriptor_pool(self.descriptor_pool, None);
+		device.unmap_memory(self.buffer_mem);
+		device.free_memory(self.buffer_mem, None);
+		device.destroy_descriptor_set_layout(self.descriptor_set_layout, None);
+	}
+}
+
+/// Sampled RGBA8 textures, all of them are bound at once as an
+/// array, so images of different ones still go into a single draw.
+pub(crate) struct Textures {
+	pub(crate) descriptor_set_layout: vk::DescriptorSetLayout,
+	pub(crate) descriptor_set:        vk::DescriptorSet,
+
+	mem_props:       vk::PhysicalDeviceMemoryProperties,
+	queue:           vk::Queue,
+	command_pool:    vk::CommandPool,
+	cmd_buffer:      vk::CommandBuffer,
+	fence:           vk::Fence,
+	descriptor_pool: vk::DescriptorPool,
+	sampler:         vk::Sampler,
+	// Bound to the unused slots.
+	white:           Texture,
+	textures:        Vec<Texture>,
+	// Per frame slot.
+	staging:         Vec<Staging>,
+}
+
+struct Texture {
+	image:  vk::Image,
+	memory: vk::DeviceMemory,
+	view:   vk::ImageView,
+	width:  u32,
+	height: u32,
+}
+
+impl Texture {
+	unsafe fn destroy(&self, device: &ash::Device) {
+		device.destroy_image_view(self.view, None);
+		device.destroy_image(self.image, None);
+		device.free_memory(self.memory, None);
+	}
+}
+
+/// Texture slot, the rect of it & where the texels come from.
+type StagedCopy = (usize, [u32; 4], vk::BufferImageCopy);
+
+/// Persistently mapped buffer the texture updates of a frame slot go
+/// through, grown on demand.
+struct Staging {
+	buffer: vk::Buffer,
+	memory: vk::DeviceMemory,
+	data:   *mut u8,
+	size:   u64,
+	used:   u64,
+	// In staging order.
+	copies: Vec<StagedCopy>,
+}
+
+impl Staging {
+	fn empty() -> Self {
+		Self {
+			buffer: vk::Buffer::null(),
+			memory: vk::DeviceMemory::null(),
+			data:   std::ptr::null_mut(),
+			size:   0,
+			used:   0,
+			copies: Vec::new(),
+		}
+	}
+
+	/// Mapped staging buffer of `size` bytes.
+	unsafe fn new(device: &ash::Device, mem_props: &vk::PhysicalDeviceMemoryProperties, size: u64) -> Result<Self> {
+		let mut staging = Self { size, ..Self::empty() };
+		// Whatever got created so far goes on failure.
+		if let Err(e) = staging.create(device, mem_props) {
+			staging.destroy(device);
+			return Err(e);
+		}
+		Ok(staging)
+	}
+
+	unsafe fn create(&mut self, device: &ash::Device, mem_props: &vk::PhysicalDeviceMemoryProperties) -> Result<()> {
+		let buffer_create_info = vk::BufferCreateInfo::builder()
+			.size(self.size)
+			.usage(vk::BufferUsageFlags::TRANSFER_SRC)
+			.sharing_mode(vk::SharingMode::EXCLUSIVE);
+		self.buffer = device.create_buffer(&buffer_create_info, None)?;
+
+		let mem_req       = device.get_buffer_memory_requirements(self.buffer);
+		let host_coherent = vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT;
+		let Some(mem_index) = find_memory_type(mem_props, mem_req.memory_type_bits, host_coherent) else {
+			return Err("Failed to find a suitable staging memory.".into());
+		};
+		let mem_alloc_info = vk::MemoryAllocateInfo::builder()
+			.allocation_size(mem_req.size)
+			.memory_type_index(mem_index);
+		self.memory = device.allocate_memory(&mem_alloc_info, None)?;
+		device.bind_buffer_memory(self.buffer, self.memory, 0)?;
+		self.data = device.map_memory(self.memory, 0, self.size, vk::MemoryMapFlags::default())? as *mut u8;
+
+		Ok(())
+	}
+
+	// Null handles are fine to destroy, a half created one goes, too.
+	unsafe fn destroy(&self, device: &ash::Device) {
+		device.destroy_buffer(self.buffer, None);
+		if !self.data.is_null() {
+			device.unmap_memory(self.memory);
+		}
+		device.free_memory(self.memory, None);
+	}
+}
+
+impl Textures {
+	pub(crate) unsafe fn new(
+		instance: &ash::Instance,
+		physical_device: vk::PhysicalDevice,
+		device: &ash::Device,
+		queue: vk::Queue,
+		queue_family: u32,
+		frames: u32,
+	) -> Result<Self> {
+		let limits = instance.get_physical_device_properties(physical_device).limits;
+		let slots  = limits.max_per_stage_descriptor_samplers.min(limits.max_per_stage_descriptor_sampled_images);
+		if (slots as usize) < MAX_TEXTURES {
+			return Err(format!("Physical device can't bind {MAX_TEXTURES} textures, only {slots}.").into());
+		}
+
+		let descriptor_set_layout_binding = vk::DescriptorSetLayoutBinding::builder()
+			.binding(0)
+			.descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+			.descriptor_count(MAX_TEXTURES as u32)
+			.stage_flags(vk::ShaderStageFlags::FRAGMENT);
+
+		let descriptor_set_layout_create_info = vk::DescriptorSetLayoutCreateInfo::builder()
+			.bindings(slice::from_ref(&descriptor_set_layout_binding));
+		let descriptor_set_layout = device.create_descriptor_set_layout(&descriptor_set_layout_create_info, None)?;
+
+		let descriptor_sizes = [
+			vk::DescriptorPoolSize {
+				ty: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+				descriptor_count: MAX_TEXTURES as u32,
+			},
+		];
+		let descriptor_pool_create_info = vk::DescriptorPoolCreateInfo::builder()
+			.max_sets(1)
+			.pool_sizes(&descriptor_sizes);
+		let descriptor_pool = device.create_descriptor_pool(&descriptor_pool_create_info, None)?;
+
+		let descriptor_set_alloc_info = vk::DescriptorSetAllocateInfo::builder()
+			.descriptor_pool(descriptor_pool)
+			.set_layouts(slice::from_ref(&descriptor_set_layout));
+		let descriptor_set = device.allocate_descriptor_sets(&descriptor_set_alloc_info)?[0];
+
+		// Mirrored by the software rasterizer.
+		let sampler_create_info = vk::SamplerCreateInfo::builder()
+			.mag_filter(vk::Filter::LINEAR)
+			.min_filter(vk::Filter::LINEAR)
+			.mipmap_mode(vk::SamplerMipmapMode::NEAREST)
+			.address_mode_u(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+			.address_mode_v(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+			.address_mode_w(vk::SamplerAddressMode::CLAMP_TO_EDGE);
+		let sampler = device.create_sampler(&sampler_create_info, None)?;
+
+		let (command_pool, cmd_buffer) = create_command_buffer(device, queue_family)?;
+
+		let fence = device.create_fence(&vk::FenceCreateInfo::default(), None)?;
+
+		let mut textures = Self {
+			descriptor_set_layout,
+			descriptor_set,
+			mem_props: instance.get_physical_device_memory_properties(physical_device),
+			queue,
+			command_pool,
+			cmd_buffer,
+			fence,
+			descriptor_pool,
+			sampler,
+			white: Texture {
+				image:  vk::Image::null(),
+				memory: vk::DeviceMemory::null(),
+				view:   vk::ImageView::null(),
+				width:  0,
+				height: 0,
+			},
+			textures: Vec::new(),
+			staging:  (0..frames).map(|_| Staging::empty()).collect(),
+		};
+
+		textures.white = textures.create_texture(device, 1, 1, &[255; 4])?;
+		for slot in 0..MAX_TEXTURES {
+			textures.bind(device, slot, textures.white.view);
+		}
+
+		Ok(textures)
+	}
+
+	/// Uploads tightly packed RGBA8 `pixels` into the next free slot &
+	/// waits for it to land.
+	///
+	/// The descriptor set is updated in place, so nothing using it may
+	/// be in flight.
+	pub(crate) unsafe fn upload(
+		&mut self,
+		device: &ash::Device,
+		width: u32,
+		height: u32,
+		pixels: &[u8],
+	) -> Result<TextureId> {
+		let id      = texture::next_id(self.textures.len(), width, height, pixels)?;
+		let texture = self.create_texture(device, width, height, pixels)?;
+		self.bind(device, id.0 as usize, texture.view);
+		self.textures.push(texture);
+		Ok(id)
+	}
+
+	/// Stages `updates` of the `id` texture for the `frame`-th slot, all
+	/// of them or none. They land once [`Self::cmd_updates`] is recorded
+	/// into the slot.
+	///
+	/// The staging buffer of the slot is written in place, so nothing
+	/// using it may be in flight.
+	pub(crate) unsafe fn stage(
+		&mut self,
+		device: &ash::Device,
+		frame: usize,
+		id: TextureId,
+		updates: &[TextureUpdate],
+	) -> Result<()> {
+		let Some(texture) = self.textures.get(id.0 as usize) else {
+			return Err(format!("There is no texture {}.", id.0).into());
+		};
+		for update in updates {
+			texture::check_update((texture.width, texture.height), update)?;
+		}
+
+		let bytes: u64 = updates.iter().map(|update| update.pixels.len() as u64).sum();
+		let needed     = self.staging[frame].used + bytes;
+		if needed > self.staging[frame].size {
+			self.grow_staging(device, frame, needed)?;
+		}
+
+		let staging = &mut self.staging[frame];
+		for TextureUpdate { rect, pixels } in updates {
+			let [x, y, width, height] = *rect;
+			slice::from_raw_parts_mut(staging.data.add(staging.used as usize), pixels.len()).copy_from_slice(pixels);
+
+			let region = vk::BufferImageCopy::builder()
+				.buffer_offset(staging.used)
+				.image_subresource(vk::ImageSubresourceLayers {
+					aspect_mask: vk::ImageAspectFlags::COLOR,
+					mip_level: 0,
+					base_array_layer: 0,
+					layer_count: 1,
+				})
+				.image_offset(vk::Offset3D { x: x as i32, y: y as i32, z: 0 })
+				.image_extent(vk::Extent3D { width, height, depth: 1 });
+			staging.copies.push((id.0 as usize, *rect, *region));
+			staging.used += pixels.len() as u64;
+		}
+		Ok(())
+	}
+
+	/// Replaces the staging buffer of the `frame`-th slot with one of at
+	/// least `needed` bytes, keeping what's staged so far.
+	unsafe fn grow_staging(&mut self, device: &ash::Device, frame: usize, needed: u64) -> Result<()> {
+		// Doubling, so a few more glyphs don't reallocate every frame.
+		let size = needed.max(self.staging[frame].size * 2);
+
+		let mut grown = Staging::new(device, &self.mem_props, size)?;
+		let old = &mut self.staging[frame];
+		if old.used > 0 {
+			std::ptr::copy_nonoverlapping(old.data, grown.data, old.used as usize);
+		}
+		grown.used   = old.used;
+		grown.copies = std::mem::take(&mut old.copies);
+		old.destroy(device);
+		*old = grown;
+		Ok(())
+	}
+
+	/// Records the copies staged for the `frame`-th slot, a copy per
+	/// texture unless the regions overlap. Goes before the render pass
+	/// sampling the textures.
+	pub(crate) unsafe fn cmd_updates(&mut self, device: &ash::Device, cmd_buffer: vk::CommandBuffer, frame: usize) {
+		let staging = &mut self.staging[frame];
+		if staging.copies.is_empty() {
+			return;
+		}
+
+		let mut slots: Vec<usize> = staging.copies.iter().map(|(slot, ..)| *slot).collect();
+		slots.sort_unstable();
+		slots.dedup();
+
+		let copy_begin_barriers: Vec<_> = slots.iter()
+			.map(|slot| image_barrier(
+				&self.textures[*slot].image,
+				vk::AccessFlags::SHADER_READ,
+				vk::AccessFlags::TRANSFER_WRITE,
+				vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+				vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+			))
+			.collect();
+		device.cmd_pipeline_barrier(
+			cmd_buffer,
+			vk::PipelineStageFlags::FRAGMENT_SHADER,
+			vk::PipelineStageFlags::TRANSFER,
+			vk::DependencyFlags::empty(),
+			&[],
+			&[],
+			&copy_begin_barriers,
+		);
+
+		// The regions of a single copy aren't ordered, so the one
+		// overlapping an earlier region goes into the next copy, after
+		// the earlier ones are done.
+		let mut batch = Vec::new();
+		for &copy in &staging.copies {
+			let (slot, rect, _) = copy;
+			if batch.iter().any(|&(s, r, _)| s == slot && texture::overlaps(r, rect)) {
+				cmd_copy_batch(device, cmd_buffer, staging.buffer, &self.textures, &mut batch);
+
+				let copy_barrier = vk::MemoryBarrier::builder()
+					.src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+					.dst_access_mask(vk::AccessFlags::TRANSFER_WRITE);
+				device.cmd_pipeline_barrier(
+					cmd_buffer,
+					vk::PipelineStageFlags::TRANSFER,
+					vk::PipelineStageFlags::TRANSFER,
+					vk::DependencyFlags::empty(),
+					slice::from_ref(&copy_barrier),
+					&[],
+					&[],
+				);
+			}
+			batch.push(copy);
+		}
+		cmd_copy_batch(device, cmd_buffer, staging.buffer, &self.textures, &mut batch);
+
+		let copy_end_barriers: Vec<_> = slots.iter()
+			.map(|slot| image_barrier(
+				&self.textures[*slot].image,
+				vk::AccessFlags::TRANSFER_WRITE,
+				vk::AccessFlags::SHADER_READ,
+				vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+				vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+			))
+			.collect();
+		device.cmd_pipeline_barrier(
+			cmd_buffer,
+			vk::PipelineStageFlags::TRANSFER,
+			vk::PipelineStageFlags::FRAGMENT_SHADER,
+			vk::DependencyFlags::empty(),
+			&[],
+			&[],
+			&copy_end_barriers,
+		);
+
+		// The buffer is reused once the slot's fence says this frame is done.
+		staging.copies.clear();
+		staging.used = 0;
+	}
+
+	unsafe fn bind(&self, device: &ash::Device, slot: usize, view: vk::ImageView) {
+		let image_info = vk::DescriptorImageInfo::builder()
+			.sampler(self.sampler)
+			.image_view(view)
+			.image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL);
+		let descriptor_write = vk::WriteDescriptorSet::builder()
+			.dst_set(self.descriptor_set)
+			.dst_binding(0)
+			.dst_array_element(slot as u32)
+			.descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+			.image_info(slice::from_ref(&image_info));
+		device.update_descriptor_sets(slice::from_ref(&descriptor_write), &[]);
+	}
+
+	unsafe fn create_texture(&self, device: &ash::Device, width: u32, height: u32, pixels: &[u8]) -> Result<Texture> {
+		let mut texture = Texture {
+			image:  vk::Image::null(),
+			memory: vk::DeviceMemory::null(),
+			view:   vk::ImageView::null(),
+			width,
+			height,
+		};
+		// Null handles are fine to destroy, so whatever got created so
+		// far goes on failure.
+		if let Err(e) = self.fill_texture(device, &mut texture, pixels) {
+			texture.destroy(device);
+			return Err(e);
+		}
+		Ok(texture)
+	}
+
+	unsafe fn fill_texture(&self, device: &ash::Device, texture: &mut Texture, pixels: &[u8]) -> Result<()> {
+		let format = vk::Format::R8G8B8A8_UNORM;
+		let (width, height) = (texture.width, texture.height);
+
+		let image_create_info = vk::ImageCreateInfo::builder()
+			.image_type(vk::ImageType::TYPE_2D)
+			.format(format)
+			.extent(vk::Extent3D { width, height, depth: 1 })
+			.mip_levels(1)
+			.array_layers(1)
+			.samples(vk::SampleCountFlags::TYPE_1)
+			.tiling(vk::ImageTiling::OPTIMAL)
+			.usage(vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST)
+			.sharing_mode(vk::SharingMode::EXCLUSIVE)
+			.initial_layout(vk::ImageLayout::UNDEFINED);
+		texture.image = device.create_image(&image_create_info, None)?;
+		let image     = texture.image;
+
+		let mem_req = device.get_image_memory_requirements(image);
+		let Some(mem_index) = find_memory_type(&self.mem_props, mem_req.memory_type_bits, vk::MemoryPropertyFlags::DEVICE_LOCAL)
+			.or_else(|| find_memory_type(&self.mem_props, mem_req.memory_type_bits, vk::MemoryPropertyFlags::empty()))
+		else {
+			return Err("Failed to find a suitable texture memory.".into());
+		};
+		let mem_alloc_info = vk::MemoryAllocateInfo::builder()
+			.allocation_size(mem_req.size)
+			.memory_type_index(mem_index);
+		texture.memory = device.allocate_memory(&mem_alloc_info, None)?;
+		device.bind_image_memory(image, texture.memory, 0)?;
+
+		// @Speed A staging buffer per upload, fine while textures are
+		// uploaded once.
+		let staging = Staging::new(device, &self.mem_props, pixels.len() as u64)?;
+		slice::from_raw_parts_mut(staging.data, pixels.len()).copy_from_slice(pixels);
+		let copied = self.copy_staging(device, &staging, image, width, height);
+		staging.destroy(device);
+		copied?;
+
+		texture.view = create_image_view(device, image, format)?;
+
+		Ok(())
+	}
+
+	/// Copies all of `staging` into the whole fresh `image` & waits for
+	/// it to land.
+	unsafe fn copy_staging(&self, device: &ash::Device, staging: &Staging, image: vk::Image, width: u32, height: u32) -> Result<()> {
+		let cmd_buffer = self.cmd_buffer;
+
+		device.reset_command_pool(self.command_pool, vk::CommandPoolResetFlags::empty())?;
+
+		let cmd_buffer_begin_info = vk::CommandBufferBeginInfo::builder()
+			.flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+		device.begin_command_buffer(cmd_buffer, &cmd_buffer_begin_info)?;
+
+		let copy_begin_barrier = image_barrier(
+			&image,
+			vk::AccessFlags::empty(),
+			vk::AccessFlags::TRANSFER_WRITE,
+			vk::ImageLayout::UNDEFINED,
+			vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+		);
+		device.cmd_pipeline_barrier(
+			cmd_buffer,
+			vk::PipelineStageFlags::TOP_OF_PIPE,
+			vk::PipelineStageFlags::TRANSFER,
+			vk::DependencyFlags::empty(),
+			&[],
+			&[],
+			slice::from_ref(&copy_begin_barrier),
+		);
+
+		let region = vk::BufferImageCopy::builder()
+			.image_subresource(vk::ImageSubresourceLayers {
+				aspect_mask: vk::ImageAspectFlags::COLOR,
+				mip_level: 0,
+				base_array_layer: 0,
+				layer_count: 1,
+			})
+			.image_extent(vk::Extent3D { width, height, depth: 1 });
+		device.cmd_copy_buffer_to_image(
+			cmd_buffer,
+			staging.buffer,
+			image,
+			vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+			slice::from_ref(&region),
+		);
+
+		let copy_end_barrier = image_barrier(
+			&image,
+			vk::AccessFlags::TRANSFER_WRITE,
+			vk::AccessFlags::SHADER_READ,
+			vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+			vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+		);
+		device.cmd_pipeline_barrier(
+			cmd_buffer,
+			vk::PipelineStageFlags::TRANSFER,
+			vk::PipelineStageFlags::FRAGMENT_SHADER,
+			vk::DependencyFlags::empty(),
+			&[],
+			&[],
+			slice::from_ref(&copy_end_barrier),
+		);
+
+		device.end_command_buffer(cmd_buffer)?;
+
+		let submit_info = vk::SubmitInfo::builder()
+			.command_buffers(slice::from_ref(&cmd_buffer));
+		device.reset_fences(slice::from_ref(&self.fence))?;
+		device.queue_submit(self.queue, slice::from_ref(&*submit_info), self.fence)?;
+		device.wait_for_fences(slice::from_ref(&self.fence), true, u64::MAX)?;
+
+		Ok(())
+	}
+
+	pub(crate) unsafe fn destroy(&mut self, device: &ash::Device) {
+		for texture in self.textures.drain(..) {
+			texture.destroy(device);
+		}
+		for staging in self.staging.drain(..) {
+			staging.destroy(device);
+		}
+		self.white.destroy(device);
+		device.destroy_sampler(self.sampler, None);
+		device.destroy_descriptor_pool(self.descriptor_pool, None);
+		device.destroy_fence(self.fence, None);
+		device.destroy_command_pool(self.command_pool, None);
+		device.destroy_descriptor_set_layout(self.descriptor_set_layout, None);
+	}
+}
+
+/// Records a copy from `buffer` per texture in `batch` & empties it.
+unsafe fn cmd_copy_batch(
+	device: &ash::Device,
+	cmd_buffer: vk::CommandBuffer,
+	buffer: vk::Buffer,
+	textures: &[Texture],
+	batch: &mut Vec<StagedCopy>,
+) {
+	batch.sort_by_key(|(slot, ..)| *slot);
+	for copies in batch.chunk_by(|a, b| a.0 == b.0) {
+		let regions: Vec<_> = copies.iter().map(|(.., region)| *region).collect();
+		device.cmd_copy_buffer_to_image(
+			cmd_buffer,
+			buffer,
+			textures[copies[0].0].image,
+			vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+			&regions,
+		);
+	}
+	batch.clear();
+}
+
+/// Clear values for the prima render pass.
+pub(crate) fn clear_value() -> vk::ClearValue {
+	let mut clear_color = vk::ClearColorValue::default();
+	clear_color.float32 = CLEAR_COLOR;
+
+	let mut clear_value = vk::ClearValue::default();
+	clear_value.color   = clear_color;
+
+	clear_value
+}
+
+pub(crate) unsafe fn image_barrier(
+	image: &vk::Image,
+	src_access_mask: vk::AccessFlags,
+	dst_access_mask: vk::AccessFlags,
+	old_layout: vk::ImageLayout,
+	new_layout: vk::ImageLayout,
+) -> vk::ImageMemoryBarrier {
+	let range = vk::ImageSubresourceRange::builder()
+		.aspect_mask(vk::ImageAspectFlags::COLOR)
+		.level_count(vk::REMAINING_MIP_LEVELS)
+		.layer_count(vk::REMAINING_ARRAY_LAYERS); // Afair, those are not fully supported on Android.
+
+	let barrier = vk::ImageMemoryBarrier::builder()
+		.src_access_mask(src_access_mask)
+		.dst_access_mask(dst_access_mask)
+		.old_layout(old_layout)
+		.new_layout(new_layout)
+		.src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+		.dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+		.image(*image)
+		.subresource_range(*range);
+
+	*barrier
+}
+
+// Not installed until it's nice enough, see `Renderer::new`.
+#[allow(dead_code)]
+pub(crate) unsafe extern "system" fn vulkan_debug_message_callback(
+	message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+	message_types: vk::DebugUtilsMessageTypeFlagsEXT,
+	p_callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT,
+	_p_user_data: *mut c_void,
+) -> vk::Bool32 {
+	eprintln!("{} [{:?}] Validation issue:\n{:#?}", message_severity.as_raw(), message_types, *p_callback_data);
+	vk::FALSE
+}
+
+/// Shaders are embedded as bytes, which are not guaranteed to be
+/// aligned to 4.
+fn read_spv(bytes: &[u8]) -> Vec<u32> {
+	assert!(bytes.len().is_multiple_of(4));
+
+	let spv: Vec<u32> = bytes
+		.chunks_exact(4)
+		.map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]]))
+		.collect();
+
+	assert!(spv[0] == 0x0723_0203);
+
+	spv
+}
+
+pub(crate) unsafe fn str_from_null_terminated_bytes(bytes: &[i8]) -> &str {
+	let n = bytes.iter().position(|&c| c == 0).unwrap_or(bytes.len());
+	let s = slice::from_raw_parts(bytes.as_ptr() as *const u8, n);
+	str::from_utf8_unchecked(s)
+}
@@ -0,0 +1,682 @@
+//! Golden image tests.
+//!
+//! Every scene is rendered by the CPU rasterizer and, if Vulkan is
+//! around (lavapipe is just fine), by the headless renderer. Both
+//! have to match the reference in `tests/golden` within the
+//! tolerance. On a mismatch the actual & diff images are written
+//! next to the test binaries, see the panic message for the path.
+//!
+//! Run with `PRIMA_BLESS=1` to (re)generate the references from the
+//! CPU rasterizer. As those are blessed from the CPU, CI should run
+//! with `PRIMA_REQUIRE_GPU=1`, so missing Vulkan fails the tests
+//! instead of skipping the GPU.
+
+use std::env;
+use std::fs;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use prima::encoder::{
+	Border,
+	BorderSide,
+	Color,
+	ColorStop,
+	CornerRadii,
+	Gradient,
+	Image,
+	Insets,
+	LineCap,
+	LineJoin,
+	PrimaEncoder,
+	Shadow,
+	Stroke,
+	Vertex,
+};
+use prima::headless::{self, Headless};
+use prima::raster::Software;
+use prima::cache::GlyphCache;
+use prima::font::Face;
+use prima::layout::{Align, Layout};
+use prima::text::{Font, Glyph, GlyphAtlas, GlyphFormat, LineMetrics};
+use prima::texture::TextureId;
+
+const WIDTH:  u32 = 64;
+const HEIGHT: u32 = 64;
+
+/// Per channel, GPUs are allowed to round a bit differently.
+const TOLERANCE: u8 = 2;
+
+const ORANGE: Color = Color::rgb(255, 179, 102);
+
+type Scene = fn(&mut PrimaEncoder) -> prima::Result<()>;
+
+/// Width, height & tightly packed RGBA8 pixels.
+type Texels = (u32, u32, Vec<u8>);
+
+#[test]
+fn empty() {
+	check("empty", |_| Ok(()));
+}
+
+#[test]
+fn rect() {
+	check("rect", |encoder| {
+		encoder.rect(8.0, 16.0, 40.0, 24.0, ORANGE)?;
+		Ok(())
+	});
+}
+
+#[test]
+fn rect_snapping() {
+	check("rect_snapping", |encoder| {
+		encoder.rect(4.4, 4.6, 10.2, 9.7, ORANGE)?;
+		encoder.rect(20.5, 20.5, 10.0, 10.0, ORANGE)?;
+		encoder.rect(40.49, 40.51, 16.0, 16.0, ORANGE)?;
+		Ok(())
+	});
+}
+
+#[test]
+fn rect_clipped() {
+	check("rect_clipped", |encoder| {
+		encoder.rect(-16.0, -16.0, 32.0, 32.0, ORANGE)?;
+		encoder.rect(48.0, 40.0, 64.0, 64.0, ORANGE)?;
+		Ok(())
+	});
+}
+
+#[test]
+fn rect_colors() {
+	check("rect_colors", |encoder| {
+		encoder.rect(4.0, 4.0, 24.0, 24.0, Color::rgb(220, 40, 40))?;
+		encoder.rect(36.0, 4.0, 24.0, 24.0, Color::rgb(40, 220, 40))?;
+		encoder.rect(4.0, 36.0, 24.0, 24.0, Color::rgb(40, 40, 220))?;
+		encoder.rect(36.0, 36.0, 24.0, 24.0, Color::WHITE)?;
+		// Overlapping, the later one wins.
+		encoder.rect(24.0, 24.0, 16.0, 16.0, Color::BLACK)?;
+		Ok(())
+	});
+}
+
+#[test]
+fn rounded_rect() {
+	check("rounded_rect", |encoder| {
+		encoder.rounded_rect(4.0, 4.0, 24.0, 24.0, CornerRadii::all(6.0), Color::WHITE)?;
+		encoder.rounded_rect(36.0, 4.0, 24.0, 24.0, CornerRadii::new(12.0, 0.0, 4.0, 8.0), Color::rgb(220, 40, 40))?;
+		// Radii larger than the rect make a pill.
+		encoder.rounded_rect(4.5, 36.5, 56.0, 10.0, CornerRadii::all(100.0), Color::rgb(40, 40, 220))?;
+		encoder.rounded_rect(8.25, 50.75, 47.5, 9.5, CornerRadii::all(0.0), Color::BLACK)?;
+		Ok(())
+	});
+}
+
+/// Anti-aliased edges blend with whatever is below.
+#[test]
+fn rounded_rect_overlap() {
+	check("rounded_rect_overlap", |encoder| {
+		encoder.rect(16.0, 0.0, 32.0, 64.0, ORANGE)?;
+		encoder.rounded_rect(4.0, 16.0, 56.0, 32.0, CornerRadii::all(16.0), Color::rgba(40, 40, 220, 160))?;
+		Ok(())
+	});
+}
+
+#[test]
+fn ellipse() {
+	check("ellipse", |encoder| {
+		encoder.circle(16.0, 16.0, 12.0, Color::WHITE)?;
+		encoder.circle(44.5, 12.5, 3.3, Color::BLACK)?;
+		encoder.ellipse(40.0, 40.0, 20.0, 8.0, Color::rgb(220, 40, 40))?;
+		encoder.ellipse(16.0, 48.0, 6.0, 14.0, Color::rgba(40, 40, 220, 160))?;
+		Ok(())
+	});
+}
+
+#[test]
+fn arc() {
+	use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+	check("arc", |encoder| {
+		encoder.arc(16.0, 16.0, 11.0, 4.0, 0.0, FRAC_PI_2, Color::WHITE)?;
+		encoder.arc(16.0, 16.0, 11.0, 4.0, PI, -FRAC_PI_2, Color::rgb(220, 40, 40))?;
+		encoder.arc(48.0, 16.0, 10.0, 1.5, -FRAC_PI_2, PI * 1.5, Color::BLACK)?;
+		encoder.arc(16.0, 48.0, 12.0, 6.0, 0.0, TAU, ORANGE)?;
+		encoder.arc(48.0, 48.0, 8.0, 10.0, 0.3, 2.5, Color::rgba(40, 40, 220, 200))?;
+		Ok(())
+	});
+}
+
+#[test]
+fn line() {
+	check("line", |encoder| {
+		let stroke = Stroke::new(6.0, Color::WHITE);
+		encoder.line([12.0, 8.0], [52.0, 8.0], &stroke)?;
+		encoder.line([12.0, 22.0], [52.0, 22.0], &stroke.with_cap(LineCap::Square))?;
+		encoder.line([12.0, 36.0], [52.0, 36.0], &stroke.with_cap(LineCap::Round))?;
+		encoder.line([6.0, 60.0], [58.0, 46.0], &Stroke::new(1.5, ORANGE))?;
+		encoder.line([40.0, 62.0], [56.0, 30.0], &Stroke::new(3.0, Color::rgba(40, 40, 220, 160)).with_cap(LineCap::Round))?;
+		Ok(())
+	});
+}
+
+/// Translucent joins must not be blended twice on the outer side.
+#[test]
+fn polyline() {
+	check("polyline", |encoder| {
+		let zigzag = |y: f32| [[6.0, y + 10.0], [20.0, y], [34.0, y + 10.0], [48.0, y], [58.0, y + 6.0]];
+		let stroke = Stroke::new(5.0, Color::WHITE);
+		encoder.polyline(&zigzag(4.0), &stroke)?;
+		encoder.polyline(&zigzag(22.0), &stroke.with_join(LineJoin::Bevel).with_cap(LineCap::Square))?;
+		encoder.polyline(&zigzag(40.0), &stroke.with_join(LineJoin::Round).with_cap(LineCap::Round))?;
+
+		let translucent = Stroke::new(4.0, Color::rgba(220, 40, 40, 160)).with_join(LineJoin::Round);
+		encoder.polyline(&[[4.0, 60.0], [30.0, 60.0], [30.0, 52.0], [60.0, 60.0]], &translucent)?;
+		Ok(())
+	});
+}
+
+/// Too sharp a turn for a miter falls back to a bevel.
+#[test]
+fn polyline_sharp() {
+	check("polyline_sharp", |encoder| {
+		let stroke = Stroke::new(6.0, ORANGE);
+		encoder.polyline(&[[8.0, 8.0], [56.0, 16.0], [8.0, 24.0]], &stroke)?;
+		encoder.polyline(&[[8.0, 36.0], [32.0, 56.0], [56.0, 36.0]], &stroke)?;
+		Ok(())
+	});
+}
+
+#[test]
+fn bezier() {
+	check("bezier", |encoder| {
+		let wire = Stroke::new(3.0, Color::WHITE).with_cap(LineCap::Round);
+		encoder.cubic_bezier([4.0, 8.0], [40.0, 8.0], [24.0, 40.0], [60.0, 40.0], &wire)?;
+		encoder.cubic_bezier([4.0, 60.0], [80.0, 20.0], [-16.0, 20.0], [60.0, 60.0], &Stroke::new(2.0, ORANGE))?;
+		encoder.quadratic_bezier([4.0, 30.0], [32.0, -10.0], [60.0, 30.0], &Stroke::new(1.0, Color::BLACK))?;
+		Ok(())
+	});
+}
+
+#[test]
+fn gradient() {
+	check("gradient", |encoder| {
+		let sunset = [
+			ColorStop::new(0.0, Color::rgb(40, 40, 220)),
+			ColorStop::new(0.6, ORANGE),
+			ColorStop::new(1.0, Color::rgb(220, 40, 40)),
+		];
+		let linear = encoder.gradient(&Gradient::Linear { start: [4.0, 0.0], end: [28.0, 0.0], stops: &sunset })?;
+		encoder.rect(4.0, 4.0, 24.0, 24.0, linear)?;
+
+		let glow = [
+			ColorStop::new(0.0, Color::WHITE),
+			ColorStop::new(1.0, Color::rgba(255, 255, 255, 0)),
+		];
+		let radial = encoder.gradient(&Gradient::Radial { center: [48.0, 16.0], radius: 14.0, stops: &glow })?;
+		encoder.rounded_rect(36.0, 4.0, 24.0, 24.0, CornerRadii::all(6.0), radial)?;
+
+		let wheel = [
+			ColorStop::new(0.0, Color::rgb(255, 0, 0)),
+			ColorStop::new(1.0 / 3.0, Color::rgb(0, 255, 0)),
+			ColorStop::new(2.0 / 3.0, Color::rgb(0, 0, 255)),
+			ColorStop::new(1.0, Color::rgb(255, 0, 0)),
+		];
+		let conic = encoder.gradient(&Gradient::Conic { center: [16.0, 48.0], angle: 0.0, stops: &wheel })?;
+		encoder.circle(16.0, 48.0, 12.0, conic)?;
+
+		// Shared by the whole path.
+		let path = encoder.gradient(&Gradient::Linear { start: [36.0, 36.0], end: [60.0, 60.0], stops: &sunset })?;
+		let stroke = Stroke::new(4.0, path).with_join(LineJoin::Round).with_cap(LineCap::Round);
+		encoder.polyline(&[[38.0, 40.0], [58.0, 40.0], [38.0, 58.0], [58.0, 58.0]], &stroke)?;
+		Ok(())
+	});
+}
+
+#[test]
+fn border() {
+	check("border", |encoder| {
+		encoder.border(4.0, 4.0, 24.0, 24.0, &Border::all(1.0, Color::WHITE), CornerRadii::all(0.0))?;
+		encoder.border(8.0, 8.0, 16.0, 16.0, &Border::all(3.0, ORANGE), CornerRadii::all(5.0))?;
+
+		let css = Border::new(
+			BorderSide::new(8.0, Color::rgb(220, 40, 40)),
+			BorderSide::new(4.0, Color::rgb(40, 220, 40)),
+			BorderSide::new(2.0, Color::rgb(40, 40, 220)),
+			BorderSide::new(6.0, Color::BLACK),
+		);
+		encoder.border(36.0, 4.0, 24.0, 24.0, &css, CornerRadii::all(0.0))?;
+		encoder.border(4.0, 36.0, 24.0, 24.0, &css, CornerRadii::new(12.0, 4.0, 0.0, 8.0))?;
+
+		// Thicker than the rect, nothing left inside.
+		encoder.border(40.0, 40.0, 16.0, 16.0, &Border::all(10.0, Color::rgba(255, 255, 255, 160)), CornerRadii::all(4.0))?;
+		Ok(())
+	});
+}
+
+#[test]
+fn shadow() {
+	check("shadow", |encoder| {
+		let card = Shadow::new(3.0, Color::rgba(0, 0, 0, 160)).with_offset(0.0, 2.0).with_radius(4.0);
+		encoder.shadow(8.0, 8.0, 20.0, 16.0, &card)?;
+		encoder.rounded_rect(8.0, 8.0, 20.0, 16.0, CornerRadii::all(4.0), Color::WHITE)?;
+
+		let glow = Shadow::new(4.0, ORANGE).with_spread(2.0).with_radius(8.0);
+		encoder.shadow(40.0, 8.0, 16.0, 16.0, &glow)?;
+
+		// No blur, just an offset copy.
+		let hard = Shadow::new(0.0, Color::BLACK).with_offset(3.0, 3.0);
+		encoder.shadow(8.0, 38.0, 16.0, 16.0, &hard)?;
+		encoder.rect(8.0, 38.0, 16.0, 16.0, Color::WHITE)?;
+
+		let inset = Shadow::new(2.0, Color::rgba(40, 40, 220, 255)).with_spread(-4.0).with_offset(4.0, 4.0);
+		encoder.shadow(36.0, 36.0, 24.0, 24.0, &inset)?;
+		Ok(())
+	});
+}
+
+/// 4x4 quadrants of red, green, blue & translucent white.
+fn quadrants() -> Texels {
+	let mut pixels = Vec::new();
+	for y in 0..4 {
+		for x in 0..4 {
+			let texel = match (x < 2, y < 2) {
+				(true,  true)  => [220, 40, 40, 255],
+				(false, true)  => [40, 220, 40, 255],
+				(true,  false) => [40, 40, 220, 255],
+				(false, false) => [255, 255, 255, 96],
+			};
+			pixels.extend_from_slice(&texel);
+		}
+	}
+	(4, 4, pixels)
+}
+
+/// Horizontal black to white ramp, a texel per step.
+fn ramp() -> Texels {
+	let pixels = (0..8).flat_map(|x| [x * 36, x * 36, x * 36, 255]).collect();
+	(8, 1, pixels)
+}
+
+#[test]
+fn image() {
+	check_textured("image", &[quadrants(), ramp()], |encoder, textures| {
+		let quadrants = Image::new(textures[0]);
+		let ramp      = Image::new(textures[1]);
+		encoder.image(4.0, 4.0, 24.0, 24.0, &quadrants, Color::WHITE)?;
+		// Tinted, then only the green quadrant.
+		encoder.image(36.0, 4.0, 24.0, 24.0, &quadrants, ORANGE)?;
+		encoder.image(4.0, 36.0, 12.0, 12.0, &quadrants.with_uv(0.5, 0.0, 0.5, 0.5), Color::WHITE)?;
+		// Images of different textures interleave within the draw.
+		encoder.image(20.0, 36.0, 40.0, 8.0, &ramp, Color::WHITE)?;
+		encoder.image(24.0, 40.0, 32.0, 20.0, &quadrants, Color::rgba(255, 255, 255, 160))?;
+		encoder.image(20.0, 52.0, 40.0, 8.0, &ramp.with_uv(1.0, 0.0, -1.0, 1.0), Color::WHITE)?;
+		Ok(())
+	});
+}
+
+/// 8x8 button skin: a 2 texel dark frame with cut corners around a
+/// light fill.
+fn button() -> Texels {
+	let mut pixels = Vec::new();
+	for y in 0..8i32 {
+		for x in 0..8i32 {
+			let edge   = x.min(7 - x).min(y.min(7 - y));
+			let corner = x.min(7 - x) + y.min(7 - y);
+			let texel = match (corner, edge) {
+				(0, _) => [0, 0, 0, 0],
+				(_, 0 | 1) => [40, 40, 90, 255],
+				_ => [200, 200, 240, 255],
+			};
+			pixels.extend_from_slice(&texel);
+		}
+	}
+	(8, 8, pixels)
+}
+
+#[test]
+fn nine_slice() {
+	check_textured("nine_slice", &[button()], |encoder, textures| {
+		let button = Image::new(textures[0]);
+		let insets = Insets::all(3.0);
+		encoder.nine_slice(4.0, 4.0, 8.0, 8.0, &button, insets, Color::WHITE)?;
+		encoder.nine_slice(16.0, 4.0, 44.0, 12.0, &button, insets, Color::WHITE)?;
+		encoder.nine_slice(4.0, 20.0, 12.0, 40.0, &button, insets, ORANGE)?;
+		encoder.nine_slice(20.0, 20.0, 40.0, 24.0, &button, Insets::new(2.0, 2.0, 3.0, 2.0), Color::WHITE)?;
+		// Insets wider than the rect shrink.
+		encoder.nine_slice(20.0, 48.0, 4.0, 4.0, &button, insets, Color::WHITE)?;
+		encoder.nine_slice(28.5, 48.5, 30.0, 10.0, &button, insets, Color::rgba(255, 255, 255, 160))?;
+		Ok(())
+	});
+}
+
+/// 5x7 pixel glyphs, enough to spell a few words.
+const PIXEL_GLYPHS: [(char, [&str; 7]); 5] = [
+	('A', [".###.", "#...#", "#...#", "#####", "#...#", "#...#", "#...#"]),
+	('I', ["#####", "..#..", "..#..", "..#..", "..#..", "..#..", "#####"]),
+	('M', ["#...#", "##.##", "#.#.#", "#.#.#", "#...#", "#...#", "#...#"]),
+	('P', ["####.", "#...#", "#...#", "####.", "#....", "#....", "#...."]),
+	('R', ["####.", "#...#", "#...#", "####.", "#.#..", "#..#.", "#...#"]),
+];
+
+fn pixel_atlas() -> (GlyphAtlas, Vec<(char, Glyph)>) {
+	let mut atlas  = GlyphAtlas::new(64, 16);
+	let mut glyphs = vec![(' ', Glyph { rect: [0; 4], offset: [0.0, 0.0], advance: 4.0 })];
+	for (c, rows) in PIXEL_GLYPHS {
+		let coverage: Vec<u8> = rows
+			.iter()
+			.flat_map(|row| row.bytes().map(|b| if b == b'#' { 255 } else { 0 }))
+			.collect();
+		let rect = atlas.insert(5, 7, &coverage).expect("Pixel glyphs don't fit.");
+		glyphs.push((c, Glyph { rect, offset: [0.0, -7.0], advance: 6.0 }));
+	}
+	(atlas, glyphs)
+}
+
+fn pixel_font(texture: TextureId) -> Font {
+	let (atlas, glyphs) = pixel_atlas();
+	let mut font = Font::new(texture, (atlas.width, atlas.height), 7.0, LineMetrics::new(7.0, -1.0, 1.0));
+	for (c, glyph) in glyphs {
+		font.add_glyph(c, glyph);
+	}
+	font
+}
+
+#[test]
+fn text() {
+	let (atlas, _) = pixel_atlas();
+	check_textured("text", &[(atlas.width, atlas.height, atlas.pixels)], |encoder, textures| {
+		let font = pixel_font(textures[0]);
+		encoder.text([2.0, 2.0], "PRIMA", &font, 7.0, Color::WHITE)?;
+		encoder.text([34.5, 2.25], "AIM\nRIP", &font, 7.0, Color::BLACK)?;
+		encoder.text([2.0, 22.0], "PRIMA", &font, 14.0, ORANGE)?;
+
+		let stops = [ColorStop::new(0.0, Color::rgb(220, 40, 40)), ColorStop::new(1.0, Color::rgb(40, 40, 220))];
+		let paint = encoder.gradient(&Gradient::Linear { start: [2.0, 0.0], end: [62.0, 0.0], stops: &stops })?;
+		encoder.text([2.0, 42.0], "RAMP", &font, 14.0, paint)?;
+		Ok(())
+	});
+}
+
+/// Real outlines, kerned: "AV" & "To" tuck into each other.
+#[test]
+fn font() {
+	let face = dejavu();
+	let mut atlas = GlyphAtlas::new(256, 128);
+	let small = face.rasterize_glyphs(&mut atlas, 11.0, ' '..='~').unwrap();
+	let large = face.rasterize_glyphs(&mut atlas, 24.0, "AVTo".chars()).unwrap();
+
+	check_textured("font", &[(atlas.width, atlas.height, atlas.pixels.clone())], |encoder, textures| {
+		let small = face.font(textures[0], &atlas, &small);
+		let large = face.font(textures[0], &atlas, &large);
+		encoder.text([2.0, 1.0], "Prima UI\nfont 11px", &small, 11.0, Color::WHITE)?;
+		encoder.text([2.0, 28.0], "AVTo", &large, 24.0, ORANGE)?;
+		Ok(())
+	});
+}
+
+/// Small glyphs scaled way up stay crisp, the multi-channel ones
+/// keep their corners too.
+#[test]
+fn font_distance_field() {
+	let face = dejavu();
+	let mut atlas = GlyphAtlas::new(128, 64);
+	let sdf  = face.rasterize_glyphs_as(&mut atlas, 16.0, GlyphFormat::Sdf { range: 2.0 }, "Fk".chars()).unwrap();
+	let msdf = face.rasterize_glyphs_as(&mut atlas, 16.0, GlyphFormat::Msdf { range: 2.0 }, "Fk".chars()).unwrap();
+
+	check_textured("font_distance_field", &[(atlas.width, atlas.height, atlas.pixels.clone())], |encoder, textures| {
+		let sdf  = face.font(textures[0], &atlas, &sdf);
+		let msdf = face.font(textures[0], &atlas, &msdf);
+		encoder.text([2.0, -4.0], "Fk", &sdf, 36.0, Color::WHITE)?;
+		encoder.text([2.0, 26.0], "Fk", &msdf, 36.0, ORANGE)?;
+		Ok(())
+	});
+}
+
+/// Wrapped & justified, centered, right aligned & cut off lines
+/// within the lighter frame.
+#[test]
+fn text_layout() {
+	let face = dejavu();
+	let mut atlas = GlyphAtlas::new(256, 128);
+	let glyphs = face.rasterize_glyphs(&mut atlas, 9.0, ' '..='~').unwrap();
+
+	check_textured("text_layout", &[(atlas.width, atlas.height, atlas.pixels.clone())], |encoder, textures| {
+		let font   = face.font(textures[0], &atlas, &glyphs);
+		let layout = Layout::new(9.0).with_max_width(56.0);
+
+		encoder.rect(4.0, 2.0, 56.0, 60.0, Color::rgba(255, 255, 255, 40))?;
+
+		let text = layout.with_align(Align::Justify).layout("Lines wrap at the spaces.", &font);
+		encoder.text_layout([4.0, 2.0], &text, &font, Color::WHITE)?;
+
+		let center = layout.with_align(Align::Center).layout("centered", &font);
+		encoder.text_layout([4.0, 2.0 + text.height], &center, &font, ORANGE)?;
+
+		let right = layout.with_align(Align::Right).layout("right", &font);
+		encoder.text_layout([4.0, 2.0 + text.height + center.height], &right, &font, ORANGE)?;
+
+		let cut = layout.with_max_lines(1).layout("Cut off, as it is too long", &font);
+		encoder.text_layout([4.0, 2.0 + text.height + center.height + right.height], &cut, &font, Color::BLACK)?;
+		Ok(())
+	});
+}
+
+/// The same word a quarter pixel further right on every line, the
+/// glyphs land between the pixels rather than on them.
+#[test]
+fn glyph_cache() {
+	let face = dejavu();
+	let mut cache = GlyphCache::new(128, 64);
+	let face = cache.add_face(face);
+
+	let mut quads = Vec::new();
+	let layout = Layout::new(11.0).layout("Illicit", cache.face(face));
+	for i in 0..4 {
+		quads.extend(cache.quads(face, [4.0 + i as f32 * 0.25, 2.0 + i as f32 * 11.0], &layout).unwrap());
+	}
+	let large = Layout::new(16.0).layout("Cache", cache.face(face));
+	quads.extend(cache.quads(face, [4.0, 44.0], &large).unwrap());
+
+	let (width, height) = cache.size();
+	check_textured("glyph_cache", &[(width, height, cache.pixels().to_vec())], |encoder, textures| {
+		encoder.glyphs(textures[0], &quads, Color::WHITE)?;
+		Ok(())
+	});
+}
+
+#[test]
+fn triangle() {
+	check("triangle", |encoder| {
+		encoder.triangle(
+			Vertex::new(32.0,  8.0, Color::rgb(255, 0, 0)),
+			Vertex::new(8.0,  56.0, Color::rgb(0, 255, 0)),
+			Vertex::new(56.0, 56.0, Color::rgb(0, 0, 255)),
+		)?;
+		Ok(())
+	});
+}
+
+#[test]
+fn triangle_windings() {
+	check("triangle_windings", |encoder| {
+		let c = Color::rgb(240, 240, 240);
+		encoder.triangle(Vertex::new(4.0, 4.0, c), Vertex::new(28.0, 4.0, c), Vertex::new(4.0, 28.0, c))?;
+		encoder.triangle(Vertex::new(36.0, 36.0, c), Vertex::new(36.0, 60.0, c), Vertex::new(60.0, 36.0, c))?;
+		Ok(())
+	});
+}
+
+/// Shared edges must neither leave gaps nor overlap.
+#[test]
+fn triangle_fan() {
+	check("triangle_fan", |encoder| {
+		let center = (32.0, 32.0);
+		let n = 12;
+		for i in 0..n {
+			let a0 = i as f32 / n as f32 * std::f32::consts::TAU;
+			let a1 = (i + 1) as f32 / n as f32 * std::f32::consts::TAU;
+			let c  = if i % 2 == 0 { Color::rgb(200, 60, 60) } else { Color::rgb(60, 60, 200) };
+			encoder.triangle(
+				Vertex::new(center.0, center.1, c),
+				Vertex::new(center.0 + a0.cos() * 28.0, center.1 + a0.sin() * 28.0, c),
+				Vertex::new(center.0 + a1.cos() * 28.0, center.1 + a1.sin() * 28.0, c),
+			)?;
+		}
+		Ok(())
+	});
+}
+
+/// The scene `fill_prima_buffers` used to hardcode.
+#[test]
+fn demo() {
+	check("demo", |encoder| {
+		let (w, h) = (WIDTH as f32, HEIGHT as f32);
+		encoder.rect(4.0, 12.0, 20.0, 12.0, ORANGE)?;
+		encoder.triangle(
+			Vertex::new(w * 0.5,  h * 0.25, Color::rgb(255, 0, 0)),
+			Vertex::new(w * 0.25, h * 0.75, Color::rgb(0, 255, 0)),
+			Vertex::new(w * 0.75, h * 0.75, Color::rgb(0, 0, 255)),
+		)?;
+		Ok(())
+	});
+}
+
+/// Interleaved primitive types, so the offsets in the indices are
+/// all over the place and drawing order matters.
+#[test]
+fn interleaved() {
+	check("interleaved", |encoder| {
+		for i in 0..8 {
+			let x = i as f32 * 7.0;
+			let c = Color::rgb(30 * i as u8, 255 - 30 * i as u8, 128);
+			encoder.rect(x, x, 12.0, 12.0, ORANGE)?;
+			encoder.triangle(
+				Vertex::new(x + 6.0,  x + 2.0,  c),
+				Vertex::new(x + 14.0, x + 14.0, c),
+				Vertex::new(x + 2.0,  x + 10.0, c),
+			)?;
+		}
+		Ok(())
+	});
+}
+
+fn check(name: &str, scene: Scene) {
+	check_textured(name, &[], |encoder, _| scene(encoder));
+}
+
+/// Uploads `textures` to every backend, the scene gets their ids.
+fn check_textured<F>(name: &str, textures: &[Texels], scene: F)
+where
+	F: Fn(&mut PrimaEncoder, &[TextureId]) -> prima::Result<()>,
+{
+	let reference_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+		.join("tests")
+		.join("golden")
+		.join(format!("{name}.png"));
+
+	let mut software = Software::new(WIDTH, HEIGHT);
+	let ids: Vec<_> = textures
+		.iter()
+		.map(|(w, h, pixels)| software.upload_texture(*w, *h, pixels).expect("Failed to upload a texture."))
+		.collect();
+	let software = software
+		.render(|encoder| scene(encoder, &ids))
+		.expect("Failed to render on the CPU.");
+
+	if env::var_os("PRIMA_BLESS").is_some() {
+		headless::write_png(&reference_path, WIDTH, HEIGHT, &software).expect("Failed to write the reference.");
+	}
+
+	let reference = read_png(&reference_path)
+		.unwrap_or_else(|e| panic!("Failed to read {}: {e}, run with PRIMA_BLESS=1 to create it.", reference_path.display()));
+
+	let mut failures = Vec::new();
+	let mut skipped  = None;
+
+	failures.extend(compare(name, "cpu", &reference, &software));
+
+	match Headless::new(WIDTH, HEIGHT) {
+		Ok(mut gpu) => {
+			let ids: Vec<_> = textures
+				.iter()
+				.map(|(w, h, pixels)| gpu.upload_texture(*w, *h, pixels).expect("Failed to upload a texture."))
+				.collect();
+			let pixels = gpu.render(|encoder| scene(encoder, &ids)).expect("Failed to render on the GPU.");
+			failures.extend(compare(name, "gpu", &reference, &pixels));
+		},
+		Err(e) if env::var_os("PRIMA_REQUIRE_GPU").is_some() => {
+			failures.push(format!("{name} (gpu): failed to initialize Vulkan due to {e}, PRIMA_REQUIRE_GPU is set"));
+		},
+		Err(e) => skipped = Some(format!("{name} (gpu): skipped, failed to initialize Vulkan due to {e}")),
+	}
+
+	// The skipped GPU goes along, as the CPU is only compared against itself then.
+	failures.extend(skipped.filter(|_| !failures.is_empty()));
+	assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+/// Returns a failure message & writes the actual and diff images,
+/// if `actual` doesn't match `reference`.
+fn compare(name: &str, backend: &str, reference: &[u8], actual: &[u8]) -> Option<String> {
+	assert_eq!(reference.len(), actual.len());
+
+	let mut mismatches = 0;
+	let mut diff       = Vec::with_capacity(reference.len());
+	for (r, a) in reference.chunks_exact(4).zip(actual.chunks_exact(4)) {
+		let bad = r.iter().zip(a).any(|(r, a)| r.abs_diff(*a) > TOLERANCE);
+		if bad {
+			mismatches += 1;
+			diff.extend_from_slice(&[255, 0, 255, 255]);
+		} else {
+			// Faded reference, so it is clear where things are.
+			let luma = (r[0] as u32 * 3 + r[1] as u32 * 6 + r[2] as u32) / 10;
+			let luma = (luma / 3) as u8;
+			diff.extend_from_slice(&[luma, luma, luma, 255]);
+		}
+	}
+
+	if mismatches == 0 {
+		return None;
+	}
+
+	let out = out_dir();
+	let actual_path = out.join(format!("{name}.{backend}.png"));
+	let diff_path   = out.join(format!("{name}.{backend}.diff.png"));
+	headless::write_png(&actual_path, WIDTH, HEIGHT, actual).expect("Failed to write the actual image.");
+	headless::write_png(&diff_path, WIDTH, HEIGHT, &diff).expect("Failed to write the diff image.");
+
+	Some(format!(
+		"{name} ({backend}): {mismatches} pixels differ by more than {TOLERANCE}, see {} and {}",
+		actual_path.display(),
+		diff_path.display(),
+	))
+}
+
+fn out_dir() -> PathBuf {
+	let out = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden");
+	fs::create_dir_all(&out).expect("Failed to create the output directory.");
+	out
+}
+
+/// Printable ASCII of DejaVu Sans with its kerning, see
+/// tests/fonts/README.md.
+fn dejavu() -> Face {
+	Face::from_file(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fonts/DejaVuSans-Subset.ttf")).unwrap()
+}
+
+fn read_png(path: &Path) -> prima::Result<Vec<u8>> {
+	let file = fs::File::open(path)?;
+
+	let decoder    = png::Decoder::new(BufReader::new(file));
+	let mut reader = decoder.read_info()?;
+
+	let mut pixels = vec![0; reader.output_buffer_size()];
+	let info       = reader.next_frame(&mut pixels)?;
+	pixels.truncate(info.buffer_size());
+
+	let expected = (png::ColorType::Rgba, png::BitDepth::Eight, WIDTH, HEIGHT);
+	if (info.color_type, info.bit_depth, info.width, info.height) != expected {
+		return Err(format!("{} is not a {WIDTH}x{HEIGHT} RGBA8 image", path.display()).into());
+	}
+
+	Ok(pixels)
+}